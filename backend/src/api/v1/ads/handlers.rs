@@ -1,28 +1,49 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
 
+use actix_multipart::Multipart;
 use actix_web::{HttpResponse, web};
-use futures::future::try_join_all;
+use chrono::Utc;
+use futures::{StreamExt, future::try_join_all};
+use log::{error, warn};
 use serde_json::json;
 
 use crate::{
-    config::AppConfig,
-    database::JsonDatabase,
-    services::{openai::OpenAiClient, telegram::TelegramService},
+    api::v1::telegram::{SESSION_EXPIRED_CODE, session_expired_response},
+    database::{
+        ChannelRepository, RepositoryError,
+        models::{AdRecord, ChannelData, ChannelFilter, MediaRecord},
+    },
+    services::{
+        campaign::{self, AdSubmitter, DEFAULT_PLAN_DELAY_MS, PlanOptions},
+        openai::OpenAiClient,
+        telegram::TelegramService,
+    },
     utils::text::TextUtils,
 };
 
-use super::models::{CreateAdRequest, GenerateAdMessageRequest};
+use super::models::{
+    AdSplitMode, AdTarget, AdTargetType, BulkAdsQuery, CreateAdRequest, GenerateAdMessageRequest,
+    ListAdsQuery, MEDIA_CONTENT_TYPES, MEDIA_MAX_SIZE, PLAN_MAX_ROWS, SplitAdResult,
+    UpdateAdRequest,
+};
 
 pub async fn generate_ad_message(
-    db: web::Data<JsonDatabase>,
+    db: web::Data<dyn ChannelRepository>,
     req: web::Json<GenerateAdMessageRequest>,
-    config: web::Data<AppConfig>,
+    openai_service: web::Data<OpenAiClient>,
 ) -> HttpResponse {
-    let openai_service = OpenAiClient::new(&config.openai.model, &config.openai.api_key);
     let product_description = &req.description;
-    let username_to_description: HashMap<String, String> = db
-        .filter_channels(None, None)
-        .await
+    let channels = match db.filter_channels(&ChannelFilter::default()).await {
+        Ok(page) => page.channels,
+        Err(e) => {
+            error!("Failed to load channel descriptions: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to load channels");
+        }
+    };
+    let username_to_description: HashMap<String, String> = channels
         .iter()
         .filter_map(|c| c.description.clone().map(|desc| (c.username.clone(), desc)))
         .collect();
@@ -33,11 +54,11 @@ pub async fn generate_ad_message(
         .collect::<Vec<String>>();
     let found_descriptions: Vec<String> = channels_names
         .iter()
-        .filter_map(|c| username_to_description.get(c).map(|desc| desc.clone()))
+        .filter_map(|c| username_to_description.get(c).cloned())
         .collect();
 
     match openai_service
-        .create_ad_message(&found_descriptions.join(", "), &product_description)
+        .create_ad_message(&found_descriptions.join(", "), product_description)
         .await
     {
         Ok(result) => HttpResponse::Ok().json(json!({"ad_message": result})),
@@ -45,64 +66,985 @@ pub async fn generate_ad_message(
     }
 }
 
-pub async fn create_ad(
-    db: web::Data<JsonDatabase>,
-    req: web::Json<CreateAdRequest>,
+/// Loads the ad's target channels, either from `channels` usernames or from
+/// the saved list named by `list_id`.
+async fn resolve_channels(
+    db: &web::Data<dyn ChannelRepository>,
+    req: &CreateAdRequest,
+) -> Result<Vec<ChannelData>, HttpResponse> {
+    let channels_ids_result = match req.list_id {
+        Some(_) if !req.channels.is_empty() => {
+            return Err(HttpResponse::BadRequest().json(json!({
+                "error": "Provide either 'channels' or 'list_id', not both"
+            })));
+        }
+        Some(list_id) => match db.get_saved_list(list_id).await {
+            Ok(Some(list)) => {
+                let channel_id_futures: Vec<_> = list
+                    .channel_ids
+                    .iter()
+                    .map(|id| {
+                        let db_clone = db.clone();
+                        let id = *id;
+                        async move {
+                            match db_clone.get_channel_by_id(id).await {
+                                Ok(Some(channel_data)) => Ok(channel_data),
+                                Ok(None) => Err(RepositoryError::NotFound(format!(
+                                    "Channel with id {} not found.",
+                                    id
+                                ))),
+                                Err(db_error) => Err(RepositoryError::Storage(format!(
+                                    "Database error for channel {}: {}",
+                                    id, db_error
+                                ))),
+                            }
+                        }
+                    })
+                    .collect();
+                try_join_all(channel_id_futures).await
+            }
+            Ok(None) => Err(RepositoryError::NotFound(format!(
+                "Saved list with id {} not found.",
+                list_id
+            ))),
+            Err(db_error) => Err(RepositoryError::Storage(format!(
+                "Database error for saved list {}: {}",
+                list_id, db_error
+            ))),
+        },
+        None => {
+            let channel_id_futures: Vec<_> = req
+                .channels
+                .iter()
+                .map(|username_str| {
+                    let db_clone = db.clone();
+                    let username_owned = username_str.clone();
+                    async move {
+                        match db_clone.get_channel_by_username(&username_owned).await {
+                            Ok(Some(channel_data)) => Ok(channel_data),
+                            Ok(None) => Err(RepositoryError::NotFound(format!(
+                                "Channel with username '{}' not found.",
+                                username_owned
+                            ))),
+                            Err(db_error) => Err(RepositoryError::Storage(format!(
+                                "Database error for channel '{}': {}",
+                                username_owned, db_error
+                            ))),
+                        }
+                    }
+                })
+                .collect();
+            try_join_all(channel_id_futures).await
+        }
+    };
+
+    channels_ids_result.map_err(|error| match error {
+        RepositoryError::NotFound(e) => HttpResponse::BadRequest().json(json!({"error": e})),
+        e => HttpResponse::InternalServerError().json(json!({"error": e.to_string()})),
+    })
+}
+
+/// Refuses ads that target any blacklisted channel, naming every offender.
+async fn check_blacklist(
+    db: &web::Data<dyn ChannelRepository>,
+    channels: &[ChannelData],
+) -> Result<(), HttpResponse> {
+    let blacklist = db
+        .list_blacklist()
+        .await
+        .map_err(|e| HttpResponse::InternalServerError().json(json!({"error": e})))?;
+
+    let mut offenders = vec![];
+    for channel in channels {
+        if let Some(entry) = blacklist
+            .iter()
+            .find(|entry| entry.matches(channel.id, Some(&channel.username)))
+        {
+            offenders.push(json!({
+                "id": channel.id,
+                "username": channel.username,
+                "reason": entry.reason,
+            }));
+        }
+    }
+
+    if offenders.is_empty() {
+        Ok(())
+    } else {
+        Err(HttpResponse::BadRequest().json(json!({
+            "field": "channels",
+            "error": "Ad includes blacklisted channels",
+            "blacklisted": offenders,
+        })))
+    }
+}
+
+fn target_error(field: &str, error: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({"field": field, "error": error}))
+}
+
+/// Response for a failed ads platform call: 400 for a validation error, 401
+/// for an expired session, 500 otherwise.
+fn telegram_error(error_message: String) -> HttpResponse {
+    if let Some((field, msg)) = TextUtils::parse_validation_error(&error_message) {
+        target_error(&field, &msg)
+    } else if TextUtils::is_session_expired_error(&error_message) {
+        session_expired_response(&error_message)
+    } else {
+        HttpResponse::InternalServerError().json(json!({"error": error_message}))
+    }
+}
+
+/// Checks that the request fills exactly the fields its `target_type` uses.
+fn validate_target(req: &CreateAdRequest) -> Result<(), HttpResponse> {
+    let uses_channels = !req.channels.is_empty() || req.list_id.is_some();
+    let uses_bots = !req.bots.is_empty();
+    let uses_search = !req.search_queries.is_empty();
+
+    let (field, used, unexpected) = match req.target_type {
+        AdTargetType::Channel => ("channels", uses_channels, uses_bots || uses_search),
+        AdTargetType::Bot => ("bots", uses_bots, uses_channels || uses_search),
+        AdTargetType::Search => ("search_queries", uses_search, uses_channels || uses_bots),
+    };
+    let target_type = req.target_type.as_str();
+
+    if unexpected {
+        return Err(target_error(
+            "target_type",
+            &format!(
+                "Only '{}' can be set for target type '{}'",
+                field, target_type
+            ),
+        ));
+    }
+    if !used {
+        return Err(target_error(
+            field,
+            &format!(
+                "At least one target is required for target type '{}'",
+                target_type
+            ),
+        ));
+    }
+
+    if req
+        .search_queries
+        .iter()
+        .any(|q| q.trim().is_empty() || q.contains(';'))
+    {
+        return Err(target_error(
+            "search_queries",
+            "Search queries must not be empty or contain ';'",
+        ));
+    }
+    Ok(())
+}
+
+fn unique_values(values: impl Iterator<Item = String>) -> Vec<String> {
+    let mut unique: Vec<String> = vec![];
+    for value in values {
+        if !value.is_empty() && !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    unique
+}
+
+/// Resolves the request's target, along with the loaded channels for a
+/// channel target (empty for bots and search queries).
+async fn resolve_target(
+    db: &web::Data<dyn ChannelRepository>,
+    telegram_service: &TelegramService,
+    req: &CreateAdRequest,
+) -> Result<(AdTarget, Vec<ChannelData>), HttpResponse> {
+    validate_target(req)?;
+
+    match req.target_type {
+        AdTargetType::Channel => {
+            let channels = resolve_channels(db, req).await?;
+            check_blacklist(db, &channels).await?;
+            let channel_ids = channels.iter().map(|c| c.id).collect();
+            Ok((AdTarget::Channels(channel_ids), channels))
+        }
+        AdTargetType::Bot => {
+            let usernames = unique_values(req.bots.iter().map(|b| TextUtils::normalize_name(b)));
+            telegram_service
+                .resolve_bot_ids(&usernames)
+                .await
+                .map(|ids| (AdTarget::Bots(ids), vec![]))
+                .map_err(telegram_error)
+        }
+        AdTargetType::Search => Ok((
+            AdTarget::SearchQueries(unique_values(
+                req.search_queries.iter().map(|q| q.trim().to_string()),
+            )),
+            vec![],
+        )),
+    }
+}
+
+/// Rejects a `media` id that was never uploaded through `POST /ads/media`.
+async fn check_media(
+    db: &web::Data<dyn ChannelRepository>,
+    req: &CreateAdRequest,
+) -> Result<(), HttpResponse> {
+    let Some(media_id) = req.media.as_deref().filter(|m| !m.is_empty()) else {
+        return Ok(());
+    };
+    match db.get_media(media_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(target_error(
+            "media",
+            &format!("Media '{}' not found, upload it first", media_id),
+        )),
+        Err(e) => Err(HttpResponse::InternalServerError().json(json!({"error": e}))),
+    }
+}
+
+/// Creates one ad per row of a CSV or JSON campaign plan; see
+/// `campaign::run_plan`. The report lists the outcome of every row; plans
+/// over `PLAN_MAX_ROWS` rows are refused.
+pub async fn create_ads_from_plan(
+    query: web::Query<BulkAdsQuery>,
+    db: web::Data<dyn ChannelRepository>,
     telegram_service: web::Data<TelegramService>,
+    body: web::Bytes,
 ) -> HttpResponse {
-    let channel_id_futures: Vec<_> = req
-        .channels
-        .iter()
-        .map(|username_str| {
-            let db_clone = db.clone();
-            let username_owned = username_str.clone();
-            async move {
-                match db_clone.get_channel_by_username(&username_owned).await {
-                    Ok(Some(channel_data)) => Ok(channel_data.id),
-                    Ok(None) => Err(format!(
-                        "Channel with username '{}' not found.",
-                        username_owned
-                    )),
-                    Err(db_error) => Err(format!(
-                        "Database error for channel '{}': {}",
-                        username_owned, db_error
-                    )),
+    let records = match campaign::parse_plan(&body, query.format) {
+        Ok(records) => records,
+        Err(e) => return HttpResponse::BadRequest().json(json!({"error": e})),
+    };
+    if records.len() > PLAN_MAX_ROWS {
+        return HttpResponse::BadRequest().json(json!({
+            "error": format!(
+                "Plan has {} rows, at most {} are accepted; use the campaign command for larger plans",
+                records.len(),
+                PLAN_MAX_ROWS
+            )
+        }));
+    }
+    let options = PlanOptions {
+        dry_run: query.dry_run,
+        delay: Duration::from_millis(query.delay_ms.unwrap_or(DEFAULT_PLAN_DELAY_MS)),
+    };
+
+    match campaign::run_plan(db.get_ref(), telegram_service.get_ref(), records, &options).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e})),
+    }
+}
+
+/// Lists the account's ads with their statistics. Ads deleted on Telegram's
+/// side stay in the list with `removed_at` set.
+pub async fn list_ads(
+    db: web::Data<dyn ChannelRepository>,
+    query: web::Query<ListAdsQuery>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    let result = if query.cached {
+        db.list_ads().await
+    } else {
+        telegram_service.sync_ads(db.clone()).await
+    };
+
+    match result {
+        Ok(ads) => HttpResponse::Ok().json(ads),
+        Err(e) if query.cached => HttpResponse::InternalServerError().json(json!({"error": e})),
+        Err(e) if TextUtils::is_session_expired_error(&e) => session_expired_response(&e),
+        Err(e) => HttpResponse::BadGateway().json(json!({"error": e})),
+    }
+}
+
+pub async fn list_media(db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    match db.list_media().await {
+        Ok(media) => HttpResponse::Ok().json(media),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e})),
+    }
+}
+
+/// Takes a multipart form with the file in its `file` field, uploads it to
+/// the ads platform and keeps the returned media id.
+pub async fn upload_media(
+    db: web::Data<dyn ChannelRepository>,
+    telegram_service: web::Data<TelegramService>,
+    mut payload: Multipart,
+) -> HttpResponse {
+    let mut upload = None;
+
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(e) => return target_error("file", &e.to_string()),
+        };
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .unwrap_or("upload")
+            .to_string();
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.essence_str().to_string())
+            .unwrap_or_default();
+        if !MEDIA_CONTENT_TYPES.contains(&content_type.as_str()) {
+            return target_error(
+                "file",
+                &format!(
+                    "Unsupported media type '{}', expected one of: {}",
+                    content_type,
+                    MEDIA_CONTENT_TYPES.join(", ")
+                ),
+            );
+        }
+
+        let mut bytes = vec![];
+        while let Some(chunk) = field.next().await {
+            match chunk {
+                Ok(chunk) if bytes.len() + chunk.len() <= MEDIA_MAX_SIZE => {
+                    bytes.extend_from_slice(&chunk)
                 }
+                Ok(_) => {
+                    return target_error(
+                        "file",
+                        &format!("File is larger than {} bytes", MEDIA_MAX_SIZE),
+                    );
+                }
+                Err(e) => return target_error("file", &e.to_string()),
             }
-        })
-        .collect();
+        }
+        upload = Some((file_name, content_type, bytes));
+        break;
+    }
 
-    let channels_ids_result = try_join_all(channel_id_futures).await;
+    let Some((file_name, content_type, bytes)) = upload else {
+        return target_error("file", "Missing 'file' field");
+    };
+    if bytes.is_empty() {
+        return target_error("file", "File is empty");
+    }
 
-    match channels_ids_result {
-        Ok(actual_channel_ids) => {
-            match telegram_service
-                .create_ad(req.into_inner(), actual_channel_ids)
-                .await
-            {
-                Ok(message) => {
-                    HttpResponse::Ok().json(json!({ "status": "success", "message": message }))
+    let size = bytes.len() as i64;
+    let media_id = match telegram_service
+        .upload_media(&file_name, &content_type, bytes)
+        .await
+    {
+        Ok(media_id) => media_id,
+        Err(error_message) => return telegram_error(error_message),
+    };
+
+    let record = MediaRecord {
+        id: media_id,
+        file_name,
+        content_type,
+        size,
+        uploaded_at: Utc::now(),
+    };
+    match db.save_media(record.clone()).await {
+        Ok(()) => HttpResponse::Created().json(record),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": e})),
+    }
+}
+
+fn validate_split(req: &CreateAdRequest) -> Result<(), HttpResponse> {
+    match req.split_by {
+        Some(_) if req.target_type != AdTargetType::Channel => Err(target_error(
+            "split_by",
+            "Splitting is only supported for target type 'channel'",
+        )),
+        Some(AdSplitMode::Chunk) if req.chunk_size.unwrap_or(0) == 0 => Err(target_error(
+            "chunk_size",
+            "Must be at least 1 when splitting by chunk",
+        )),
+        Some(AdSplitMode::Category | AdSplitMode::Geo) | None if req.chunk_size.is_some() => Err(
+            target_error("chunk_size", "Only used with split_by 'chunk'"),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Groups the target channels into one list per ad. Category and geo groups
+/// are ordered by name, with channels lacking the value in their own group;
+/// chunks keep the request's channel order.
+fn group_channels(
+    channels: &[ChannelData],
+    split_by: AdSplitMode,
+    chunk_size: Option<usize>,
+) -> Vec<(String, Vec<i64>)> {
+    if split_by == AdSplitMode::Chunk {
+        let chunks: Vec<&[ChannelData]> = channels.chunks(chunk_size.unwrap_or(1)).collect();
+        let total = chunks.len();
+        return chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                (
+                    format!("{}/{}", index + 1, total),
+                    chunk.iter().map(|c| c.id).collect(),
+                )
+            })
+            .collect();
+    }
+
+    let mut groups: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for channel in channels {
+        let value = match split_by {
+            AdSplitMode::Category => channel.category.clone(),
+            _ => channel.geo.clone(),
+        };
+        let group = match value.filter(|v| !v.trim().is_empty()) {
+            Some(value) => value,
+            None if split_by == AdSplitMode::Category => "no category".to_string(),
+            None => "no geo".to_string(),
+        };
+        groups.entry(group).or_default().push(channel.id);
+    }
+    groups.into_iter().collect()
+}
+
+/// Creates the ads one after another, so a failed group doesn't stop the
+/// rest; after an expired session the remaining groups are only listed as
+/// skipped. Responds 200 when at least one ad was created.
+async fn create_split_ads(
+    submitter: &(impl AdSubmitter + ?Sized),
+    req: CreateAdRequest,
+    groups: Vec<(String, Vec<i64>)>,
+) -> HttpResponse {
+    let mut results = vec![];
+    let mut session_expired = false;
+    for (group, channel_ids) in groups {
+        let mut ad = req.clone();
+        let title = req.title_for_group(&group);
+        ad.title = Some(title.clone());
+
+        let mut result = SplitAdResult {
+            group,
+            title,
+            channels: channel_ids.clone(),
+            message: None,
+            field: None,
+            error: None,
+            skipped: session_expired,
+        };
+        if session_expired {
+            results.push(result);
+            continue;
+        }
+
+        match submitter
+            .create_ad(ad, AdTarget::Channels(channel_ids))
+            .await
+        {
+            Ok(message) => result.message = Some(message),
+            Err(error_message) => match TextUtils::parse_validation_error(&error_message) {
+                Some((field, msg)) => {
+                    result.field = Some(field);
+                    result.error = Some(msg);
                 }
-                Err(error_message) => {
-                    if let Some((field, msg)) = TextUtils::parse_validation_error(&error_message) {
-                        HttpResponse::BadRequest().json(json!({
-                            "field": field,
-                            "error": msg
-                        }))
-                    } else {
-                        HttpResponse::InternalServerError().json(json!({
-                            "error": error_message
-                        }))
-                    }
+                None => {
+                    session_expired = TextUtils::is_session_expired_error(&error_message);
+                    result.error = Some(error_message);
                 }
+            },
+        }
+        results.push(result);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|r| r.error.is_some() || r.skipped)
+        .count();
+    let status = match failed {
+        0 => "success",
+        n if n < results.len() => "partial",
+        _ => "failed",
+    };
+    let mut body = json!({ "status": status, "ads": results });
+    if session_expired {
+        body["code"] = json!(SESSION_EXPIRED_CODE);
+    }
+
+    if failed < results.len() {
+        HttpResponse::Ok().json(body)
+    } else if results.iter().all(|r| r.field.is_some()) {
+        HttpResponse::BadRequest().json(body)
+    } else if session_expired {
+        HttpResponse::Unauthorized().json(body)
+    } else {
+        HttpResponse::InternalServerError().json(body)
+    }
+}
+
+pub async fn create_ad(
+    db: web::Data<dyn ChannelRepository>,
+    req: web::Json<CreateAdRequest>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    if let Err((field, error)) = req.validate_lengths() {
+        return target_error(field, &error);
+    }
+    if let Err(response) = check_media(&db, &req).await {
+        return response;
+    }
+    if let Err(response) = validate_split(&req) {
+        return response;
+    }
+
+    let (target, channels) = match resolve_target(&db, &telegram_service, &req).await {
+        Ok(resolved) => resolved,
+        Err(response) => return response,
+    };
+
+    if let (Some(split_by), AdTarget::Channels(_)) = (req.split_by, &target) {
+        let groups = group_channels(&channels, split_by, req.chunk_size);
+        return create_split_ads(telegram_service.get_ref(), req.into_inner(), groups).await;
+    }
+
+    match telegram_service.create_ad(req.into_inner(), target).await {
+        Ok(message) => HttpResponse::Ok().json(json!({ "status": "success", "message": message })),
+        Err(error_message) => telegram_error(error_message),
+    }
+}
+
+fn ad_action_response(result: Result<Option<String>, String>, message: String) -> HttpResponse {
+    match result {
+        Ok(ad_status) => HttpResponse::Ok().json(json!({
+            "status": "success",
+            "message": message,
+            "ad_status": ad_status,
+        })),
+        Err(error_message) => telegram_error(error_message),
+    }
+}
+
+/// Applies what a successful call changed to the stored copy of the ad, if
+/// one has been synced; the next `GET /ads` refreshes the rest.
+async fn update_stored_ad(
+    db: &web::Data<dyn ChannelRepository>,
+    ad_id: i64,
+    update: impl FnOnce(&mut AdRecord),
+) {
+    let stored = match db.get_ad(ad_id).await {
+        Ok(ad) => ad,
+        Err(e) => {
+            warn!("Failed to read stored ad {}: {}", ad_id, e);
+            return;
+        }
+    };
+    if let Some(mut ad) = stored {
+        update(&mut ad);
+        if let Err(e) = db.save_ads(vec![ad]).await {
+            warn!("Failed to update stored ad {}: {}", ad_id, e);
+        }
+    }
+}
+
+pub async fn update_ad(
+    db: web::Data<dyn ChannelRepository>,
+    path: web::Path<i64>,
+    req: web::Json<UpdateAdRequest>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    let ad_id = path.into_inner();
+    if req.is_empty() {
+        return HttpResponse::BadRequest().json(json!({"error": "Nothing to update"}));
+    }
+
+    let result = telegram_service.update_ad(ad_id, &req).await;
+    if let Ok(status) = &result {
+        // Budgets and views per user aren't part of the stored ad.
+        update_stored_ad(&db, ad_id, |ad| {
+            if let Some(cpm) = req.cpm {
+                ad.cpm = cpm;
             }
+            if let Some(status) = status {
+                ad.status = status.clone();
+            }
+        })
+        .await;
+    }
+    ad_action_response(result, format!("Ad {} updated", ad_id))
+}
+
+/// Starts or pauses the ad and stores the status it was left with.
+async fn set_ad_active(
+    db: web::Data<dyn ChannelRepository>,
+    ad_id: i64,
+    active: bool,
+    telegram_service: web::Data<TelegramService>,
+) -> Result<Option<String>, String> {
+    let status = telegram_service.set_ad_active(ad_id, active).await?;
+    update_stored_ad(&db, ad_id, |ad| ad.status = status.clone()).await;
+    Ok(Some(status))
+}
+
+pub async fn pause_ad(
+    db: web::Data<dyn ChannelRepository>,
+    path: web::Path<i64>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    let ad_id = path.into_inner();
+    let result = set_ad_active(db, ad_id, false, telegram_service).await;
+    ad_action_response(result, format!("Ad {} paused", ad_id))
+}
+
+pub async fn resume_ad(
+    db: web::Data<dyn ChannelRepository>,
+    path: web::Path<i64>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    let ad_id = path.into_inner();
+    let result = set_ad_active(db, ad_id, true, telegram_service).await;
+    ad_action_response(result, format!("Ad {} resumed", ad_id))
+}
+
+/// Deletes the ad on Telegram; its local history is kept and marked removed.
+pub async fn delete_ad(
+    db: web::Data<dyn ChannelRepository>,
+    path: web::Path<i64>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    let ad_id = path.into_inner();
+    let result = telegram_service.delete_ad(ad_id).await;
+    if let Ok(status) = &result {
+        update_stored_ad(&db, ad_id, |ad| {
+            ad.status = status.clone();
+            ad.removed_at.get_or_insert_with(Utc::now);
+        })
+        .await;
+    }
+    ad_action_response(result.map(Some), format!("Ad {} deleted", ad_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use actix_web::{App, body::to_bytes, http::StatusCode, test as actix_test};
+    use async_trait::async_trait;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        test_support::{channel, json_db, mock_telegram_service},
+        utils::text::SESSION_EXPIRED_ERROR,
+    };
+
+    fn split_request(split_by: &str, chunk_size: Option<usize>) -> CreateAdRequest {
+        serde_json::from_value(json!({
+            "title": "Spring sale",
+            "text": "Buy now",
+            "promote_url": "https://t.me/example/",
+            "cpm": 1.0,
+            "views_per_user": 1,
+            "budget": 10.0,
+            "daily_budget": 1.0,
+            "active": true,
+            "target_type": "channel",
+            "method": "draft",
+            "split_by": split_by,
+            "chunk_size": chunk_size,
+        }))
+        .unwrap()
+    }
+
+    fn tagged(id: i64, category: Option<&str>, geo: Option<&str>) -> ChannelData {
+        ChannelData {
+            category: category.map(str::to_string),
+            geo: geo.map(str::to_string),
+            ..channel(id, &format!("channel{}", id))
         }
-        Err(error_message) => {
-            if error_message.contains("not found") {
-                HttpResponse::BadRequest().json(json!({"error": error_message}))
-            } else {
-                HttpResponse::InternalServerError().json(json!({"error": error_message}))
+    }
+
+    fn channels() -> Vec<ChannelData> {
+        vec![
+            tagged(1, Some("tech"), Some("us")),
+            tagged(2, Some("news"), None),
+            tagged(3, None, Some("us")),
+            tagged(4, Some("tech"), Some(" ")),
+            tagged(5, Some(""), Some("de")),
+        ]
+    }
+
+    #[test]
+    fn validate_split_checks_target_type_and_chunk_size() {
+        assert!(validate_split(&split_request("category", None)).is_ok());
+        assert!(validate_split(&split_request("chunk", Some(1))).is_ok());
+
+        let mut bots = split_request("geo", None);
+        bots.target_type = AdTargetType::Bot;
+        assert!(validate_split(&bots).is_err());
+
+        assert!(validate_split(&split_request("chunk", None)).is_err());
+        assert!(validate_split(&split_request("chunk", Some(0))).is_err());
+        assert!(validate_split(&split_request("geo", Some(2))).is_err());
+
+        let mut unsplit = split_request("chunk", Some(2));
+        unsplit.split_by = None;
+        assert!(validate_split(&unsplit).is_err());
+    }
+
+    #[test]
+    fn group_channels_by_category_and_geo() {
+        assert_eq!(
+            group_channels(&channels(), AdSplitMode::Category, None),
+            vec![
+                ("news".to_string(), vec![2]),
+                ("no category".to_string(), vec![3, 5]),
+                ("tech".to_string(), vec![1, 4]),
+            ]
+        );
+        assert_eq!(
+            group_channels(&channels(), AdSplitMode::Geo, None),
+            vec![
+                ("de".to_string(), vec![5]),
+                ("no geo".to_string(), vec![2, 4]),
+                ("us".to_string(), vec![1, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn group_channels_by_chunk_keeps_order() {
+        let chunks = |size| {
+            group_channels(&channels(), AdSplitMode::Chunk, Some(size))
+                .into_iter()
+                .map(|(group, ids)| (group, ids.len()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            chunks(2),
+            vec![
+                ("1/3".to_string(), 2),
+                ("2/3".to_string(), 2),
+                ("3/3".to_string(), 1),
+            ]
+        );
+        assert_eq!(chunks(5), vec![("1/1".to_string(), 5)]);
+        assert_eq!(chunks(10), vec![("1/1".to_string(), 5)]);
+        assert_eq!(chunks(1).len(), 5);
+        assert_eq!(
+            group_channels(&channels(), AdSplitMode::Chunk, Some(2))[2].1,
+            vec![5]
+        );
+        assert!(group_channels(&[], AdSplitMode::Chunk, Some(2)).is_empty());
+    }
+
+    /// Answers each ad with the next queued result and records what was sent.
+    #[derive(Default)]
+    struct FakeSubmitter {
+        results: Mutex<VecDeque<Result<String, String>>>,
+        submitted: Mutex<Vec<(String, Vec<i64>)>>,
+    }
+
+    impl FakeSubmitter {
+        fn answering(results: Vec<Result<String, String>>) -> Self {
+            Self {
+                results: Mutex::new(results.into()),
+                ..Default::default()
             }
         }
     }
+
+    #[async_trait]
+    impl AdSubmitter for FakeSubmitter {
+        async fn create_ad(&self, ad: CreateAdRequest, target: AdTarget) -> Result<String, String> {
+            let AdTarget::Channels(channel_ids) = target else {
+                return Err("Unexpected target".to_string());
+            };
+            self.submitted
+                .lock()
+                .unwrap()
+                .push((ad.title.unwrap_or_default(), channel_ids));
+            self.results.lock().unwrap().pop_front().unwrap()
+        }
+    }
+
+    async fn response_json(response: HttpResponse) -> (StatusCode, Value) {
+        let status = response.status();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn split_ads_are_titled_per_group() {
+        let submitter = FakeSubmitter::answering(vec![
+            Ok("Ad created".to_string()),
+            Ok("Ad created".to_string()),
+        ]);
+        let groups = vec![
+            ("news".to_string(), vec![2]),
+            ("tech".to_string(), vec![1, 4]),
+        ];
+
+        let (status, body) = response_json(
+            create_split_ads(&submitter, split_request("category", None), groups).await,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "success");
+        assert_eq!(
+            *submitter.submitted.lock().unwrap(),
+            vec![
+                ("Spring sale (news)".to_string(), vec![2]),
+                ("Spring sale (tech)".to_string(), vec![1, 4]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_session_skips_the_remaining_groups() {
+        let submitter = FakeSubmitter::answering(vec![
+            Ok("Ad created".to_string()),
+            Err("Validation error in field 'text': too long".to_string()),
+            Err(format!("{}: log in again", SESSION_EXPIRED_ERROR)),
+        ]);
+        let groups = (1..=4).map(|i| (format!("{}/4", i), vec![i])).collect();
+
+        let (status, body) = response_json(
+            create_split_ads(&submitter, split_request("chunk", Some(1)), groups).await,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "partial");
+        assert_eq!(body["code"], SESSION_EXPIRED_CODE);
+        let ads = body["ads"].as_array().unwrap();
+        assert_eq!(ads[0]["message"], "Ad created");
+        assert_eq!(ads[1]["field"], "text");
+        assert!(
+            ads[2]["error"]
+                .as_str()
+                .unwrap()
+                .starts_with(SESSION_EXPIRED_ERROR)
+        );
+        assert_eq!(ads[3]["skipped"], true);
+        assert_eq!(ads[3]["title"], "Spring sale (4/4)");
+        assert_eq!(submitter.submitted.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn split_ads_fail_with_the_session_status() {
+        let submitter = FakeSubmitter::answering(vec![Err(format!(
+            "{}: log in again",
+            SESSION_EXPIRED_ERROR
+        ))]);
+        let groups = vec![("us".to_string(), vec![1]), ("de".to_string(), vec![5])];
+
+        let (status, body) =
+            response_json(create_split_ads(&submitter, split_request("geo", None), groups).await)
+                .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["status"], "failed");
+        assert_eq!(body["ads"][1]["skipped"], true);
+
+        let submitter = FakeSubmitter::answering(vec![Err(
+            "Validation error in field 'text': too long".to_string(),
+        )]);
+        let (status, _) = response_json(
+            create_split_ads(
+                &submitter,
+                split_request("geo", None),
+                vec![("us".to_string(), vec![1])],
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    const BOUNDARY: &str = "media-boundary";
+
+    /// A multipart form with `bytes` in its `file` field.
+    fn media_upload(content_type: &str, bytes: &[u8]) -> actix_test::TestRequest {
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"photo.png\"\r\nContent-Type: {}\r\n\r\n",
+            BOUNDARY, content_type
+        )
+        .into_bytes();
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+        actix_test::TestRequest::post()
+            .uri("/media")
+            .insert_header((
+                "content-type",
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            ))
+            .set_payload(body)
+    }
+
+    #[actix_web::test]
+    async fn mock_upload_saves_one_record_per_file() {
+        let (db, dir) = json_db("ads-media-upload").await;
+        let db: web::Data<dyn ChannelRepository> =
+            web::Data::from(Arc::new(db) as Arc<dyn ChannelRepository>);
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(db.clone())
+                .app_data(web::Data::new(mock_telegram_service()))
+                .route("/media", web::post().to(upload_media)),
+        )
+        .await;
+
+        let first: MediaRecord = actix_test::call_and_read_body_json(
+            &app,
+            media_upload("image/png", b"image").to_request(),
+        )
+        .await;
+        let second: MediaRecord = actix_test::call_and_read_body_json(
+            &app,
+            media_upload("image/png", b"image").to_request(),
+        )
+        .await;
+        assert_eq!(second.id, first.id);
+        assert_eq!(first.file_name, "photo.png");
+        assert_eq!(first.content_type, "image/png");
+        assert_eq!(first.size, 5);
+        assert_eq!(db.list_media().await.unwrap().len(), 1);
+
+        // An ad can then use the stored id, but not an unknown one.
+        let mut ad = split_request("category", None);
+        ad.media = Some(first.id);
+        assert!(check_media(&db, &ad).await.is_ok());
+        ad.media = Some("mock-unknown".to_string());
+        assert!(check_media(&db, &ad).await.is_err());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[actix_web::test]
+    async fn upload_rejects_bad_files() {
+        let (db, dir) = json_db("ads-media-rejected").await;
+        let db: web::Data<dyn ChannelRepository> =
+            web::Data::from(Arc::new(db) as Arc<dyn ChannelRepository>);
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(db.clone())
+                .app_data(web::Data::new(mock_telegram_service()))
+                .route("/media", web::post().to(upload_media)),
+        )
+        .await;
+
+        let too_large = vec![0; MEDIA_MAX_SIZE + 1];
+        for request in [
+            media_upload("text/plain", b"text"),
+            media_upload("image/png", b""),
+            media_upload("image/png", &too_large),
+        ] {
+            let response = actix_test::call_service(&app, request.to_request()).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body: Value = actix_test::read_body_json(response).await;
+            assert_eq!(body["field"], "file");
+        }
+        assert!(db.list_media().await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
 }
@@ -2,10 +2,24 @@ use actix_web::web;
 mod handlers;
 pub mod models;
 
+const PLAN_PAYLOAD_LIMIT: usize = 4 * 1024 * 1024;
+
 pub fn routers(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/ads")
+            .route("/", web::get().to(handlers::list_ads))
             .route("/", web::post().to(handlers::create_ad))
             .route("/generate", web::post().to(handlers::generate_ad_message))
+            .service(
+                web::resource("/bulk")
+                    .app_data(web::PayloadConfig::new(PLAN_PAYLOAD_LIMIT))
+                    .route(web::post().to(handlers::create_ads_from_plan)),
+            )
+            .route("/media", web::get().to(handlers::list_media))
+            .route("/media", web::post().to(handlers::upload_media))
+            .route("/{id}", web::put().to(handlers::update_ad))
+            .route("/{id}", web::delete().to(handlers::delete_ad))
+            .route("/{id}/pause", web::post().to(handlers::pause_ad))
+            .route("/{id}/resume", web::post().to(handlers::resume_ad)),
     );
 }
@@ -1,6 +1,20 @@
-use serde::Deserialize;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
 
+use crate::database::transfer::TransferFormat;
 
+// Length limits of the ads platform's ad form, counted in characters.
+pub const TEXT_MAX_LEN: usize = 160;
+pub const TITLE_MAX_LEN: usize = 40;
+pub const WEBSITE_NAME_MAX_LEN: usize = 30;
+pub const AD_INFO_MAX_LEN: usize = 100;
+
+/// Rows a plan sent over HTTP may have, so one request doesn't submit for
+/// minutes; larger plans go through the `campaign` CLI command.
+pub const PLAN_MAX_ROWS: usize = 100;
+
+pub const MEDIA_MAX_SIZE: usize = 20 * 1024 * 1024;
+pub const MEDIA_CONTENT_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/gif", "video/mp4"];
 
 #[derive(Deserialize)]
 pub struct GenerateAdMessageRequest {
@@ -8,35 +22,222 @@ pub struct GenerateAdMessageRequest {
     pub channels_names: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct CreateAdRequest {
+    /// Defaults to the promoted URL plus today's date.
+    pub title: Option<String>,
     pub text: String,
     pub promote_url: String,
+    pub website_name: Option<String>,
+    pub website_photo: Option<String>,
+    /// Id of a file uploaded through `POST /ads/media`.
+    pub media: Option<String>,
+    pub ad_info: Option<String>,
     pub cpm: f32,
     pub views_per_user: i32,
     pub budget: f32,
     pub daily_budget: f32,
     pub active: bool,
     pub target_type: AdTargetType,
+    #[serde(default)]
     pub channels: Vec<String>,
+    /// Saved list to target instead of `channels`.
+    pub list_id: Option<i64>,
+    /// Bot usernames, for `target_type: "bot"`.
+    #[serde(default)]
+    pub bots: Vec<String>,
+    /// Search queries, for `target_type: "search"`.
+    #[serde(default)]
+    pub search_queries: Vec<String>,
     pub method: AdMethodType,
+    /// Creates one ad per group of target channels instead of a single ad.
+    pub split_by: Option<AdSplitMode>,
+    /// Channels per ad, for `split_by: "chunk"`.
+    pub chunk_size: Option<usize>,
 }
 
-#[derive(Deserialize)]
+impl CreateAdRequest {
+    /// Checks the free-text fields against the ad form's length limits and
+    /// returns the offending field with a message.
+    pub fn validate_lengths(&self) -> Result<(), (&'static str, String)> {
+        let fields = [
+            ("title", self.title.as_deref(), TITLE_MAX_LEN),
+            ("text", Some(self.text.as_str()), TEXT_MAX_LEN),
+            (
+                "website_name",
+                self.website_name.as_deref(),
+                WEBSITE_NAME_MAX_LEN,
+            ),
+            ("ad_info", self.ad_info.as_deref(), AD_INFO_MAX_LEN),
+        ];
+
+        for (field, value, max_len) in fields {
+            let len = value.map_or(0, |v| v.trim().chars().count());
+            if len > max_len {
+                return Err((
+                    field,
+                    format!("Must be at most {} characters, got {}", max_len, len),
+                ));
+            }
+        }
+
+        if self.text.trim().is_empty() {
+            return Err(("text", "Must not be empty".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn title_or_default(&self) -> String {
+        match self.title.as_deref().map(str::trim) {
+            Some(title) if !title.is_empty() => title.to_string(),
+            _ => default_title(&self.promote_url, Utc::now().date_naive()),
+        }
+    }
+
+    /// The title with the split group appended, e.g. "Spring sale (tech)",
+    /// shortening the title part to stay within `TITLE_MAX_LEN`.
+    pub fn title_for_group(&self, group: &str) -> String {
+        let suffix = format!(" ({})", group);
+        let max_title_len = TITLE_MAX_LEN.saturating_sub(suffix.chars().count());
+        let title: String = self
+            .title_or_default()
+            .chars()
+            .take(max_title_len)
+            .collect();
+        format!("{}{}", title.trim_end(), suffix)
+            .chars()
+            .take(TITLE_MAX_LEN)
+            .collect()
+    }
+}
+
+/// Builds a title like "t.me/channel 2025-06-01" from the promoted URL,
+/// shortening the URL part so the whole title fits `TITLE_MAX_LEN`.
+pub fn default_title(promote_url: &str, date: NaiveDate) -> String {
+    let date = date.format("%Y-%m-%d").to_string();
+    let url = promote_url.trim();
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url)
+        .trim_end_matches('/');
+
+    let max_url_len = TITLE_MAX_LEN - date.len() - 1;
+    let url: String = url.chars().take(max_url_len).collect();
+    if url.is_empty() {
+        date
+    } else {
+        format!("{} {}", url, date)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum AdTargetType {
     Channel,
+    Bot,
+    Search,
 }
 
 impl AdTargetType {
     pub fn as_str(&self) -> &'static str {
         match self {
             AdTargetType::Channel => "channel",
+            AdTargetType::Bot => "bot",
+            AdTargetType::Search => "search",
         }
     }
 }
 
+/// Targets of an ad after usernames have been resolved to ids.
+pub enum AdTarget {
+    Channels(Vec<i64>),
+    Bots(Vec<i64>),
+    SearchQueries(Vec<String>),
+}
+
+impl AdTarget {
+    /// Values for the `channels`, `bots` and `search_queries` form fields;
+    /// the ones not used by the target type are sent empty.
+    pub fn form_fields(&self) -> (String, String, String) {
+        let join_ids = |ids: &[i64]| {
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(";")
+        };
+
+        match self {
+            AdTarget::Channels(ids) => (join_ids(ids), String::new(), String::new()),
+            AdTarget::Bots(ids) => (String::new(), join_ids(ids), String::new()),
+            AdTarget::SearchQueries(queries) => (String::new(), String::new(), queries.join(";")),
+        }
+    }
+}
+
+/// Fields to change on an existing ad; unset fields are left as they are.
 #[derive(Deserialize)]
+pub struct UpdateAdRequest {
+    pub cpm: Option<f64>,
+    pub views_per_user: Option<i32>,
+    pub budget: Option<f32>,
+    pub daily_budget: Option<f32>,
+    pub active: Option<bool>,
+}
+
+impl UpdateAdRequest {
+    pub fn is_empty(&self) -> bool {
+        self.cpm.is_none()
+            && self.views_per_user.is_none()
+            && self.budget.is_none()
+            && self.daily_budget.is_none()
+            && self.active.is_none()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BulkAdsQuery {
+    #[serde(default)]
+    pub format: TransferFormat,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Pause between two submitted ads, defaults to `DEFAULT_PLAN_DELAY_MS`.
+    pub delay_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct ListAdsQuery {
+    /// Skip the ads account and return only the locally stored history.
+    #[serde(default)]
+    pub cached: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AdSplitMode {
+    Category,
+    Geo,
+    Chunk,
+}
+
+/// Outcome of one ad created in split mode.
+#[derive(Serialize)]
+pub struct SplitAdResult {
+    pub group: String,
+    pub title: String,
+    pub channels: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Not sent because the ads session expired on an earlier group.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum AdMethodType {
     Save,
@@ -51,3 +252,86 @@ impl AdMethodType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_form_fields_fill_only_their_field() {
+        assert_eq!(
+            AdTarget::Channels(vec![1, 2]).form_fields(),
+            ("1;2".to_string(), String::new(), String::new())
+        );
+        assert_eq!(
+            AdTarget::Bots(vec![3]).form_fields(),
+            (String::new(), "3".to_string(), String::new())
+        );
+        assert_eq!(
+            AdTarget::SearchQueries(vec!["crypto".to_string(), "news".to_string()]).form_fields(),
+            (String::new(), String::new(), "crypto;news".to_string())
+        );
+    }
+
+    fn create_request(title: Option<&str>, text: &str) -> CreateAdRequest {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "text": text,
+            "promote_url": "https://t.me/example/",
+            "cpm": 1.0,
+            "views_per_user": 1,
+            "budget": 10.0,
+            "daily_budget": 1.0,
+            "active": true,
+            "target_type": "channel",
+            "method": "draft",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn validate_lengths_names_the_offending_field() {
+        assert!(
+            create_request(Some("Sale"), "Buy now")
+                .validate_lengths()
+                .is_ok()
+        );
+
+        let long_title = "x".repeat(TITLE_MAX_LEN + 1);
+        let (field, _) = create_request(Some(&long_title), "Buy now")
+            .validate_lengths()
+            .unwrap_err();
+        assert_eq!(field, "title");
+
+        let (field, _) = create_request(None, "  ").validate_lengths().unwrap_err();
+        assert_eq!(field, "text");
+    }
+
+    #[test]
+    fn default_title_strips_scheme_and_fits_limit() {
+        let date = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        assert_eq!(
+            default_title("https://t.me/example/", date),
+            "t.me/example 2025-06-01"
+        );
+
+        let title = default_title(&format!("https://{}", "a".repeat(100)), date);
+        assert_eq!(title.chars().count(), TITLE_MAX_LEN);
+        assert!(title.ends_with(" 2025-06-01"));
+
+        assert_eq!(default_title("https://", date), "2025-06-01");
+    }
+
+    #[test]
+    fn title_for_group_keeps_the_group_suffix() {
+        assert_eq!(
+            create_request(Some("Spring sale"), "Buy now").title_for_group("tech"),
+            "Spring sale (tech)"
+        );
+
+        let long_title = "x".repeat(TITLE_MAX_LEN);
+        let title = create_request(Some(&long_title), "Buy now").title_for_group("tech");
+        assert_eq!(title.chars().count(), TITLE_MAX_LEN);
+        assert!(title.ends_with(" (tech)"));
+    }
+}
@@ -0,0 +1,47 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use log::error;
+use serde_json::json;
+
+use crate::{
+    api::v1::authorize_admin,
+    config::AppConfig,
+    database::{ChannelRepository, RepositoryError},
+};
+
+fn backup_error_response(error: RepositoryError) -> HttpResponse {
+    match error {
+        RepositoryError::Unsupported(e) => HttpResponse::NotImplemented().json(json!({"error": e})),
+        RepositoryError::NotFound(e) => HttpResponse::NotFound().json(json!({"error": e})),
+        e => {
+            error!("Backup operation failed: {}", e);
+            HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    }
+}
+
+pub async fn get_backups(db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    match db.list_backups().await {
+        Ok(backups) => HttpResponse::Ok().json(json!(backups)),
+        Err(e) => backup_error_response(e),
+    }
+}
+
+/// Replaces the whole database with the named backup. Requires
+/// `Authorization: Bearer <APP_ADMIN_TOKEN>`; disabled while no admin token is
+/// configured.
+pub async fn restore_backup(
+    req: HttpRequest,
+    name: web::Path<String>,
+    db: web::Data<dyn ChannelRepository>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, "restore backups") {
+        return response;
+    }
+
+    let name = name.into_inner();
+    match db.restore_backup(&name).await {
+        Ok(()) => HttpResponse::Ok().json(json!({"status": "ok", "restored": name})),
+        Err(e) => backup_error_response(e),
+    }
+}
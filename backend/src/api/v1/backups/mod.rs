@@ -0,0 +1,10 @@
+use actix_web::web;
+mod handlers;
+
+pub fn routers(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/backups")
+            .route("/", web::get().to(handlers::get_backups))
+            .route("/{name}/restore", web::post().to(handlers::restore_backup)),
+    );
+}
@@ -0,0 +1,86 @@
+use actix_web::{HttpResponse, web};
+use chrono::Utc;
+use log::error;
+use serde_json::json;
+
+use crate::{
+    database::{ChannelRepository, RepositoryError, models::BlacklistEntry},
+    utils::text::TextUtils,
+};
+
+use super::models::BlacklistRequest;
+
+fn blacklist_error_response(error: RepositoryError) -> HttpResponse {
+    match error {
+        RepositoryError::NotFound(e) => HttpResponse::NotFound().json(json!({"error": e})),
+        e => {
+            error!("Blacklist operation failed: {}", e);
+            HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    }
+}
+
+pub async fn get_blacklist(db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    match db.list_blacklist().await {
+        Ok(entries) => HttpResponse::Ok().json(json!(entries)),
+        Err(e) => blacklist_error_response(e.into()),
+    }
+}
+
+pub async fn add_to_blacklist(
+    db: web::Data<dyn ChannelRepository>,
+    req: web::Json<BlacklistRequest>,
+) -> HttpResponse {
+    let req = req.into_inner();
+    let mut channel_id = req.channel_id;
+    let mut username = req
+        .username
+        .as_deref()
+        .map(|u| TextUtils::normalize_name(u).to_lowercase())
+        .filter(|u| !u.is_empty());
+
+    if channel_id.is_none() && username.is_none() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Provide 'channel_id' or 'username'"
+        }));
+    }
+
+    // Fill in the other half from the catalog so the entry survives a
+    // username change or a lookup by id.
+    let known = match (channel_id, &username) {
+        (Some(id), _) => db.get_channel_by_id(id).await,
+        (None, Some(name)) => db.get_channel_by_username(name).await,
+        (None, None) => Ok(None),
+    };
+    match known {
+        Ok(Some(channel)) => {
+            channel_id = channel_id.or(Some(channel.id));
+            username = username.or(Some(channel.username.to_lowercase()));
+        }
+        Ok(None) => {}
+        Err(e) => return blacklist_error_response(e.into()),
+    }
+
+    let entry = BlacklistEntry {
+        id: 0,
+        channel_id,
+        username,
+        reason: req.reason.trim().to_string(),
+        created_at: Utc::now(),
+    };
+
+    match db.add_blacklist_entry(entry).await {
+        Ok(entry) => HttpResponse::Created().json(json!(entry)),
+        Err(e) => blacklist_error_response(e.into()),
+    }
+}
+
+pub async fn remove_from_blacklist(
+    id: web::Path<i64>,
+    db: web::Data<dyn ChannelRepository>,
+) -> HttpResponse {
+    match db.remove_blacklist_entry(id.into_inner()).await {
+        Ok(()) => HttpResponse::Ok().json(json!({"status": "ok"})),
+        Err(e) => blacklist_error_response(e),
+    }
+}
@@ -0,0 +1,12 @@
+use actix_web::web;
+mod handlers;
+mod models;
+
+pub fn routers(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/blacklist")
+            .route("/", web::get().to(handlers::get_blacklist))
+            .route("/", web::post().to(handlers::add_to_blacklist))
+            .route("/{id}", web::delete().to(handlers::remove_from_blacklist)),
+    );
+}
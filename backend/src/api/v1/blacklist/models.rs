@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct BlacklistRequest {
+    pub channel_id: Option<i64>,
+    pub username: Option<String>,
+    pub reason: String,
+}
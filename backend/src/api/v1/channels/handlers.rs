@@ -1,32 +1,215 @@
+use std::collections::HashSet;
+
 use crate::{
-    config::AppConfig, database::JsonDatabase, services::telegram::TelegramService,
+    api::v1::telegram::{SESSION_EXPIRED_CODE, session_expired_response},
+    config::AppConfig,
+    database::{
+        ChannelRepository, RepositoryError,
+        graph::{build_graph, render_graph},
+        growth::ChannelGrowth,
+        merge::find_username_collisions,
+        models::{ChannelFilter, normalize_tags},
+        relevance::{rank_channels, score_channels},
+        transfer::{self, TransferFormat},
+    },
+    services::{crawl, telegram::TelegramService},
     utils::text::TextUtils,
 };
 
 use super::models::{
-    ChannelQuery, SimilarChannelRequest, UpdateChannelCategoryRequest, UpdateChannelGeoRequest,
+    ChannelQuery, CrawlRequest, ExportQuery, GraphQuery, ImportQuery, MergeChannelRequest,
+    RankedQuery, SimilarChannelRequest, UpdateChannelCategoryRequest, UpdateChannelGeoRequest,
+    UpdateChannelTagsRequest,
 };
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpResponse, http::header, web};
 use log::error;
 use serde_json::json;
 
 pub async fn get_channels(
     query: web::Query<ChannelQuery>,
-    db: web::Data<JsonDatabase>,
+    db: web::Data<dyn ChannelRepository>,
 ) -> HttpResponse {
     let category = &query.category;
     let geo = &query.geo;
-    let channels = db.filter_channels(category.as_ref(), geo.as_ref()).await;
+    let page = match db.filter_channels(&query.to_filter()).await {
+        Ok(page) => page,
+        Err(e) => {
+            error!("Failed to load channels: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to load channels");
+        }
+    };
 
     HttpResponse::Ok().json(json!({
         "category": category,
         "geo": geo,
-        "channels": channels,
+        "channels": page.channels,
+        "total": page.total,
+        "limit": query.limit,
+        "offset": query.offset.unwrap_or_default(),
     }))
 }
 
+pub async fn get_ranked_channels(
+    query: web::Query<ChannelQuery>,
+    ranked: web::Query<RankedQuery>,
+    db: web::Data<dyn ChannelRepository>,
+) -> HttpResponse {
+    let seeds = match &ranked.seeds {
+        None => None,
+        Some(seeds) => {
+            let mut seed_ids = HashSet::new();
+            for username in seeds.split(',').map(TextUtils::normalize_name) {
+                if username.is_empty() {
+                    continue;
+                }
+                match db.get_channel_by_username(&username).await {
+                    Ok(Some(channel)) => {
+                        seed_ids.insert(channel.id);
+                    }
+                    Ok(None) => {
+                        return HttpResponse::BadRequest().json(json!({
+                            "field": "seeds",
+                            "error": format!("Channel with username '{}' not found", username),
+                        }));
+                    }
+                    Err(e) => {
+                        error!("Failed to load seed '{}': {}", username, e);
+                        return HttpResponse::InternalServerError().body("Failed to load seeds");
+                    }
+                }
+            }
+            Some(seed_ids)
+        }
+    };
+
+    let expansions = match db.list_crawl_expansions().await {
+        Ok(expansions) => expansions,
+        Err(e) => {
+            error!("Failed to load similarity edges: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to load similarity edges");
+        }
+    };
+    let scores = score_channels(&expansions, seeds.as_ref());
+    let blacklist = match db.list_blacklist().await {
+        Ok(blacklist) => blacklist,
+        Err(e) => {
+            error!("Failed to load blacklist: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to load blacklist");
+        }
+    };
+
+    // Pagination applies to the ranked list, not the catalog order.
+    let filter = query.to_filter();
+    let page = match db
+        .filter_channels(&ChannelFilter {
+            limit: None,
+            offset: 0,
+            ..filter.clone()
+        })
+        .await
+    {
+        Ok(page) => page,
+        Err(e) => {
+            error!("Failed to load channels: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to load channels");
+        }
+    };
+    let candidates = page
+        .channels
+        .into_iter()
+        .filter(|c| seeds.as_ref().is_none_or(|seeds| !seeds.contains(&c.id)))
+        .filter(|c| !blacklist.iter().any(|e| e.matches(c.id, Some(&c.username))))
+        .collect();
+    let ranked = rank_channels(candidates, &scores);
+
+    HttpResponse::Ok().json(json!({
+        "total": ranked.len(),
+        "channels": filter.paginate(ranked),
+        "limit": query.limit,
+        "offset": query.offset.unwrap_or_default(),
+    }))
+}
+
+pub async fn export_channels(
+    query: web::Query<ChannelQuery>,
+    export: web::Query<ExportQuery>,
+    db: web::Data<dyn ChannelRepository>,
+) -> HttpResponse {
+    let page = match db.filter_channels(&query.to_filter()).await {
+        Ok(page) => page,
+        Err(e) => {
+            error!("Failed to load channels for export: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to export channels");
+        }
+    };
+
+    let (content_type, extension) = match export.format {
+        TransferFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        TransferFormat::Json => ("application/json", "json"),
+    };
+    match transfer::export_channels(&page.channels, export.format) {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"channels.{}\"", extension),
+            ))
+            .body(body),
+        Err(e) => {
+            error!("Failed to export channels: {}", e);
+            HttpResponse::InternalServerError().body("Failed to export channels")
+        }
+    }
+}
+
+pub async fn export_graph(
+    query: web::Query<ChannelQuery>,
+    graph_query: web::Query<GraphQuery>,
+    db: web::Data<dyn ChannelRepository>,
+) -> HttpResponse {
+    let format = graph_query.format;
+
+    match build_graph(db.get_ref(), &query.to_filter()).await {
+        Ok(graph) => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"channels.{}\"", format.extension()),
+            ))
+            .body(render_graph(&graph, format)),
+        Err(e) => {
+            error!("Failed to export similarity graph: {}", e);
+            HttpResponse::InternalServerError().body("Failed to export similarity graph")
+        }
+    }
+}
+
+pub async fn import_channels(
+    query: web::Query<ImportQuery>,
+    db: web::Data<dyn ChannelRepository>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let records = match transfer::parse_channels(&body, query.format) {
+        Ok(records) => records,
+        Err(e) => return HttpResponse::BadRequest().json(json!({"error": e})),
+    };
+
+    let report = transfer::import_channels(records, db.get_ref(), query.dry_run).await;
+    HttpResponse::Ok().json(report)
+}
+
+pub async fn get_collisions(db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    match db.filter_channels(&ChannelFilter::default()).await {
+        Ok(page) => HttpResponse::Ok().json(json!(find_username_collisions(&page.channels))),
+        Err(e) => {
+            error!("Failed to load channels: {}", e);
+            HttpResponse::InternalServerError().body("Failed to load channels")
+        }
+    }
+}
+
 pub async fn get_similar_channels(
-    db: web::Data<JsonDatabase>,
+    db: web::Data<dyn ChannelRepository>,
     req: web::Json<SimilarChannelRequest>,
     config: web::Data<AppConfig>,
     telegram_service: web::Data<TelegramService>,
@@ -37,17 +220,32 @@ pub async fn get_similar_channels(
         .await
     {
         Ok(channels_data) => {
-            match telegram_service
-                .fetch_similar_channels(
-                    db.clone(),
-                    channels_data.clone(),
-                    config.categories.clone(),
-                    config.geos.clone(),
-                )
-                .await
+            match crawl::fetch_similar_channels(
+                telegram_service.get_ref(),
+                db.clone(),
+                channels_data.clone(),
+                config.categories.clone(),
+                config.geos.clone(),
+            )
+            .await
             {
-                Ok(similar_channels) => HttpResponse::Ok().json(json!(similar_channels)),
-                Err(_) => HttpResponse::InternalServerError().body("Failed to get channel IDs"),
+                // Like a crawl, seeds that failed on their own only fail the
+                // request when no seed could be expanded at all.
+                Ok(similar) => match similar.error {
+                    Some(e) if TextUtils::is_session_expired_error(&e) => {
+                        session_expired_response(&e)
+                    }
+                    _ if !similar.failed_seeds.is_empty()
+                        && similar.expanded + similar.resumed == 0 =>
+                    {
+                        HttpResponse::BadGateway().json(json!(similar))
+                    }
+                    _ => HttpResponse::Ok().json(json!(similar.channels)),
+                },
+                Err(e) => {
+                    error!("Failed to fetch similar channels: {}", e);
+                    HttpResponse::InternalServerError().body("Failed to get channel IDs")
+                }
             }
         }
         Err(e) => {
@@ -57,17 +255,71 @@ pub async fn get_similar_channels(
     }
 }
 
+pub async fn crawl_channels(
+    db: web::Data<dyn ChannelRepository>,
+    req: web::Json<CrawlRequest>,
+    config: web::Data<AppConfig>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    if let Err((field, error)) = req.validate_limits() {
+        return HttpResponse::BadRequest().json(json!({"field": field, "error": error}));
+    }
+
+    let normalized_channels = TextUtils::normalize_names(&req.channels_names);
+    let seeds = match telegram_service
+        .check_and_add_channels(db.clone(), &normalized_channels)
+        .await
+    {
+        Ok(seeds) => seeds,
+        Err(e) => {
+            error!("Error checking and adding channels: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to process channels");
+        }
+    };
+
+    let report = crawl::crawl_similar_channels(
+        telegram_service.get_ref(),
+        db.clone(),
+        seeds,
+        &req.to_options(),
+        config.categories.clone(),
+        config.geos.clone(),
+    )
+    .await;
+
+    // A failed crawl still returns what it found; expanded seeds are stored,
+    // so repeating the request resumes from there. Seeds that failed on their
+    // own are listed in `failed_seeds` and only fail the request when no seed
+    // could be expanded at all.
+    if report
+        .error
+        .as_deref()
+        .is_some_and(TextUtils::is_session_expired_error)
+    {
+        let mut body = json!(report);
+        body["code"] = json!(SESSION_EXPIRED_CODE);
+        HttpResponse::Unauthorized().json(body)
+    } else if report.error.is_some()
+        || (!report.failed_seeds.is_empty() && report.expanded + report.resumed == 0)
+    {
+        HttpResponse::BadGateway().json(json!(report))
+    } else {
+        HttpResponse::Ok().json(json!(report))
+    }
+}
+
 pub async fn update_category(
     id: web::Path<i64>,
-    db: web::Data<JsonDatabase>,
+    db: web::Data<dyn ChannelRepository>,
     req: web::Json<UpdateChannelCategoryRequest>,
 ) -> HttpResponse {
     let id = id.into_inner();
     let category = req.category.clone();
     let _ = db
-        .update_channel_by_id(id, |channel| {
-            channel.category = Some(category.clone());
-        })
+        .update_channel_by_id(
+            id,
+            Box::new(move |channel| channel.category = Some(category)),
+        )
         .await;
 
     HttpResponse::Ok().json(json!({"status": "ok"}))
@@ -75,23 +327,82 @@ pub async fn update_category(
 
 pub async fn update_geo(
     id: web::Path<i64>,
-    db: web::Data<JsonDatabase>,
+    db: web::Data<dyn ChannelRepository>,
     req: web::Json<UpdateChannelGeoRequest>,
 ) -> HttpResponse {
     let id = id.into_inner();
     let geo = req.into_inner().geo;
     let _ = db
-        .update_channel_by_id(id, |channel| {
-            channel.geo = Some(geo.clone());
-        })
+        .update_channel_by_id(id, Box::new(move |channel| channel.geo = Some(geo)))
         .await;
 
     HttpResponse::Ok().json(json!({"status": "ok"}))
 }
 
+pub async fn update_tags(
+    id: web::Path<i64>,
+    db: web::Data<dyn ChannelRepository>,
+    req: web::Json<UpdateChannelTagsRequest>,
+) -> HttpResponse {
+    let id = id.into_inner();
+    let tags = normalize_tags(&req.tags);
+
+    match db
+        .update_channel_by_id(id, {
+            let tags = tags.clone();
+            Box::new(move |channel| channel.tags = tags)
+        })
+        .await
+    {
+        Ok(()) => HttpResponse::Ok().json(json!({"status": "ok", "tags": tags})),
+        Err(RepositoryError::NotFound(e)) => HttpResponse::NotFound().body(e),
+        Err(e) => {
+            error!("Failed to update tags for channel {}: {}", id, e);
+            HttpResponse::InternalServerError().body("Failed to update tags")
+        }
+    }
+}
+
+pub async fn delete_channel(
+    id: web::Path<i64>,
+    db: web::Data<dyn ChannelRepository>,
+) -> HttpResponse {
+    let id = id.into_inner();
+
+    match db.delete_channel(id).await {
+        Ok(()) => HttpResponse::Ok().json(json!({"status": "ok"})),
+        Err(RepositoryError::NotFound(e)) => HttpResponse::NotFound().body(e),
+        Err(e) => {
+            error!("Failed to delete channel {}: {}", id, e);
+            HttpResponse::InternalServerError().body("Failed to delete channel")
+        }
+    }
+}
+
+pub async fn merge_channel(
+    id: web::Path<i64>,
+    db: web::Data<dyn ChannelRepository>,
+    req: web::Json<MergeChannelRequest>,
+) -> HttpResponse {
+    let target_id = id.into_inner();
+
+    match db.merge_channels(req.source_id, target_id).await {
+        Ok(channel) => HttpResponse::Ok().json(json!(channel)),
+        Err(RepositoryError::NotFound(e)) => HttpResponse::NotFound().body(e),
+        Err(RepositoryError::Invalid(e)) => HttpResponse::BadRequest().body(e),
+        Err(e) => {
+            error!(
+                "Failed to merge channel {} into {}: {}",
+                req.source_id, target_id, e
+            );
+            HttpResponse::InternalServerError().body("Failed to merge channels")
+        }
+    }
+}
+
 pub async fn get_new_data(
     id: web::Path<i64>,
-    db: web::Data<JsonDatabase>,
+    db: web::Data<dyn ChannelRepository>,
     config: web::Data<AppConfig>,
     telegram_service: web::Data<TelegramService>,
 ) -> HttpResponse {
@@ -99,7 +410,7 @@ pub async fn get_new_data(
 
     match telegram_service
         .fetch_new_data(
-            id.clone(),
+            id,
             db.clone(),
             config.categories.clone(),
             config.geos.clone(),
@@ -111,3 +422,34 @@ pub async fn get_new_data(
             .body(format!("Failed to update data for channel {}", id)),
     }
 }
+
+pub async fn get_history(id: web::Path<i64>, db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    let id = id.into_inner();
+
+    match db.get_channel_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().body(format!("Channel with id {} not found", id));
+        }
+        Err(e) => {
+            error!("Failed to load channel {}: {}", id, e);
+            return HttpResponse::InternalServerError().body("Failed to load channel");
+        }
+    }
+
+    match db.get_subscriber_history(id).await {
+        Ok(history) => {
+            let growth = ChannelGrowth::from_history(&history);
+            HttpResponse::Ok().json(json!({
+                "id": id,
+                "history": history,
+                "growth_7d": growth.growth_7d,
+                "growth_30d": growth.growth_30d,
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load subscriber history for {}: {}", id, e);
+            HttpResponse::InternalServerError().body("Failed to load subscriber history")
+        }
+    }
+}
@@ -2,13 +2,29 @@ use actix_web::web;
 mod handlers;
 mod models;
 
+const IMPORT_PAYLOAD_LIMIT: usize = 16 * 1024 * 1024;
+
 pub fn routers(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/channels")
             .route("/", web::get().to(handlers::get_channels))
+            .route("/export", web::get().to(handlers::export_channels))
+            .service(
+                web::resource("/import")
+                    .app_data(web::PayloadConfig::new(IMPORT_PAYLOAD_LIMIT))
+                    .route(web::post().to(handlers::import_channels)),
+            )
+            .route("/graph", web::get().to(handlers::export_graph))
+            .route("/ranked", web::get().to(handlers::get_ranked_channels))
+            .route("/collisions", web::get().to(handlers::get_collisions))
             .route("/similar", web::post().to(handlers::get_similar_channels))
+            .route("/crawl", web::post().to(handlers::crawl_channels))
+            .route("/{id}", web::delete().to(handlers::delete_channel))
+            .route("/{id}/merge", web::post().to(handlers::merge_channel))
             .route("/{id}/get-new-data", web::get().to(handlers::get_new_data))
+            .route("/{id}/history", web::get().to(handlers::get_history))
             .route("/{id}/category", web::put().to(handlers::update_category))
-            .route("/{id}/geo", web::put().to(handlers::update_geo)),
+            .route("/{id}/geo", web::put().to(handlers::update_geo))
+            .route("/{id}/tags", web::put().to(handlers::update_tags)),
     );
 }
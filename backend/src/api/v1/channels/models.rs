@@ -1,9 +1,80 @@
 use serde::Deserialize;
 
+use crate::{
+    database::{
+        graph::GraphFormat,
+        models::{ChannelFilter, ChannelSortField, SortOrder},
+        transfer::TransferFormat,
+    },
+    services::crawl::CrawlOptions,
+};
+
+pub const CRAWL_MAX_DEPTH: usize = 5;
+pub const CRAWL_MAX_CHANNELS: usize = 1000;
+
 #[derive(Deserialize)]
 pub struct ChannelQuery {
     pub category: Option<String>,
     pub geo: Option<String>,
+    pub min_growth_7d: Option<f64>,
+    pub max_growth_7d: Option<f64>,
+    pub min_growth_30d: Option<f64>,
+    pub max_growth_30d: Option<f64>,
+    pub search: Option<String>,
+    pub min_subscribers: Option<i64>,
+    pub max_subscribers: Option<i64>,
+    pub tag: Option<String>,
+    pub sort_by: Option<ChannelSortField>,
+    pub order: Option<SortOrder>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl ChannelQuery {
+    pub fn to_filter(&self) -> ChannelFilter {
+        ChannelFilter {
+            category: self.category.clone(),
+            geo: self.geo.clone(),
+            min_growth_7d: self.min_growth_7d,
+            max_growth_7d: self.max_growth_7d,
+            min_growth_30d: self.min_growth_30d,
+            max_growth_30d: self.max_growth_30d,
+            search: self.search.clone(),
+            min_subscribers: self.min_subscribers,
+            max_subscribers: self.max_subscribers,
+            tag: self.tag.clone(),
+            sort_by: self.sort_by,
+            order: self.order.unwrap_or_default(),
+            limit: self.limit,
+            offset: self.offset.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RankedQuery {
+    /// Comma-separated usernames; only these seeds' edges are scored.
+    pub seeds: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: TransferFormat,
+}
+
+#[derive(Deserialize)]
+pub struct GraphQuery {
+    #[serde(default)]
+    pub format: GraphFormat,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub format: TransferFormat,
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 #[derive(Deserialize)]
@@ -11,6 +82,54 @@ pub struct SimilarChannelRequest {
     pub channels_names: Vec<String>,
 }
 
+fn default_crawl_depth() -> usize {
+    2
+}
+
+fn default_crawl_max_channels() -> usize {
+    200
+}
+
+#[derive(Deserialize)]
+pub struct CrawlRequest {
+    pub channels_names: Vec<String>,
+    #[serde(default = "default_crawl_depth")]
+    pub max_depth: usize,
+    #[serde(default = "default_crawl_max_channels")]
+    pub max_channels: usize,
+    pub min_subscribers: Option<i64>,
+    /// Ask Telegram again for seeds that were already expanded.
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl CrawlRequest {
+    /// Checks the crawl limits against `CRAWL_MAX_DEPTH` and
+    /// `CRAWL_MAX_CHANNELS` and returns the offending field with a message.
+    pub fn validate_limits(&self) -> Result<(), (&'static str, String)> {
+        let limits = [
+            ("max_depth", self.max_depth, CRAWL_MAX_DEPTH),
+            ("max_channels", self.max_channels, CRAWL_MAX_CHANNELS),
+        ];
+
+        for (field, value, max) in limits {
+            if value > max {
+                return Err((field, format!("Must be at most {}, got {}", max, value)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_options(&self) -> CrawlOptions {
+        CrawlOptions {
+            max_depth: self.max_depth,
+            max_channels: self.max_channels,
+            min_subscribers: self.min_subscribers,
+            force: self.force,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct UpdateChannelCategoryRequest {
     pub category: String,
@@ -20,3 +139,13 @@ pub struct UpdateChannelCategoryRequest {
 pub struct UpdateChannelGeoRequest {
     pub geo: String,
 }
+
+#[derive(Deserialize)]
+pub struct UpdateChannelTagsRequest {
+    pub tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MergeChannelRequest {
+    pub source_id: i64,
+}
@@ -0,0 +1,112 @@
+use actix_web::{HttpResponse, web};
+use log::error;
+use serde_json::json;
+
+use crate::database::{ChannelRepository, RepositoryError};
+
+use super::models::SavedListRequest;
+
+fn list_error_response(error: RepositoryError) -> HttpResponse {
+    match error {
+        RepositoryError::NotFound(e) => HttpResponse::NotFound().json(json!({"error": e})),
+        RepositoryError::Conflict(e) => HttpResponse::Conflict().json(json!({"error": e})),
+        e => {
+            error!("Saved list operation failed: {}", e);
+            HttpResponse::InternalServerError().json(json!({"error": e.to_string()}))
+        }
+    }
+}
+
+/// Checks the name and that every channel id is in the catalog, returning the
+/// trimmed name.
+async fn validate_request(
+    db: &web::Data<dyn ChannelRepository>,
+    req: &SavedListRequest,
+) -> Result<String, HttpResponse> {
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "field": "name",
+            "error": "List name must not be empty"
+        })));
+    }
+
+    let mut unknown_ids = vec![];
+    for id in &req.channel_ids {
+        match db.get_channel_by_id(*id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => unknown_ids.push(*id),
+            Err(e) => return Err(list_error_response(e.into())),
+        }
+    }
+
+    if !unknown_ids.is_empty() {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "field": "channel_ids",
+            "error": "Unknown channel ids",
+            "channel_ids": unknown_ids
+        })));
+    }
+
+    Ok(name)
+}
+
+pub async fn get_lists(db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    match db.list_saved_lists().await {
+        Ok(lists) => HttpResponse::Ok().json(json!(lists)),
+        Err(e) => list_error_response(e.into()),
+    }
+}
+
+pub async fn get_list(id: web::Path<i64>, db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    let id = id.into_inner();
+    match db.get_saved_list(id).await {
+        Ok(Some(list)) => HttpResponse::Ok().json(json!(list)),
+        Ok(None) => list_error_response(RepositoryError::NotFound(format!(
+            "Saved list with id {} not found",
+            id
+        ))),
+        Err(e) => list_error_response(e.into()),
+    }
+}
+
+pub async fn create_list(
+    db: web::Data<dyn ChannelRepository>,
+    req: web::Json<SavedListRequest>,
+) -> HttpResponse {
+    let name = match validate_request(&db, &req).await {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+
+    match db.create_saved_list(&name, &req.channel_ids).await {
+        Ok(list) => HttpResponse::Created().json(json!(list)),
+        Err(e) => list_error_response(e),
+    }
+}
+
+pub async fn update_list(
+    id: web::Path<i64>,
+    db: web::Data<dyn ChannelRepository>,
+    req: web::Json<SavedListRequest>,
+) -> HttpResponse {
+    let name = match validate_request(&db, &req).await {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+
+    match db
+        .update_saved_list(id.into_inner(), &name, &req.channel_ids)
+        .await
+    {
+        Ok(list) => HttpResponse::Ok().json(json!(list)),
+        Err(e) => list_error_response(e),
+    }
+}
+
+pub async fn delete_list(id: web::Path<i64>, db: web::Data<dyn ChannelRepository>) -> HttpResponse {
+    match db.delete_saved_list(id.into_inner()).await {
+        Ok(()) => HttpResponse::Ok().json(json!({"status": "ok"})),
+        Err(e) => list_error_response(e),
+    }
+}
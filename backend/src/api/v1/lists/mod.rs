@@ -0,0 +1,14 @@
+use actix_web::web;
+mod handlers;
+mod models;
+
+pub fn routers(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/lists")
+            .route("/", web::get().to(handlers::get_lists))
+            .route("/", web::post().to(handlers::create_list))
+            .route("/{id}", web::get().to(handlers::get_list))
+            .route("/{id}", web::put().to(handlers::update_list))
+            .route("/{id}", web::delete().to(handlers::delete_list)),
+    );
+}
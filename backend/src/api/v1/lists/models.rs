@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct SavedListRequest {
+    pub name: String,
+    pub channel_ids: Vec<i64>,
+}
@@ -1,9 +1,17 @@
-use actix_web::web;
+use actix_web::{HttpRequest, HttpResponse, http::header::AUTHORIZATION, web};
+use serde_json::json;
 
+use crate::config::AppConfig;
+
+pub mod ads;
+mod backups;
+mod blacklist;
+mod categories;
 mod channels;
 mod geos;
-mod categories;
-pub mod ads;
+mod lists;
+mod rate_limits;
+mod telegram;
 
 pub fn routers_v1(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -12,5 +20,45 @@ pub fn routers_v1(cfg: &mut web::ServiceConfig) {
             .configure(geos::routers)
             .configure(categories::routers)
             .configure(ads::routers)
+            .configure(backups::routers)
+            .configure(lists::routers)
+            .configure(blacklist::routers)
+            .configure(rate_limits::routers)
+            .configure(telegram::routers),
     );
 }
+
+/// Compares without stopping at the first differing byte, so response times
+/// don't reveal how much of a guessed token was right.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Checks for `Authorization: Bearer <APP_ADMIN_TOKEN>`. Responds 403 while no
+/// admin token is configured, naming `action` as what it would allow, and 401
+/// for a missing or wrong token.
+pub(crate) fn authorize_admin(
+    req: &HttpRequest,
+    config: &AppConfig,
+    action: &str,
+) -> Result<(), HttpResponse> {
+    let Some(admin_token) = config.credentials.admin_token.as_deref() else {
+        return Err(HttpResponse::Forbidden()
+            .json(json!({"error": format!("Set APP_ADMIN_TOKEN to {}", action)})));
+    };
+    let authorized = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token_matches(token.trim(), admin_token));
+    if !authorized {
+        return Err(HttpResponse::Unauthorized().json(json!({"error": "Invalid admin token"})));
+    }
+    Ok(())
+}
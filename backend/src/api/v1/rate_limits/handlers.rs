@@ -0,0 +1,8 @@
+use actix_web::{HttpResponse, web};
+
+use crate::services::rate_limit::RateLimiters;
+
+/// Current limit and queue depth of every upstream.
+pub async fn get_rate_limits(rate_limiters: web::Data<RateLimiters>) -> HttpResponse {
+    HttpResponse::Ok().json(rate_limiters.status())
+}
@@ -0,0 +1,6 @@
+use actix_web::web;
+mod handlers;
+
+pub fn routers(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/rate-limits").route("/", web::get().to(handlers::get_rate_limits)));
+}
@@ -0,0 +1,52 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde_json::json;
+
+use crate::{
+    api::v1::authorize_admin,
+    config::AppConfig,
+    services::{
+        credentials::{CredentialStore, CredentialsUpdate},
+        telegram::TelegramService,
+    },
+    utils::text::TextUtils,
+};
+
+/// Reports whether the configured ads hash and cookies are valid, expired or
+/// missing; 502 when the ads platform couldn't be reached.
+pub async fn get_session(telegram_service: web::Data<TelegramService>) -> HttpResponse {
+    match telegram_service.check_session().await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => HttpResponse::BadGateway().json(json!({"error": e})),
+    }
+}
+
+/// Replaces the ads hash and cookies of the running service and saves them
+/// to the encrypted credentials file. Requires `Authorization: Bearer
+/// <APP_ADMIN_TOKEN>`; disabled while no admin token is configured.
+pub async fn update_credentials(
+    req: HttpRequest,
+    body: web::Json<CredentialsUpdate>,
+    config: web::Data<AppConfig>,
+    credential_store: web::Data<CredentialStore>,
+    telegram_service: web::Data<TelegramService>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, "update credentials") {
+        return response;
+    }
+
+    if body.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Provide at least one of hash, stel_ssid, stel_token or stel_owner"
+        }));
+    }
+
+    match credential_store.update(&telegram_service, &body).await {
+        Ok(credentials) => HttpResponse::Ok().json(json!({"missing": credentials.missing()})),
+        Err(e) => match TextUtils::parse_validation_error(&e) {
+            Some((field, message)) => {
+                HttpResponse::BadRequest().json(json!({"error": message, "field": field}))
+            }
+            None => HttpResponse::InternalServerError().json(json!({"error": e})),
+        },
+    }
+}
@@ -0,0 +1,20 @@
+use actix_web::{HttpResponse, web};
+use serde_json::json;
+mod handlers;
+
+/// `code` of the 401 sent when the ads platform rejected the session cookies.
+pub const SESSION_EXPIRED_CODE: &str = "session_expired";
+
+/// Response for a call that failed because the ads session expired, so the
+/// frontend can ask for new cookies instead of showing a generic error.
+pub fn session_expired_response(error: &str) -> HttpResponse {
+    HttpResponse::Unauthorized().json(json!({"error": error, "code": SESSION_EXPIRED_CODE}))
+}
+
+pub fn routers(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/telegram")
+            .route("/session", web::get().to(handlers::get_session))
+            .route("/credentials", web::put().to(handlers::update_credentials)),
+    );
+}
@@ -0,0 +1,42 @@
+use crate::{
+    config::DatabaseConfig,
+    database::{self, backup},
+};
+
+use super::BackupsCommand;
+
+pub async fn run(action: BackupsCommand, database_config: DatabaseConfig) -> Result<(), String> {
+    // A running server keeps the database in memory and would write its own
+    // state over a restored file, so it has to restore through the API.
+    let _lock = match action {
+        BackupsCommand::Restore { ref name } => Some(
+            backup::lock_database(&database_config.file_path).map_err(|e| {
+                format!(
+                    "{}; stop it or use POST /api/v1/backups/{}/restore",
+                    e, name
+                )
+            })?,
+        ),
+        BackupsCommand::List => None,
+    };
+    let db = database::connect(database_config).await?;
+
+    match action {
+        BackupsCommand::List => {
+            for backup in db.list_backups().await? {
+                println!(
+                    "{}\t{}\t{} bytes",
+                    backup.name,
+                    backup.created_at.to_rfc3339(),
+                    backup.size
+                );
+            }
+        }
+        BackupsCommand::Restore { name } => {
+            db.restore_backup(&name).await?;
+            println!("Restored database from '{}'", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    config::AppConfig,
+    database::{self, transfer::TransferFormat},
+    services::{
+        campaign::{self, PlanOptions},
+        rate_limit::RateLimiters,
+        telegram::TelegramService,
+    },
+};
+
+pub async fn run(
+    plan: PathBuf,
+    format: Option<TransferFormat>,
+    dry_run: bool,
+    delay_ms: u64,
+    config: AppConfig,
+) -> Result<(), String> {
+    let format = format.unwrap_or_else(|| match plan.extension().and_then(|e| e.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("csv") => TransferFormat::Csv,
+        _ => TransferFormat::Json,
+    });
+    let contents = tokio::fs::read(&plan)
+        .await
+        .map_err(|e| format!("Failed to read plan {:?}: {}", plan, e))?;
+    let records = campaign::parse_plan(&contents, format)?;
+
+    let db = database::connect(config.database.clone()).await?;
+    let rate_limiters = RateLimiters::new(&config.rate_limits);
+    let telegram_service = TelegramService::from_config(&config.telegram, &rate_limiters, None)?;
+    let options = PlanOptions {
+        dry_run,
+        delay: Duration::from_millis(delay_ms),
+    };
+    let report = campaign::run_plan(db.as_ref(), &telegram_service, records, &options).await?;
+
+    let output = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize campaign report: {}", e))?;
+    println!("{}", output);
+
+    if report.invalid > 0 {
+        return Err(format!(
+            "{} of {} rows are invalid",
+            report.invalid, report.total
+        ));
+    }
+    if report.failed > 0 {
+        return Err(format!("{} of {} ads failed", report.failed, report.total));
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use serde_json::Value;
+
+use crate::{
+    config::AppConfig,
+    services::credentials::{CredentialStore, CredentialsUpdate},
+};
+
+pub async fn run(
+    update: CredentialsUpdate,
+    server: String,
+    offline: bool,
+    config: AppConfig,
+) -> Result<(), String> {
+    if update.is_empty() {
+        return Err(
+            "Pass at least one of --hash, --stel-ssid, --stel-token or --stel-owner".to_string(),
+        );
+    }
+
+    if offline {
+        // The config already holds the stored credentials, if there are any.
+        let credentials = update.apply_to(&config.telegram.ads)?;
+        let store = CredentialStore::new(&config.credentials);
+        store.save(&credentials)?;
+        println!(
+            "Saved credentials to {:?}; they are used from the next start",
+            config.credentials.path
+        );
+        return Ok(());
+    }
+
+    let admin_token = config
+        .credentials
+        .admin_token
+        .ok_or("APP_ADMIN_TOKEN is required to update a running backend")?;
+    let url = format!(
+        "{}/api/v1/telegram/credentials",
+        server.trim_end_matches('/')
+    );
+    // The same timeouts and proxy as the backend's own calls.
+    let client = config.telegram.http.build_client()?;
+    let response = client
+        .put(&url)
+        .bearer_auth(admin_token)
+        .json(&update)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        let error = body["error"].as_str().unwrap_or("no error message");
+        return Err(format!("{} {}", status.as_u16(), error));
+    }
+
+    println!("Updated credentials of the backend at {}", server);
+    if let Some(missing) = body["missing"].as_array().filter(|m| !m.is_empty()) {
+        println!("Still missing: {}", Value::from(missing.clone()));
+    }
+    Ok(())
+}
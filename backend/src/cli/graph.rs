@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use crate::{
+    config::DatabaseConfig,
+    database::{
+        self,
+        graph::{GraphFormat, build_graph, render_graph},
+        models::ChannelFilter,
+    },
+};
+
+pub async fn run(
+    format: GraphFormat,
+    category: Option<String>,
+    geo: Option<String>,
+    output: Option<PathBuf>,
+    database_config: DatabaseConfig,
+) -> Result<(), String> {
+    let db = database::connect(database_config).await?;
+    let filter = ChannelFilter {
+        category,
+        geo,
+        ..Default::default()
+    };
+
+    let graph = build_graph(db.as_ref(), &filter).await?;
+    let rendered = render_graph(&graph, format);
+
+    match output {
+        Some(path) => {
+            tokio::fs::write(&path, rendered)
+                .await
+                .map_err(|e| format!("Failed to write graph to {:?}: {}", path, e))?;
+            eprintln!(
+                "Wrote {} nodes and {} edges to {:?}",
+                graph.nodes.len(),
+                graph.edges.len(),
+                path
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use crate::{
+    config::{DatabaseBackend, DatabaseConfig},
+    database::{self, migration},
+};
+
+pub async fn run(
+    source: PathBuf,
+    backend: Option<DatabaseBackend>,
+    target: Option<PathBuf>,
+    database_config: DatabaseConfig,
+) -> Result<(), String> {
+    let target_config = match (backend, target) {
+        (None, None) => database_config,
+        (backend, target) => {
+            let backend = backend.unwrap_or(database_config.backend);
+            DatabaseConfig {
+                backend,
+                file_path: target.unwrap_or_else(|| backend.default_file_path()),
+                ..database_config
+            }
+        }
+    };
+
+    if target_config.backend == DatabaseBackend::Json && target_config.file_path == source {
+        return Err(format!(
+            "Source and target are the same JSON file: {:?}",
+            source
+        ));
+    }
+
+    let store = database::connect(target_config).await?;
+    let report = migration::migrate_json_file(&source, store.as_ref()).await?;
+
+    let output = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize migration report: {}", e))?;
+    println!("{}", output);
+    Ok(())
+}
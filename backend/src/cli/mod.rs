@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::{
+    config::{AppConfig, DatabaseBackend},
+    database::{graph::GraphFormat, transfer::TransferFormat},
+    services::{campaign::DEFAULT_PLAN_DELAY_MS, credentials::CredentialsUpdate},
+};
+
+mod backups;
+mod campaign;
+mod credentials;
+mod graph;
+mod migrate;
+
+#[derive(Parser, Debug)]
+#[command(name = "backend", about = "Telegram Ads Manager backend")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Copy channels from a channels.json file into a channel store
+    Migrate {
+        /// JSON database file to read channels from
+        #[arg(long, default_value = "channels.json")]
+        source: PathBuf,
+        /// Target backend, defaults to APP_DATABASE_BACKEND
+        #[arg(long, value_parser = DatabaseBackend::from_env_value)]
+        backend: Option<DatabaseBackend>,
+        /// Target file, defaults to APP_DATABASE_PATH or the backend default
+        #[arg(long)]
+        target: Option<PathBuf>,
+    },
+    /// Write the channel similarity graph for Gephi or Graphviz
+    Graph {
+        /// Output format: graphml, gexf or dot
+        #[arg(long, default_value = "graphml", value_parser = GraphFormat::from_name)]
+        format: GraphFormat,
+        /// Only include channels in this category
+        #[arg(long)]
+        category: Option<String>,
+        /// Only include channels in this geo
+        #[arg(long)]
+        geo: Option<String>,
+        /// File to write to, defaults to stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Create one ad per row of a CSV or JSON campaign plan
+    Campaign {
+        /// Plan file; columns are title, text, promote_url, cpm,
+        /// views_per_user, budget, daily_budget, active, channels and method
+        plan: PathBuf,
+        /// csv or json, defaults to the file extension
+        #[arg(long, value_parser = TransferFormat::from_name)]
+        format: Option<TransferFormat>,
+        /// Only validate the plan against the catalog
+        #[arg(long)]
+        dry_run: bool,
+        /// Milliseconds to wait between two submitted ads
+        #[arg(long, default_value_t = DEFAULT_PLAN_DELAY_MS)]
+        delay_ms: u64,
+    },
+    /// Replace the Telegram Ads hash and cookies; omitted values are kept
+    Credentials {
+        #[arg(long)]
+        hash: Option<String>,
+        #[arg(long)]
+        stel_ssid: Option<String>,
+        #[arg(long)]
+        stel_token: Option<String>,
+        #[arg(long)]
+        stel_owner: Option<String>,
+        /// Running backend to update, authenticated with APP_ADMIN_TOKEN
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        server: String,
+        /// Only write the credentials file, for a backend that isn't running
+        #[arg(long)]
+        offline: bool,
+    },
+    /// List or restore backups of the JSON database
+    Backups {
+        #[command(subcommand)]
+        action: BackupsCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupsCommand {
+    /// List available backups, newest first
+    List,
+    /// Roll the database back to a backup from `backups list`
+    Restore { name: String },
+}
+
+pub async fn run(command: Command, config: AppConfig) -> Result<(), String> {
+    match command {
+        Command::Migrate {
+            source,
+            backend,
+            target,
+        } => migrate::run(source, backend, target, config.database).await,
+        Command::Graph {
+            format,
+            category,
+            geo,
+            output,
+        } => graph::run(format, category, geo, output, config.database).await,
+        Command::Campaign {
+            plan,
+            format,
+            dry_run,
+            delay_ms,
+        } => campaign::run(plan, format, dry_run, delay_ms, config).await,
+        Command::Credentials {
+            hash,
+            stel_ssid,
+            stel_token,
+            stel_owner,
+            server,
+            offline,
+        } => {
+            let update = CredentialsUpdate {
+                hash,
+                stel_ssid,
+                stel_token,
+                stel_owner,
+            };
+            credentials::run(update, server, offline, config).await
+        }
+        Command::Backups { action } => backups::run(action, config.database).await,
+    }
+}
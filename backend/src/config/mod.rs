@@ -1,5 +1,5 @@
-mod config;
 mod models;
+mod settings;
 
-pub use config::*;
 pub use models::*;
+pub use settings::*;
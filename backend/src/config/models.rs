@@ -2,7 +2,34 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseBackend {
+    Json,
+    Sqlite,
+}
+
+impl DatabaseBackend {
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "json" => Ok(DatabaseBackend::Json),
+            "sqlite" => Ok(DatabaseBackend::Sqlite),
+            other => Err(format!("Unknown database backend: '{}'", other)),
+        }
+    }
+
+    pub fn default_file_path(&self) -> PathBuf {
+        match self {
+            DatabaseBackend::Json => PathBuf::from("channels.json"),
+            DatabaseBackend::Sqlite => PathBuf::from("channels.db"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
+    pub backend: DatabaseBackend,
     pub file_path: PathBuf,
+    pub backups: usize,
+    pub backup_interval_secs: u64,
 }
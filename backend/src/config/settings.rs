@@ -0,0 +1,162 @@
+use std::env;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    credentials::{CredentialStore, CredentialsConfig},
+    http::{DEFAULT_USER_AGENT, HttpClientConfig},
+    openai::OpenAiConfig,
+    rate_limit::{RateLimit, RateLimitConfig},
+    telegram::{AdsCredentials, MediaUploadMode, TelegramConfig},
+};
+
+use super::models::{DatabaseBackend, DatabaseConfig};
+
+/// Reads `name` with surrounding whitespace trimmed; unset or blank gives
+/// `default`.
+fn parse_env<T: FromStr>(name: &str, default: T) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid {}: {}", name, e)),
+        _ => Ok(default),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub database: DatabaseConfig,
+    pub log_level: String,
+    pub geos: Vec<String>,
+    pub categories: Vec<String>,
+    pub telegram: TelegramConfig,
+    pub openai: OpenAiConfig,
+    pub rate_limits: RateLimitConfig,
+    pub credentials: CredentialsConfig,
+}
+
+impl AppConfig {
+    pub fn new() -> Result<Self, String> {
+        let backend =
+            DatabaseBackend::from_env_value(&env::var("APP_DATABASE_BACKEND").unwrap_or_default())?;
+
+        // Telegram and OpenAI clients share the same timeouts and proxy.
+        let http = HttpClientConfig {
+            connect_timeout_secs: parse_env("APP_HTTP_CONNECT_TIMEOUT", 10)?,
+            read_timeout_secs: parse_env("APP_HTTP_READ_TIMEOUT", 30)?,
+            proxy: env::var("APP_HTTP_PROXY")
+                .ok()
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty()),
+            user_agent: parse_env("APP_HTTP_USER_AGENT", DEFAULT_USER_AGENT.to_string())?,
+        };
+
+        let mut config = Self {
+            database: DatabaseConfig {
+                backend,
+                file_path: parse_env("APP_DATABASE_PATH", backend.default_file_path())?,
+                backups: parse_env("APP_DATABASE_BACKUPS", 5)?,
+                backup_interval_secs: parse_env("APP_DATABASE_BACKUP_INTERVAL", 3600)?,
+            },
+            log_level: "INFO".to_string(),
+            geos: env::var("APP_AVAILABLE_GEOS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .collect(),
+            categories: env::var("APP_AVAILABLE_CATEGORIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .collect(),
+            telegram: TelegramConfig {
+                bot_token: env::var("APP_TELEGRAM_BOT_TOKEN")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .collect(),
+                ads: AdsCredentials {
+                    hash: env::var("APP_TELEGRAM_ADS_HASH")
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                    stel_ssid: env::var("APP_TELEGRAM_STEL_SSID")
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                    stel_token: env::var("APP_TELEGRAM_STEL_TOKEN")
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                    stel_owner: env::var("APP_TELEGRAM_STEL_OWNER")
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                },
+                media_upload: MediaUploadMode::from_env_value(
+                    &env::var("APP_TELEGRAM_MEDIA_UPLOAD").unwrap_or_default(),
+                )?,
+                http: http.clone(),
+            },
+            openai: OpenAiConfig {
+                api_key: env::var("APP_OPENAI_API_KEY")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .collect(),
+                model: env::var("APP_OPENAI_API_MODEL")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .collect(),
+                http,
+            },
+            rate_limits: RateLimitConfig {
+                bot_api: RateLimit::from_env_value(
+                    &env::var("APP_RATE_LIMIT_BOT_API").unwrap_or_default(),
+                    20.0,
+                )
+                .map_err(|e| format!("APP_RATE_LIMIT_BOT_API: {}", e))?,
+                ads_api: RateLimit::from_env_value(
+                    &env::var("APP_RATE_LIMIT_ADS_API").unwrap_or_default(),
+                    2.0,
+                )
+                .map_err(|e| format!("APP_RATE_LIMIT_ADS_API: {}", e))?,
+                openai: RateLimit::from_env_value(
+                    &env::var("APP_RATE_LIMIT_OPENAI").unwrap_or_default(),
+                    5.0,
+                )
+                .map_err(|e| format!("APP_RATE_LIMIT_OPENAI: {}", e))?,
+            },
+            credentials: CredentialsConfig {
+                path: parse_env("APP_CREDENTIALS_PATH", PathBuf::from("credentials.enc"))?,
+                key: env::var("APP_CREDENTIALS_KEY")
+                    .ok()
+                    .filter(|k| !k.is_empty()),
+                admin_token: env::var("APP_ADMIN_TOKEN")
+                    .ok()
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty()),
+            },
+        };
+
+        // Credentials updated at runtime outlive the env vars they replaced.
+        if let Some(ads) = CredentialStore::new(&config.credentials).load()? {
+            info!(
+                "Using Telegram Ads credentials from {:?}",
+                config.credentials.path
+            );
+            config.telegram.ads = ads;
+        }
+
+        Ok(config)
+    }
+}
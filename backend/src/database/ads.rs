@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use super::models::AdRecord;
+
+/// Combines the stored ads with a fresh listing from the ads account. Listed
+/// ads take the new statistics but keep their `first_seen_at`; stored ads
+/// missing from the listing keep their last statistics and get `removed_at`
+/// set, so deleted campaigns stay in the history. An ad that shows up again
+/// is no longer marked as removed.
+pub fn merge_synced_ads(
+    stored: Vec<AdRecord>,
+    fetched: Vec<AdRecord>,
+    synced_at: DateTime<Utc>,
+) -> Vec<AdRecord> {
+    let mut stored: HashMap<i64, AdRecord> = stored.into_iter().map(|ad| (ad.id, ad)).collect();
+
+    let mut merged: Vec<AdRecord> = fetched
+        .into_iter()
+        .map(|mut ad| {
+            if let Some(previous) = stored.remove(&ad.id) {
+                ad.first_seen_at = previous.first_seen_at;
+            }
+            ad.synced_at = synced_at;
+            ad.removed_at = None;
+            ad
+        })
+        .collect();
+
+    merged.extend(stored.into_values().map(|mut ad| {
+        ad.removed_at.get_or_insert(synced_at);
+        ad
+    }));
+
+    sort_ads(&mut merged);
+    merged
+}
+
+/// Newest ads first, ads removed from the account after the live ones.
+pub fn sort_ads(ads: &mut [AdRecord]) {
+    ads.sort_by(|a, b| {
+        a.removed_at
+            .is_some()
+            .cmp(&b.removed_at.is_some())
+            .then(b.id.cmp(&a.id))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, day, 0, 0, 0).unwrap()
+    }
+
+    fn ad(id: i64, views: i64, seen_on: u32) -> AdRecord {
+        AdRecord {
+            id,
+            title: None,
+            text: None,
+            promote_url: None,
+            status: "Active".to_string(),
+            views,
+            clicks: 0,
+            spent: 0.0,
+            cpm: 1.0,
+            first_seen_at: at(seen_on),
+            synced_at: at(seen_on),
+            removed_at: None,
+        }
+    }
+
+    #[test]
+    fn listed_ads_take_new_stats_and_keep_first_seen() {
+        let merged = merge_synced_ads(vec![ad(1, 10, 1)], vec![ad(1, 50, 5)], at(5));
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].views, 50);
+        assert_eq!(merged[0].first_seen_at, at(1));
+        assert_eq!(merged[0].synced_at, at(5));
+    }
+
+    #[test]
+    fn missing_ads_are_marked_removed_once() {
+        let merged = merge_synced_ads(vec![ad(1, 10, 1)], vec![], at(5));
+        assert_eq!(merged[0].removed_at, Some(at(5)));
+        assert_eq!(merged[0].views, 10);
+
+        let merged = merge_synced_ads(merged, vec![], at(6));
+        assert_eq!(merged[0].removed_at, Some(at(5)));
+    }
+
+    #[test]
+    fn relisted_ads_are_no_longer_removed() {
+        let mut removed = ad(1, 10, 1);
+        removed.removed_at = Some(at(2));
+
+        let merged = merge_synced_ads(vec![removed], vec![ad(1, 20, 3)], at(3));
+
+        assert_eq!(merged[0].removed_at, None);
+    }
+
+    #[test]
+    fn live_ads_sort_newest_first_before_removed_ones() {
+        let mut removed = ad(9, 0, 1);
+        removed.removed_at = Some(at(2));
+        let mut ads = vec![ad(1, 0, 1), removed, ad(3, 0, 1)];
+
+        sort_ads(&mut ads);
+
+        let ids: Vec<i64> = ads.iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![3, 1, 9]);
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::{info, warn};
+use serde::Serialize;
+use tokio::{fs, io::AsyncWriteExt};
+
+const BACKUP_SUFFIX: &str = ".bak";
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+fn file_name(file_path: &Path) -> String {
+    file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn parent_dir(file_path: &Path) -> PathBuf {
+    match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+fn parse_backup_name(file_path: &Path, name: &str) -> Option<DateTime<Utc>> {
+    let timestamp = name
+        .strip_prefix(&format!("{}.", file_name(file_path)))?
+        .strip_suffix(BACKUP_SUFFIX)?;
+
+    NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT)
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+/// Writes `contents` to a temp file next to `file_path`, fsyncs it and renames
+/// it over the original, so readers see either the old or the new file.
+pub async fn write_atomic(file_path: &Path, contents: &[u8]) -> Result<(), String> {
+    let tmp_path = parent_dir(file_path).join(format!(".{}.tmp", file_name(file_path)));
+
+    let mut file = fs::File::create(&tmp_path)
+        .await
+        .map_err(|e| format!("Failed to create temp DB file: {}", e))?;
+    file.write_all(contents)
+        .await
+        .map_err(|e| format!("Failed to write temp DB file: {}", e))?;
+    file.sync_all()
+        .await
+        .map_err(|e| format!("Failed to fsync temp DB file: {}", e))?;
+    drop(file);
+
+    fs::rename(&tmp_path, file_path)
+        .await
+        .map_err(|e| format!("Failed to move temp DB file into place: {}", e))?;
+
+    // Persist the rename itself; not every platform allows opening a directory.
+    if let Ok(dir) = fs::File::open(parent_dir(file_path)).await {
+        dir.sync_all().await.ok();
+    }
+
+    Ok(())
+}
+
+pub async fn list_backups(file_path: &Path) -> Result<Vec<BackupInfo>, String> {
+    let mut entries = match fs::read_dir(parent_dir(file_path)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("Failed to read backups directory: {}", e)),
+    };
+
+    let mut backups = vec![];
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("Failed to read backups directory: {}", e))?
+    {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some(created_at) = parse_backup_name(file_path, &name) {
+            let size = entry.metadata().await.map(|m| m.len()).unwrap_or_default();
+            backups.push(BackupInfo {
+                name,
+                created_at,
+                size,
+            });
+        }
+    }
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(backups)
+}
+
+/// Copies the current DB file to a timestamped backup and removes the oldest
+/// backups so that at most `keep` remain.
+pub async fn create_backup(file_path: &Path, keep: usize) -> Result<Option<BackupInfo>, String> {
+    if keep == 0 || !file_path.exists() {
+        return Ok(None);
+    }
+
+    let created_at = Utc::now();
+    let name = format!(
+        "{}.{}{}",
+        file_name(file_path),
+        created_at.format(BACKUP_TIMESTAMP_FORMAT),
+        BACKUP_SUFFIX
+    );
+    let backup_path = parent_dir(file_path).join(&name);
+
+    let size = fs::copy(file_path, &backup_path)
+        .await
+        .map_err(|e| format!("Failed to create DB backup: {}", e))?;
+    info!("Database backup created: {:?}", backup_path);
+
+    for old in list_backups(file_path).await?.into_iter().skip(keep) {
+        let old_path = parent_dir(file_path).join(&old.name);
+        if let Err(e) = fs::remove_file(&old_path).await {
+            warn!("Failed to remove old DB backup {:?}: {}", old_path, e);
+        }
+    }
+
+    Ok(Some(BackupInfo {
+        name,
+        created_at,
+        size,
+    }))
+}
+
+/// Exclusive lock on a DB file, held by the server for as long as it runs.
+/// The OS releases it when the process exits, so a crash leaves no stale lock.
+#[derive(Debug)]
+pub struct DatabaseLock {
+    _file: std::fs::File,
+}
+
+/// Locks `file_path` against other processes, failing if one already holds it.
+pub fn lock_database(file_path: &Path) -> Result<DatabaseLock, String> {
+    let lock_path = parent_dir(file_path).join(format!(".{}.lock", file_name(file_path)));
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open DB lock file {:?}: {}", lock_path, e))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(DatabaseLock { _file: file }),
+        Err(std::fs::TryLockError::WouldBlock) => Err(format!(
+            "Database file {:?} is in use by a running backend",
+            file_path
+        )),
+        Err(std::fs::TryLockError::Error(e)) => {
+            Err(format!("Failed to lock DB file {:?}: {}", file_path, e))
+        }
+    }
+}
+
+/// Resolves a backup name from `list_backups` to its path, or `None` for
+/// anything that isn't one of this file's backups.
+pub async fn backup_path(file_path: &Path, name: &str) -> Result<Option<PathBuf>, String> {
+    Ok(list_backups(file_path)
+        .await?
+        .into_iter()
+        .find(|b| b.name == name)
+        .map(|b| parent_dir(file_path).join(b.name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn temp_file(name: &str) -> PathBuf {
+        temp_dir(&format!("backup-{}", name)).join("channels.json")
+    }
+
+    fn dir_entries(file_path: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(parent_dir(file_path))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn write_atomic_replaces_file_without_leaving_temp_file() {
+        let file_path = temp_file("atomic");
+        std::fs::write(&file_path, "old").unwrap();
+
+        write_atomic(&file_path, b"new").await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "new");
+        assert_eq!(dir_entries(&file_path), vec!["channels.json"]);
+
+        std::fs::remove_dir_all(parent_dir(&file_path)).ok();
+    }
+
+    #[tokio::test]
+    async fn create_backup_keeps_only_newest_backups() {
+        let file_path = temp_file("rotation");
+        let mut created = vec![];
+        for i in 0..4 {
+            std::fs::write(&file_path, format!("version {}", i)).unwrap();
+            created.push(create_backup(&file_path, 2).await.unwrap().unwrap().name);
+            // Backup names have millisecond resolution.
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let backups = list_backups(&file_path).await.unwrap();
+        let names: Vec<&str> = backups.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec![created[3].as_str(), created[2].as_str()]);
+
+        let newest = backup_path(&file_path, &created[3]).await.unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(newest).unwrap(), "version 3");
+
+        std::fs::remove_dir_all(parent_dir(&file_path)).ok();
+    }
+
+    #[tokio::test]
+    async fn create_backup_skips_missing_file_and_zero_keep() {
+        let file_path = temp_file("skip");
+        assert!(create_backup(&file_path, 3).await.unwrap().is_none());
+
+        std::fs::write(&file_path, "data").unwrap();
+        assert!(create_backup(&file_path, 0).await.unwrap().is_none());
+        assert!(list_backups(&file_path).await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(parent_dir(&file_path)).ok();
+    }
+
+    #[tokio::test]
+    async fn backup_path_rejects_names_that_are_not_backups() {
+        let file_path = temp_file("path");
+        std::fs::write(&file_path, "data").unwrap();
+        let backup = create_backup(&file_path, 3).await.unwrap().unwrap();
+
+        assert!(
+            backup_path(&file_path, &backup.name)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            backup_path(&file_path, "channels.json")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            backup_path(&file_path, "../channels.json")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        std::fs::remove_dir_all(parent_dir(&file_path)).ok();
+    }
+
+    #[test]
+    fn lock_database_is_exclusive_until_dropped() {
+        let file_path = temp_file("lock");
+
+        let lock = lock_database(&file_path).unwrap();
+        assert!(lock_database(&file_path).is_err());
+        drop(lock);
+        assert!(lock_database(&file_path).is_ok());
+
+        std::fs::remove_dir_all(parent_dir(&file_path)).ok();
+    }
+}
@@ -0,0 +1,357 @@
+use std::{collections::HashSet, fmt::Write};
+
+use serde::Deserialize;
+
+use super::{
+    ChannelRepository,
+    models::{ChannelData, ChannelFilter},
+    relevance::edge_strength,
+};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Graphml,
+    Gexf,
+    Dot,
+}
+
+impl GraphFormat {
+    pub fn from_name(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "graphml" => Ok(GraphFormat::Graphml),
+            "gexf" => Ok(GraphFormat::Gexf),
+            "dot" => Ok(GraphFormat::Dot),
+            other => Err(format!("Unknown graph format: '{}'", other)),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Graphml => "graphml",
+            GraphFormat::Gexf => "gexf",
+            GraphFormat::Dot => "dot",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Graphml | GraphFormat::Gexf => "application/xml; charset=utf-8",
+            GraphFormat::Dot => "text/vnd.graphviz; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GraphEdge {
+    pub source: i64,
+    pub target: i64,
+    pub weight: f64,
+}
+
+/// Channels as nodes and "similar to" relations as directed edges from the
+/// seed to the channel Telegram suggested for it.
+#[derive(Debug)]
+pub struct SimilarityGraph {
+    pub nodes: Vec<ChannelData>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Builds the graph of the channels matching `filter`. Edges are kept only
+/// when both ends pass the filter, so the graph never points at missing nodes.
+pub async fn build_graph(
+    db: &dyn ChannelRepository,
+    filter: &ChannelFilter,
+) -> Result<SimilarityGraph, String> {
+    let nodes = db.filter_channels(filter).await?.channels;
+    let node_ids: HashSet<i64> = nodes.iter().map(|c| c.id).collect();
+
+    let mut edges = vec![];
+    for (seed_id, expansion) in db.list_crawl_expansions().await? {
+        if !node_ids.contains(&seed_id) {
+            continue;
+        }
+        let list_len = expansion.similar_ids.len();
+        for (position, channel_id) in expansion.similar_ids.iter().enumerate() {
+            if *channel_id != seed_id && node_ids.contains(channel_id) {
+                edges.push(GraphEdge {
+                    source: seed_id,
+                    target: *channel_id,
+                    weight: edge_strength(position, list_len),
+                });
+            }
+        }
+    }
+
+    Ok(SimilarityGraph { nodes, edges })
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn node_label(channel: &ChannelData) -> &str {
+    channel.title.as_deref().unwrap_or(&channel.username)
+}
+
+/// Text attributes shared by every format; subscribers are written
+/// separately since they are numeric.
+fn node_attributes(channel: &ChannelData) -> [(&'static str, Option<&str>); 3] {
+    [
+        ("username", Some(channel.username.as_str())),
+        ("category", channel.category.as_deref()),
+        ("geo", channel.geo.as_deref()),
+    ]
+}
+
+fn render_graphml(graph: &SimilarityGraph) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for key in ["label", "username", "category", "geo"] {
+        let _ = writeln!(
+            out,
+            "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"string\"/>",
+            key
+        );
+    }
+    out.push_str(
+        "  <key id=\"subscribers\" for=\"node\" attr.name=\"subscribers\" attr.type=\"long\"/>\n",
+    );
+    out.push_str("  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n");
+    out.push_str("  <graph id=\"similarity\" edgedefault=\"directed\">\n");
+
+    for channel in &graph.nodes {
+        let _ = writeln!(out, "    <node id=\"{}\">", channel.id);
+        let _ = writeln!(
+            out,
+            "      <data key=\"label\">{}</data>",
+            xml_escape(node_label(channel))
+        );
+        for (key, value) in node_attributes(channel) {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "      <data key=\"{}\">{}</data>",
+                    key,
+                    xml_escape(value)
+                );
+            }
+        }
+        if let Some(subscribers) = channel.subscribers {
+            let _ = writeln!(
+                out,
+                "      <data key=\"subscribers\">{}</data>",
+                subscribers
+            );
+        }
+        out.push_str("    </node>\n");
+    }
+
+    for edge in &graph.edges {
+        let _ = writeln!(
+            out,
+            "    <edge source=\"{}\" target=\"{}\"><data key=\"weight\">{}</data></edge>",
+            edge.source, edge.target, edge.weight
+        );
+    }
+
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn render_gexf(graph: &SimilarityGraph) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n");
+    out.push_str("  <graph mode=\"static\" defaultedgetype=\"directed\">\n");
+    out.push_str("    <attributes class=\"node\">\n");
+    for key in ["username", "category", "geo"] {
+        let _ = writeln!(
+            out,
+            "      <attribute id=\"{0}\" title=\"{0}\" type=\"string\"/>",
+            key
+        );
+    }
+    out.push_str("      <attribute id=\"subscribers\" title=\"subscribers\" type=\"long\"/>\n");
+    out.push_str("    </attributes>\n");
+
+    out.push_str("    <nodes>\n");
+    for channel in &graph.nodes {
+        let _ = writeln!(
+            out,
+            "      <node id=\"{}\" label=\"{}\">",
+            channel.id,
+            xml_escape(node_label(channel))
+        );
+        out.push_str("        <attvalues>\n");
+        for (key, value) in node_attributes(channel) {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "          <attvalue for=\"{}\" value=\"{}\"/>",
+                    key,
+                    xml_escape(value)
+                );
+            }
+        }
+        if let Some(subscribers) = channel.subscribers {
+            let _ = writeln!(
+                out,
+                "          <attvalue for=\"subscribers\" value=\"{}\"/>",
+                subscribers
+            );
+        }
+        out.push_str("        </attvalues>\n");
+        out.push_str("      </node>\n");
+    }
+    out.push_str("    </nodes>\n");
+
+    out.push_str("    <edges>\n");
+    for (index, edge) in graph.edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\"/>",
+            index, edge.source, edge.target, edge.weight
+        );
+    }
+    out.push_str("    </edges>\n");
+
+    out.push_str("  </graph>\n</gexf>\n");
+    out
+}
+
+fn render_dot(graph: &SimilarityGraph) -> String {
+    let mut out = String::from("digraph similarity {\n");
+
+    for channel in &graph.nodes {
+        let mut attributes = vec![format!("label=\"{}\"", dot_escape(node_label(channel)))];
+        for (key, value) in node_attributes(channel) {
+            if let Some(value) = value {
+                attributes.push(format!("{}=\"{}\"", key, dot_escape(value)));
+            }
+        }
+        if let Some(subscribers) = channel.subscribers {
+            attributes.push(format!("subscribers={}", subscribers));
+        }
+        let _ = writeln!(out, "  \"{}\" [{}];", channel.id, attributes.join(", "));
+    }
+
+    for edge in &graph.edges {
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [weight={}];",
+            edge.source, edge.target, edge.weight
+        );
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+pub fn render_graph(graph: &SimilarityGraph, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Graphml => render_graphml(graph),
+        GraphFormat::Gexf => render_gexf(graph),
+        GraphFormat::Dot => render_dot(graph),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::models::CrawlExpansion, test_support::json_db};
+    use chrono::Utc;
+
+    fn channel(id: i64, username: &str, category: Option<&str>) -> ChannelData {
+        ChannelData {
+            category: category.map(str::to_string),
+            ..crate::test_support::channel(id, username)
+        }
+    }
+
+    #[tokio::test]
+    async fn build_graph_keeps_edges_between_filtered_nodes() {
+        let (db, dir) = json_db("graph").await;
+
+        db.add_channel(channel(1, "one", Some("crypto")))
+            .await
+            .unwrap();
+        db.add_channel(channel(2, "two", Some("crypto")))
+            .await
+            .unwrap();
+        db.add_channel(channel(3, "three", Some("news")))
+            .await
+            .unwrap();
+        db.save_crawl_expansion(
+            1,
+            CrawlExpansion {
+                expanded_at: Utc::now(),
+                similar_ids: vec![3, 2, 1],
+            },
+        )
+        .await
+        .unwrap();
+
+        let graph = build_graph(
+            &db,
+            &ChannelFilter {
+                category: Some("crypto".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(graph.nodes.len(), 2);
+        let edges: Vec<(i64, i64, f64)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source, e.target, e.weight))
+            .collect();
+        assert_eq!(edges, vec![(1, 2, 2.0 / 3.0)]);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn renderers_escape_labels() {
+        let mut node = channel(1, "one", None);
+        node.title = Some("Tom & \"Jerry\"".to_string());
+        let graph = SimilarityGraph {
+            nodes: vec![node, channel(2, "two", None)],
+            edges: vec![GraphEdge {
+                source: 1,
+                target: 2,
+                weight: 1.0,
+            }],
+        };
+
+        let graphml = render_graph(&graph, GraphFormat::Graphml);
+        assert!(graphml.contains("<data key=\"label\">Tom &amp; &quot;Jerry&quot;</data>"));
+        assert!(graphml.contains("<edge source=\"1\" target=\"2\">"));
+
+        let gexf = render_graph(&graph, GraphFormat::Gexf);
+        assert!(gexf.contains("label=\"Tom &amp; &quot;Jerry&quot;\""));
+
+        let dot = render_graph(&graph, GraphFormat::Dot);
+        assert!(dot.contains("label=\"Tom & \\\"Jerry\\\"\""));
+        assert!(dot.contains("\"1\" -> \"2\" [weight=1];"));
+    }
+
+    #[test]
+    fn format_names_are_case_insensitive() {
+        assert_eq!(GraphFormat::from_name(" GEXF ").unwrap(), GraphFormat::Gexf);
+        assert!(GraphFormat::from_name("csv").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::Duration;
+use serde::Serialize;
+
+use super::models::{ChannelFilter, SubscriberSnapshot};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ChannelGrowth {
+    pub growth_7d: Option<f64>,
+    pub growth_30d: Option<f64>,
+}
+
+impl ChannelGrowth {
+    pub fn from_history(history: &[SubscriberSnapshot]) -> Self {
+        Self {
+            growth_7d: growth_rate(history, Duration::days(7)),
+            growth_30d: growth_rate(history, Duration::days(30)),
+        }
+    }
+
+    pub fn matches(&self, filter: &ChannelFilter) -> bool {
+        fn in_bounds(value: Option<f64>, min: Option<f64>, max: Option<f64>) -> bool {
+            match value {
+                Some(v) => min.is_none_or(|m| v >= m) && max.is_none_or(|m| v <= m),
+                None => min.is_none() && max.is_none(),
+            }
+        }
+
+        in_bounds(self.growth_7d, filter.min_growth_7d, filter.max_growth_7d)
+            && in_bounds(
+                self.growth_30d,
+                filter.min_growth_30d,
+                filter.max_growth_30d,
+            )
+    }
+}
+
+/// Relative change between the latest observation and the last one taken at
+/// least `window` before it, e.g. `0.1` for +10%. `None` until the history
+/// covers the whole window.
+pub fn growth_rate(history: &[SubscriberSnapshot], window: Duration) -> Option<f64> {
+    let latest = history.iter().max_by_key(|s| s.observed_at)?;
+    let window_start = latest.observed_at - window;
+
+    let baseline = history
+        .iter()
+        .filter(|s| s.observed_at <= window_start)
+        .max_by_key(|s| s.observed_at)?;
+
+    if baseline.subscribers <= 0 {
+        return None;
+    }
+
+    Some((latest.subscribers - baseline.subscribers) as f64 / baseline.subscribers as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+
+    fn snapshot(days_ago: i64, subscribers: i64) -> SubscriberSnapshot {
+        let now = DateTime::parse_from_rfc3339("2025-06-30T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        SubscriberSnapshot {
+            observed_at: now - Duration::days(days_ago),
+            subscribers,
+        }
+    }
+
+    #[test]
+    fn growth_rate_compares_latest_with_last_snapshot_before_window() {
+        let history = vec![
+            snapshot(40, 500),
+            snapshot(30, 800),
+            snapshot(10, 1000),
+            snapshot(3, 1050),
+            snapshot(0, 1100),
+        ];
+
+        let growth = ChannelGrowth::from_history(&history);
+
+        assert_eq!(growth.growth_7d, Some(0.1));
+        assert_eq!(growth.growth_30d, Some(0.375));
+    }
+
+    #[test]
+    fn growth_rate_ignores_snapshot_order() {
+        let history = vec![snapshot(0, 900), snapshot(8, 1000), snapshot(2, 950)];
+
+        assert_eq!(growth_rate(&history, Duration::days(7)), Some(-0.1));
+    }
+
+    #[test]
+    fn growth_rate_is_none_until_history_covers_window() {
+        let history = vec![snapshot(5, 1000), snapshot(0, 1200)];
+
+        assert_eq!(growth_rate(&history, Duration::days(7)), None);
+        assert_eq!(growth_rate(&[], Duration::days(7)), None);
+    }
+
+    #[test]
+    fn growth_rate_is_none_for_empty_baseline() {
+        let history = vec![snapshot(10, 0), snapshot(0, 100)];
+
+        assert_eq!(growth_rate(&history, Duration::days(7)), None);
+    }
+
+    #[test]
+    fn matches_applies_bounds_and_rejects_unknown_growth() {
+        let growth = ChannelGrowth {
+            growth_7d: Some(0.2),
+            growth_30d: None,
+        };
+
+        let filter = |min_7d, max_7d, min_30d| ChannelFilter {
+            min_growth_7d: min_7d,
+            max_growth_7d: max_7d,
+            min_growth_30d: min_30d,
+            ..Default::default()
+        };
+
+        assert!(growth.matches(&ChannelFilter::default()));
+        assert!(growth.matches(&filter(Some(0.1), Some(0.3), None)));
+        assert!(!growth.matches(&filter(Some(0.25), None, None)));
+        assert!(!growth.matches(&filter(None, Some(0.1), None)));
+        assert!(!growth.matches(&filter(None, None, Some(0.0))));
+    }
+}
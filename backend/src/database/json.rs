@@ -0,0 +1,582 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{
+    ChannelRepository, RepositoryError,
+    ads::sort_ads,
+    backup::{self, BackupInfo},
+    growth::ChannelGrowth,
+    merge::{merge_channel_data, merge_histories, replace_channel_id},
+    models::{
+        AdRecord, BlacklistEntry, ChannelData, ChannelFilter, ChannelPage, CrawlExpansion,
+        Database, MediaRecord, SavedList, SubscriberSnapshot, dedup_channel_ids,
+    },
+    schema,
+};
+use crate::config::DatabaseConfig;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+use tokio::{fs, sync::Mutex};
+
+#[derive(Clone, Debug)]
+pub struct JsonDatabase {
+    _file_path: PathBuf,
+    db: Arc<Mutex<Database>>,
+    backups: usize,
+    backup_interval: Duration,
+    last_backup: Arc<Mutex<Option<DateTime<Utc>>>>,
+}
+
+impl JsonDatabase {
+    async fn read_file(file_path: &Path) -> Result<(Database, u32), String> {
+        let contents = fs::read_to_string(file_path)
+            .await
+            .map_err(|e| format!("Failed to read DB file: {}", e))?;
+
+        schema::load(&contents)
+    }
+
+    pub async fn load_file(file_path: &Path) -> Result<Database, String> {
+        Ok(Self::read_file(file_path).await?.0)
+    }
+
+    pub async fn new(config: DatabaseConfig) -> Result<Self, String> {
+        let (data, stored_version) = if config.file_path.exists() {
+            Self::read_file(&config.file_path).await?
+        } else {
+            info!("Database file not found, creating a new one.");
+            (Database::default(), schema::CURRENT_SCHEMA_VERSION)
+        };
+
+        if stored_version < schema::CURRENT_SCHEMA_VERSION {
+            // Keep the pre-upgrade file around in case the upgrade needs to be undone.
+            backup::create_backup(&config.file_path, config.backups.max(1)).await?;
+            let contents = serde_json::to_string_pretty(&data)
+                .map_err(|e| format!("Failed to serialize database: {}", e))?;
+            backup::write_atomic(&config.file_path, contents.as_bytes()).await?;
+            info!(
+                "Database file {:?} upgraded from schema version {} to {}",
+                &config.file_path,
+                stored_version,
+                schema::CURRENT_SCHEMA_VERSION
+            );
+        }
+
+        let last_backup = backup::list_backups(&config.file_path)
+            .await?
+            .first()
+            .map(|b| b.created_at);
+
+        Ok(Self {
+            _file_path: config.file_path,
+            db: Arc::new(Mutex::new(data)),
+            backups: config.backups,
+            backup_interval: Duration::seconds(config.backup_interval_secs as i64),
+            last_backup: Arc::new(Mutex::new(last_backup)),
+        })
+    }
+
+    async fn backup_if_due(&self) {
+        let mut last_backup = self.last_backup.lock().await;
+        let now = Utc::now();
+
+        if last_backup.is_some_and(|t| now - t < self.backup_interval) {
+            return;
+        }
+
+        match backup::create_backup(&self._file_path, self.backups).await {
+            Ok(_) => *last_backup = Some(now),
+            Err(e) => warn!("Skipping DB backup: {}", e),
+        }
+    }
+
+    async fn write(&self, data: &Database) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(data)
+            .map_err(|e| format!("Failed to serialize database: {}", e))?;
+        backup::write_atomic(&self._file_path, contents.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to DB file: {}", e))?;
+        info!("Database saved to {:?}", &self._file_path);
+        Ok(())
+    }
+
+    async fn save(&self, data: &Database) -> Result<(), String> {
+        self.backup_if_due().await;
+        self.write(data).await
+    }
+}
+
+fn upsert_channel(data: &mut Database, channel: ChannelData) {
+    if let Some(existing_index) = data.channels.iter().position(|c| c.id == channel.id) {
+        data.channels[existing_index] = channel;
+    } else {
+        data.channels.push(channel);
+    }
+}
+
+/// Adds `snapshot` to the channel's history; false if one with the same
+/// `observed_at` is already there.
+fn push_snapshot(data: &mut Database, id: i64, snapshot: SubscriberSnapshot) -> bool {
+    let history = data.subscriber_history.entry(id).or_default();
+    if history
+        .iter()
+        .any(|s| s.observed_at == snapshot.observed_at)
+    {
+        return false;
+    }
+
+    history.push(snapshot);
+    history.sort_by_key(|s| s.observed_at);
+    true
+}
+
+#[async_trait]
+impl ChannelRepository for JsonDatabase {
+    async fn filter_channels(&self, filter: &ChannelFilter) -> Result<ChannelPage, String> {
+        let data = self.db.lock().await;
+        let mut channels: Vec<ChannelData> = data
+            .channels
+            .iter()
+            .filter(|channel| filter.matches(channel))
+            .filter(|channel| {
+                !filter.has_growth_filter()
+                    || ChannelGrowth::from_history(
+                        data.subscriber_history
+                            .get(&channel.id)
+                            .map_or(&[], |h| h.as_slice()),
+                    )
+                    .matches(filter)
+            })
+            .cloned()
+            .collect();
+
+        filter.sort(&mut channels);
+        Ok(ChannelPage {
+            total: channels.len(),
+            channels: filter.paginate(channels),
+        })
+    }
+
+    async fn get_channel_by_username(&self, username: &str) -> Result<Option<ChannelData>, String> {
+        let data = self.db.lock().await;
+        Ok(data
+            .channels
+            .iter()
+            .find(|c| c.username == username)
+            .cloned())
+    }
+
+    async fn get_channel_by_id(&self, id: i64) -> Result<Option<ChannelData>, String> {
+        let data = self.db.lock().await;
+        Ok(data.channels.iter().find(|c| c.id == id).cloned())
+    }
+
+    async fn add_channel(&self, channel: ChannelData) -> Result<(), String> {
+        let mut data = self.db.lock().await;
+        if data.channels.iter().any(|c| c.id == channel.id) {
+            return Err(format!("Channel with id {} already exists", channel.id));
+        }
+        data.channels.push(channel);
+        self.save(&data).await?;
+        Ok(())
+    }
+
+    async fn add_or_update_channel(&self, channel: ChannelData) -> Result<(), String> {
+        let mut data = self.db.lock().await;
+        upsert_channel(&mut data, channel);
+        self.save(&data).await
+    }
+
+    async fn add_or_update_channels(&self, channels: Vec<ChannelData>) -> Result<(), String> {
+        if channels.is_empty() {
+            return Ok(());
+        }
+
+        let mut data = self.db.lock().await;
+        for channel in channels {
+            upsert_channel(&mut data, channel);
+        }
+        self.save(&data).await
+    }
+
+    async fn delete_channel(&self, id: i64) -> Result<(), RepositoryError> {
+        let mut data = self.db.lock().await;
+
+        let before = data.channels.len();
+        data.channels.retain(|c| c.id != id);
+        if data.channels.len() == before {
+            return Err(RepositoryError::channel_not_found(id));
+        }
+
+        data.subscriber_history.remove(&id);
+        let now = Utc::now();
+        for list in data
+            .saved_lists
+            .iter_mut()
+            .filter(|l| l.channel_ids.contains(&id))
+        {
+            list.channel_ids.retain(|channel_id| *channel_id != id);
+            list.updated_at = now;
+        }
+
+        data.crawl_expansions.remove(&id);
+        for expansion in data.crawl_expansions.values_mut() {
+            expansion.similar_ids.retain(|similar_id| *similar_id != id);
+        }
+
+        Ok(self.save(&data).await?)
+    }
+
+    async fn merge_channels(
+        &self,
+        source_id: i64,
+        target_id: i64,
+    ) -> Result<ChannelData, RepositoryError> {
+        if source_id == target_id {
+            return Err(RepositoryError::Invalid(format!(
+                "Cannot merge channel {} into itself",
+                source_id
+            )));
+        }
+
+        let mut data = self.db.lock().await;
+
+        let source = data
+            .channels
+            .iter()
+            .find(|c| c.id == source_id)
+            .cloned()
+            .ok_or_else(|| RepositoryError::channel_not_found(source_id))?;
+        let target = data
+            .channels
+            .iter_mut()
+            .find(|c| c.id == target_id)
+            .ok_or_else(|| RepositoryError::channel_not_found(target_id))?;
+
+        let merged = merge_channel_data(target, &source);
+        *target = merged.clone();
+        data.channels.retain(|c| c.id != source_id);
+
+        if let Some(history) = data.subscriber_history.remove(&source_id) {
+            merge_histories(
+                data.subscriber_history.entry(target_id).or_default(),
+                history,
+            );
+        }
+
+        let now = Utc::now();
+        for list in data
+            .saved_lists
+            .iter_mut()
+            .filter(|l| l.channel_ids.contains(&source_id))
+        {
+            list.channel_ids = replace_channel_id(&list.channel_ids, source_id, target_id);
+            list.updated_at = now;
+        }
+
+        for entry in data
+            .blacklist
+            .iter_mut()
+            .filter(|e| e.channel_id == Some(source_id))
+        {
+            entry.channel_id = Some(target_id);
+        }
+
+        // The target's own crawl wins; the source's is only kept when the
+        // target was never expanded.
+        if let Some(expansion) = data.crawl_expansions.remove(&source_id) {
+            data.crawl_expansions.entry(target_id).or_insert(expansion);
+        }
+        for (seed_id, expansion) in data.crawl_expansions.iter_mut() {
+            expansion.similar_ids =
+                replace_channel_id(&expansion.similar_ids, source_id, target_id);
+            expansion
+                .similar_ids
+                .retain(|similar_id| similar_id != seed_id);
+        }
+
+        self.save(&data).await?;
+        Ok(merged)
+    }
+
+    async fn update_channel_by_id(
+        &self,
+        id: i64,
+        update_fn: Box<dyn for<'c> FnOnce(&'c mut ChannelData) + Send>,
+    ) -> Result<(), RepositoryError> {
+        let mut data = self.db.lock().await;
+
+        if let Some(channel) = data.channels.iter_mut().find(|c| c.id == id) {
+            update_fn(channel);
+            self.save(&data).await?;
+            Ok(())
+        } else {
+            Err(RepositoryError::channel_not_found(id))
+        }
+    }
+
+    async fn record_subscriber_snapshots(
+        &self,
+        snapshots: Vec<(i64, SubscriberSnapshot)>,
+    ) -> Result<(), String> {
+        let mut data = self.db.lock().await;
+        let mut changed = false;
+        for (id, snapshot) in snapshots {
+            changed |= push_snapshot(&mut data, id, snapshot);
+        }
+        if !changed {
+            return Ok(());
+        }
+        self.save(&data).await
+    }
+
+    async fn get_subscriber_history(&self, id: i64) -> Result<Vec<SubscriberSnapshot>, String> {
+        let data = self.db.lock().await;
+        Ok(data
+            .subscriber_history
+            .get(&id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn list_saved_lists(&self) -> Result<Vec<SavedList>, String> {
+        let data = self.db.lock().await;
+        Ok(data.saved_lists.clone())
+    }
+
+    async fn get_saved_list(&self, id: i64) -> Result<Option<SavedList>, String> {
+        let data = self.db.lock().await;
+        Ok(data.saved_lists.iter().find(|l| l.id == id).cloned())
+    }
+
+    async fn create_saved_list(
+        &self,
+        name: &str,
+        channel_ids: &[i64],
+    ) -> Result<SavedList, RepositoryError> {
+        let mut data = self.db.lock().await;
+
+        if data.saved_lists.iter().any(|l| l.name == name) {
+            return Err(RepositoryError::Conflict(format!(
+                "Saved list '{}' already exists",
+                name
+            )));
+        }
+
+        let now = Utc::now();
+        let list = SavedList {
+            id: data.saved_lists.iter().map(|l| l.id).max().unwrap_or(0) + 1,
+            name: name.to_string(),
+            channel_ids: dedup_channel_ids(channel_ids),
+            created_at: now,
+            updated_at: now,
+        };
+        data.saved_lists.push(list.clone());
+        self.save(&data).await?;
+        Ok(list)
+    }
+
+    async fn update_saved_list(
+        &self,
+        id: i64,
+        name: &str,
+        channel_ids: &[i64],
+    ) -> Result<SavedList, RepositoryError> {
+        let mut data = self.db.lock().await;
+
+        if data
+            .saved_lists
+            .iter()
+            .any(|l| l.name == name && l.id != id)
+        {
+            return Err(RepositoryError::Conflict(format!(
+                "Saved list '{}' already exists",
+                name
+            )));
+        }
+
+        let list = data
+            .saved_lists
+            .iter_mut()
+            .find(|l| l.id == id)
+            .ok_or_else(|| {
+                RepositoryError::NotFound(format!("Saved list with id {} not found", id))
+            })?;
+        list.name = name.to_string();
+        list.channel_ids = dedup_channel_ids(channel_ids);
+        list.updated_at = Utc::now();
+
+        let list = list.clone();
+        self.save(&data).await?;
+        Ok(list)
+    }
+
+    async fn delete_saved_list(&self, id: i64) -> Result<(), RepositoryError> {
+        let mut data = self.db.lock().await;
+
+        let before = data.saved_lists.len();
+        data.saved_lists.retain(|l| l.id != id);
+        if data.saved_lists.len() == before {
+            return Err(RepositoryError::NotFound(format!(
+                "Saved list with id {} not found",
+                id
+            )));
+        }
+
+        Ok(self.save(&data).await?)
+    }
+
+    async fn list_blacklist(&self) -> Result<Vec<BlacklistEntry>, String> {
+        let data = self.db.lock().await;
+        Ok(data.blacklist.clone())
+    }
+
+    async fn add_blacklist_entry(
+        &self,
+        mut entry: BlacklistEntry,
+    ) -> Result<BlacklistEntry, String> {
+        let mut data = self.db.lock().await;
+
+        entry.id = data.blacklist.iter().map(|e| e.id).max().unwrap_or(0) + 1;
+        entry.created_at = Utc::now();
+        data.blacklist.push(entry.clone());
+        self.save(&data).await?;
+        Ok(entry)
+    }
+
+    async fn remove_blacklist_entry(&self, id: i64) -> Result<(), RepositoryError> {
+        let mut data = self.db.lock().await;
+
+        let before = data.blacklist.len();
+        data.blacklist.retain(|e| e.id != id);
+        if data.blacklist.len() == before {
+            return Err(RepositoryError::NotFound(format!(
+                "Blacklist entry with id {} not found",
+                id
+            )));
+        }
+
+        Ok(self.save(&data).await?)
+    }
+
+    async fn list_crawl_expansions(&self) -> Result<BTreeMap<i64, CrawlExpansion>, String> {
+        let data = self.db.lock().await;
+        Ok(data.crawl_expansions.clone())
+    }
+
+    async fn get_crawl_expansion(&self, seed_id: i64) -> Result<Option<CrawlExpansion>, String> {
+        let data = self.db.lock().await;
+        Ok(data.crawl_expansions.get(&seed_id).cloned())
+    }
+
+    async fn save_crawl_expansion(
+        &self,
+        seed_id: i64,
+        expansion: CrawlExpansion,
+    ) -> Result<(), String> {
+        let mut data = self.db.lock().await;
+        data.crawl_expansions.insert(seed_id, expansion);
+        self.save(&data).await
+    }
+
+    async fn list_media(&self) -> Result<Vec<MediaRecord>, String> {
+        let data = self.db.lock().await;
+        Ok(data.media.clone())
+    }
+
+    async fn get_media(&self, id: &str) -> Result<Option<MediaRecord>, String> {
+        let data = self.db.lock().await;
+        Ok(data.media.iter().find(|m| m.id == id).cloned())
+    }
+
+    async fn save_media(&self, media: MediaRecord) -> Result<(), String> {
+        let mut data = self.db.lock().await;
+        data.media.retain(|m| m.id != media.id);
+        data.media.push(media);
+        self.save(&data).await
+    }
+
+    async fn list_ads(&self) -> Result<Vec<AdRecord>, String> {
+        let data = self.db.lock().await;
+        let mut ads = data.ads.clone();
+        sort_ads(&mut ads);
+        Ok(ads)
+    }
+
+    async fn get_ad(&self, id: i64) -> Result<Option<AdRecord>, String> {
+        let data = self.db.lock().await;
+        Ok(data.ads.iter().find(|a| a.id == id).cloned())
+    }
+
+    async fn save_ads(&self, ads: Vec<AdRecord>) -> Result<(), String> {
+        let mut data = self.db.lock().await;
+        for ad in ads {
+            match data.ads.iter_mut().find(|a| a.id == ad.id) {
+                Some(existing) => *existing = ad,
+                None => data.ads.push(ad),
+            }
+        }
+        self.save(&data).await
+    }
+
+    async fn list_backups(&self) -> Result<Vec<BackupInfo>, RepositoryError> {
+        Ok(backup::list_backups(&self._file_path).await?)
+    }
+
+    async fn restore_backup(&self, name: &str) -> Result<(), RepositoryError> {
+        let mut data = self.db.lock().await;
+
+        let backup_path = backup::backup_path(&self._file_path, name)
+            .await?
+            .ok_or_else(|| RepositoryError::NotFound(format!("Backup '{}' not found", name)))?;
+        let restored = Self::load_file(&backup_path).await?;
+
+        // Keep the state we are about to replace, so a restore can be undone.
+        backup::create_backup(&self._file_path, self.backups).await?;
+        *self.last_backup.lock().await = Some(Utc::now());
+
+        *data = restored;
+        self.write(&data).await?;
+        info!("Database restored from backup '{}'", name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::DatabaseBackend,
+        test_support::{database_config, temp_dir},
+    };
+
+    #[tokio::test]
+    async fn new_upgrades_v1_file_on_disk() {
+        let dir = temp_dir("json-upgrade-v1");
+        let config = database_config(&dir, DatabaseBackend::Json);
+        std::fs::write(
+            &config.file_path,
+            r#"{"channels": [{"id": 42, "username": "oldchannel", "category": "news"}]}"#,
+        )
+        .unwrap();
+
+        let db = JsonDatabase::new(config.clone()).await.unwrap();
+
+        let channel = db.get_channel_by_id(42).await.unwrap().unwrap();
+        assert_eq!(channel.username, "oldchannel");
+        assert_eq!(channel.category.as_deref(), Some("news"));
+
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&config.file_path).unwrap()).unwrap();
+        assert_eq!(stored["version"], schema::CURRENT_SCHEMA_VERSION);
+
+        let backups = backup::list_backups(&config.file_path).await.unwrap();
+        assert_eq!(backups.len(), 1);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
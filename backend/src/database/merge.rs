@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::models::{ChannelData, SubscriberSnapshot, dedup_channel_ids, normalize_tags};
+
+#[derive(Debug, Serialize)]
+pub struct UsernameCollision {
+    pub username: String,
+    pub channels: Vec<ChannelData>,
+}
+
+/// Folds `source` into `target`. The target keeps its id and username, and any
+/// field already set on it — including hand-edited category and geo — wins;
+/// the source only fills the gaps. Tags from both records are kept.
+pub fn merge_channel_data(target: &ChannelData, source: &ChannelData) -> ChannelData {
+    let tags: Vec<String> = target.tags.iter().chain(&source.tags).cloned().collect();
+
+    ChannelData {
+        id: target.id,
+        title: target.title.clone().or_else(|| source.title.clone()),
+        username: target.username.clone(),
+        photo_element: target
+            .photo_element
+            .clone()
+            .or_else(|| source.photo_element.clone()),
+        category: target.category.clone().or_else(|| source.category.clone()),
+        description: target
+            .description
+            .clone()
+            .or_else(|| source.description.clone()),
+        subscribers: target.subscribers.or(source.subscribers),
+        geo: target.geo.clone().or_else(|| source.geo.clone()),
+        tags: normalize_tags(&tags),
+    }
+}
+
+/// Adds the source's snapshots to `target`; where both have a snapshot at the
+/// same time, the target's is kept.
+pub fn merge_histories(target: &mut Vec<SubscriberSnapshot>, source: Vec<SubscriberSnapshot>) {
+    for snapshot in source {
+        if !target.iter().any(|s| s.observed_at == snapshot.observed_at) {
+            target.push(snapshot);
+        }
+    }
+    target.sort_by_key(|s| s.observed_at);
+}
+
+/// Points `from` at `to` in a list of channel ids, keeping the first position
+/// if `to` was already present.
+pub fn replace_channel_id(channel_ids: &[i64], from: i64, to: i64) -> Vec<i64> {
+    let replaced: Vec<i64> = channel_ids
+        .iter()
+        .map(|id| if *id == from { to } else { *id })
+        .collect();
+    dedup_channel_ids(&replaced)
+}
+
+/// Groups channels that share a username, compared case-insensitively the way
+/// Telegram does.
+pub fn find_username_collisions(channels: &[ChannelData]) -> Vec<UsernameCollision> {
+    let mut by_username: BTreeMap<String, Vec<ChannelData>> = BTreeMap::new();
+    for channel in channels {
+        by_username
+            .entry(channel.username.trim().to_lowercase())
+            .or_default()
+            .push(channel.clone());
+    }
+
+    by_username
+        .into_iter()
+        .filter(|(_, channels)| channels.len() > 1)
+        .map(|(username, channels)| UsernameCollision { username, channels })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::channel;
+    use chrono::{TimeZone, Utc};
+
+    fn snapshot(hour: u32, subscribers: i64) -> SubscriberSnapshot {
+        SubscriberSnapshot {
+            observed_at: Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap(),
+            subscribers,
+        }
+    }
+
+    #[test]
+    fn target_fields_win_and_source_fills_gaps() {
+        let mut target = channel(1, "target");
+        target.category = Some("crypto".to_string());
+        target.subscribers = Some(100);
+        target.tags = vec!["defi".to_string()];
+
+        let mut source = channel(2, "source");
+        source.title = Some("Source title".to_string());
+        source.category = Some("news".to_string());
+        source.geo = Some("us".to_string());
+        source.subscribers = Some(500);
+        source.tags = vec!["DeFi".to_string(), "trading".to_string()];
+
+        let merged = merge_channel_data(&target, &source);
+
+        assert_eq!(merged.id, 1);
+        assert_eq!(merged.username, "target");
+        assert_eq!(merged.category.as_deref(), Some("crypto"));
+        assert_eq!(merged.subscribers, Some(100));
+        assert_eq!(merged.title.as_deref(), Some("Source title"));
+        assert_eq!(merged.geo.as_deref(), Some("us"));
+        assert_eq!(merged.tags, vec!["defi", "trading"]);
+    }
+
+    #[test]
+    fn merge_histories_keeps_target_snapshot_at_same_time() {
+        let mut target = vec![snapshot(2, 200), snapshot(3, 300)];
+        merge_histories(&mut target, vec![snapshot(1, 10), snapshot(2, 20)]);
+
+        let merged: Vec<i64> = target.iter().map(|s| s.subscribers).collect();
+        assert_eq!(merged, vec![10, 200, 300]);
+    }
+
+    #[test]
+    fn replace_channel_id_keeps_first_position() {
+        assert_eq!(replace_channel_id(&[3, 1, 2], 1, 2), vec![3, 2]);
+        assert_eq!(replace_channel_id(&[1, 3], 1, 4), vec![4, 3]);
+    }
+
+    #[test]
+    fn username_collisions_ignore_case() {
+        let channels = vec![
+            channel(1, "Crypto"),
+            channel(2, "crypto "),
+            channel(3, "news"),
+        ];
+
+        let collisions = find_username_collisions(&channels);
+
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].username, "crypto");
+        assert_eq!(collisions[0].channels.len(), 2);
+    }
+}
@@ -0,0 +1,416 @@
+use std::{collections::HashMap, path::Path};
+
+use log::{info, warn};
+use serde::Serialize;
+
+use super::{
+    ChannelRepository, JsonDatabase,
+    models::{ChannelData, Database, SubscriberSnapshot},
+};
+
+#[derive(Debug, Serialize)]
+pub struct MigrationDuplicate {
+    pub id: i64,
+    pub username: String,
+    pub kept_username: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrationSkip {
+    pub id: i64,
+    pub username: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MigrationReport {
+    pub total: usize,
+    pub migrated: usize,
+    pub history_snapshots: usize,
+    pub saved_lists: usize,
+    pub blacklist: usize,
+    pub crawl_expansions: usize,
+    pub media: usize,
+    pub ads: usize,
+    pub duplicates: Vec<MigrationDuplicate>,
+    pub skipped: Vec<MigrationSkip>,
+}
+
+pub(crate) fn validate_channel(channel: &ChannelData) -> Result<(), String> {
+    if channel.id <= 0 {
+        return Err(format!("Invalid channel id {}", channel.id));
+    }
+
+    let username = channel.username.trim();
+    if username.is_empty() {
+        return Err("Missing username".to_string());
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!("Invalid username '{}'", channel.username));
+    }
+
+    if channel.subscribers.is_some_and(|s| s < 0) {
+        return Err(format!(
+            "Negative subscribers count {}",
+            channel.subscribers.unwrap_or_default()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Copies every valid channel from `source` into `target`. Records are written
+/// through `add_or_update_channels`, so running it again over the same file
+/// leaves the target unchanged. Fails without copying anything else if the
+/// channels cannot be written.
+pub async fn migrate_channels(
+    source: Database,
+    target: &dyn ChannelRepository,
+) -> Result<MigrationReport, String> {
+    let Database {
+        channels,
+        mut subscriber_history,
+        saved_lists,
+        blacklist,
+        crawl_expansions,
+        media,
+        ads,
+        ..
+    } = source;
+    let mut report = MigrationReport {
+        total: channels.len(),
+        ..Default::default()
+    };
+    let mut seen_ids: HashMap<i64, String> = HashMap::new();
+    let mut seen_usernames: HashMap<String, i64> = HashMap::new();
+    let mut to_write = vec![];
+
+    for channel in channels {
+        if let Err(reason) = validate_channel(&channel) {
+            warn!(
+                "Skipping channel {} ({}): {}",
+                channel.id, channel.username, reason
+            );
+            report.skipped.push(MigrationSkip {
+                id: channel.id,
+                username: channel.username,
+                reason,
+            });
+            continue;
+        }
+
+        if let Some(kept_username) = seen_ids.get(&channel.id) {
+            warn!(
+                "Duplicate channel id {}: '{}' already migrated as '{}'",
+                channel.id, channel.username, kept_username
+            );
+            report.duplicates.push(MigrationDuplicate {
+                id: channel.id,
+                username: channel.username,
+                kept_username: kept_username.clone(),
+            });
+            continue;
+        }
+
+        if let Some(kept_id) = seen_usernames.get(&channel.username) {
+            report.skipped.push(MigrationSkip {
+                id: channel.id,
+                username: channel.username,
+                reason: format!("Username already migrated with id {}", kept_id),
+            });
+            continue;
+        }
+
+        seen_ids.insert(channel.id, channel.username.clone());
+        seen_usernames.insert(channel.username.clone(), channel.id);
+        to_write.push(channel);
+    }
+
+    // One write for all channels and one for all history, instead of a full
+    // save per record on the JSON backend.
+    let written: Vec<(i64, String)> = to_write
+        .iter()
+        .map(|c| (c.id, c.username.clone()))
+        .collect();
+    // Nothing else is copied when the channels could not be written: the
+    // saved lists would be emptied and the rest would point at missing records.
+    target
+        .add_or_update_channels(to_write)
+        .await
+        .map_err(|e| format!("Failed to write channels: {}", e))?;
+    report.migrated = written.len();
+
+    let snapshots: Vec<(i64, SubscriberSnapshot)> = written
+        .iter()
+        .flat_map(|(id, _)| {
+            subscriber_history
+                .remove(id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|snapshot| (*id, snapshot))
+        })
+        .collect();
+    let snapshot_count = snapshots.len();
+    match target.record_subscriber_snapshots(snapshots).await {
+        Ok(()) => report.history_snapshots = snapshot_count,
+        Err(e) => warn!("Failed to copy subscriber history: {}", e),
+    }
+
+    // Lists are matched by name so a rerun updates them instead of adding copies.
+    let existing_lists = target.list_saved_lists().await.unwrap_or_else(|e| {
+        warn!("Failed to read saved lists from target: {}", e);
+        vec![]
+    });
+    for list in saved_lists {
+        let channel_ids: Vec<i64> = list
+            .channel_ids
+            .iter()
+            .copied()
+            .filter(|id| seen_ids.contains_key(id))
+            .collect();
+        let result = match existing_lists.iter().find(|l| l.name == list.name) {
+            Some(existing) => target
+                .update_saved_list(existing.id, &list.name, &channel_ids)
+                .await
+                .map(|_| ()),
+            None => target
+                .create_saved_list(&list.name, &channel_ids)
+                .await
+                .map(|_| ()),
+        };
+        match result {
+            Ok(()) => report.saved_lists += 1,
+            Err(e) => warn!("Failed to copy saved list '{}': {}", list.name, e),
+        }
+    }
+
+    // Entries already present in the target are not added a second time.
+    let existing_blacklist = target.list_blacklist().await.unwrap_or_else(|e| {
+        warn!("Failed to read blacklist from target: {}", e);
+        vec![]
+    });
+    for entry in blacklist {
+        if existing_blacklist
+            .iter()
+            .any(|e| e.channel_id == entry.channel_id && e.username == entry.username)
+        {
+            continue;
+        }
+        match target.add_blacklist_entry(entry).await {
+            Ok(_) => report.blacklist += 1,
+            Err(e) => warn!("Failed to copy blacklist entry: {}", e),
+        }
+    }
+
+    for (seed_id, expansion) in crawl_expansions {
+        match target.save_crawl_expansion(seed_id, expansion).await {
+            Ok(()) => report.crawl_expansions += 1,
+            Err(e) => warn!("Failed to copy crawl expansion for {}: {}", seed_id, e),
+        }
+    }
+
+    for record in media {
+        let id = record.id.clone();
+        match target.save_media(record).await {
+            Ok(()) => report.media += 1,
+            Err(e) => warn!("Failed to copy media '{}': {}", id, e),
+        }
+    }
+
+    let ads_count = ads.len();
+    match target.save_ads(ads).await {
+        Ok(()) => report.ads = ads_count,
+        Err(e) => warn!("Failed to copy ads: {}", e),
+    }
+
+    info!(
+        "Migrated {} of {} channels ({} duplicates, {} skipped)",
+        report.migrated,
+        report.total,
+        report.duplicates.len(),
+        report.skipped.len()
+    );
+    Ok(report)
+}
+
+pub async fn migrate_json_file(
+    source_path: &Path,
+    target: &dyn ChannelRepository,
+) -> Result<MigrationReport, String> {
+    let source = JsonDatabase::load_file(source_path).await?;
+    migrate_channels(source, target).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::*;
+    use crate::{
+        database::models::{
+            AdRecord, BlacklistEntry, ChannelFilter, CrawlExpansion, MediaRecord, SavedList,
+        },
+        test_support::{channel, json_db, sqlite_db},
+    };
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, day, 0, 0, 0).unwrap()
+    }
+
+    /// Two valid channels, one duplicate id and three channels that fail
+    /// validation or reuse a username, with one of every other record.
+    fn fixture() -> Database {
+        let snapshot = |day, subscribers| SubscriberSnapshot {
+            observed_at: at(day),
+            subscribers,
+        };
+        Database {
+            channels: vec![
+                channel(1, "alpha"),
+                channel(2, "beta"),
+                channel(1, "alpha_copy"),
+                channel(-5, "negative"),
+                channel(6, "bad name"),
+                channel(7, "alpha"),
+            ],
+            subscriber_history: BTreeMap::from([
+                (1, vec![snapshot(1, 100), snapshot(2, 120)]),
+                (6, vec![snapshot(1, 50)]),
+            ]),
+            saved_lists: vec![SavedList {
+                id: 1,
+                name: "favourites".to_string(),
+                channel_ids: vec![1, 6, 2, 99],
+                created_at: at(1),
+                updated_at: at(1),
+            }],
+            blacklist: vec![BlacklistEntry {
+                id: 1,
+                channel_id: Some(2),
+                username: None,
+                reason: "spam".to_string(),
+                created_at: at(1),
+            }],
+            crawl_expansions: BTreeMap::from([(
+                1,
+                CrawlExpansion {
+                    expanded_at: at(1),
+                    similar_ids: vec![2],
+                },
+            )]),
+            media: vec![MediaRecord {
+                id: "photo:1".to_string(),
+                file_name: "ad.png".to_string(),
+                content_type: "image/png".to_string(),
+                size: 10,
+                uploaded_at: at(1),
+            }],
+            ads: vec![AdRecord {
+                id: 10,
+                title: Some("Ad".to_string()),
+                text: None,
+                promote_url: None,
+                status: "Active".to_string(),
+                views: 0,
+                clicks: 0,
+                spent: 0.0,
+                cpm: 1.0,
+                first_seen_at: at(1),
+                synced_at: at(1),
+                removed_at: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    async fn assert_target_contents(target: &dyn ChannelRepository) {
+        let channels = target
+            .filter_channels(&ChannelFilter::default())
+            .await
+            .unwrap()
+            .channels;
+        assert_eq!(channels, vec![channel(1, "alpha"), channel(2, "beta")]);
+        assert_eq!(target.get_subscriber_history(1).await.unwrap().len(), 2);
+        assert!(target.get_subscriber_history(6).await.unwrap().is_empty());
+
+        let lists = target.list_saved_lists().await.unwrap();
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].name, "favourites");
+        assert_eq!(lists[0].channel_ids, vec![1, 2]);
+
+        assert_eq!(target.list_blacklist().await.unwrap().len(), 1);
+        assert_eq!(
+            target
+                .get_crawl_expansion(1)
+                .await
+                .unwrap()
+                .unwrap()
+                .similar_ids,
+            vec![2]
+        );
+        assert_eq!(target.list_media().await.unwrap().len(), 1);
+        assert_eq!(target.list_ads().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn migrates_json_into_sqlite_and_reports_rejected_channels() {
+        let (target, dir) = sqlite_db("migration-sqlite").await;
+
+        let report = migrate_channels(fixture(), &target).await.unwrap();
+
+        assert_eq!(report.total, 6);
+        assert_eq!(report.migrated, 2);
+        assert_eq!(report.history_snapshots, 2);
+        let duplicates: Vec<(i64, &str)> = report
+            .duplicates
+            .iter()
+            .map(|d| (d.id, d.kept_username.as_str()))
+            .collect();
+        assert_eq!(duplicates, vec![(1, "alpha")]);
+        let skipped: Vec<i64> = report.skipped.iter().map(|s| s.id).collect();
+        assert_eq!(skipped, vec![-5, 6, 7]);
+        assert_eq!(report.saved_lists, 1);
+        assert_eq!(report.blacklist, 1);
+        assert_eq!(report.crawl_expansions, 1);
+        assert_eq!(report.media, 1);
+        assert_eq!(report.ads, 1);
+        assert_target_contents(&target).await;
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn rerun_leaves_target_unchanged() {
+        let (target, dir) = sqlite_db("migration-rerun").await;
+        migrate_channels(fixture(), &target).await.unwrap();
+
+        let report = migrate_channels(fixture(), &target).await.unwrap();
+
+        assert_eq!(report.migrated, 2);
+        assert_eq!(report.blacklist, 0);
+        assert_target_contents(&target).await;
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn failed_channel_write_aborts_migration() {
+        let (target, dir) = json_db("migration-write-fails").await;
+        // The JSON store can no longer write its file, only its in-memory copy.
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let error = migrate_channels(fixture(), &target).await.unwrap_err();
+
+        assert!(error.starts_with("Failed to write channels"), "{}", error);
+        assert!(target.list_saved_lists().await.unwrap().is_empty());
+        assert!(target.list_blacklist().await.unwrap().is_empty());
+        assert!(target.list_crawl_expansions().await.unwrap().is_empty());
+        assert!(target.list_media().await.unwrap().is_empty());
+        assert!(target.list_ads().await.unwrap().is_empty());
+    }
+}
@@ -1,4 +1,17 @@
-mod database;
+pub mod ads;
+pub mod backup;
+pub mod graph;
+pub mod growth;
+mod json;
+pub mod merge;
+pub mod migration;
 pub mod models;
+pub mod relevance;
+mod repository;
+pub mod schema;
+mod sqlite;
+pub mod transfer;
 
-pub use database::*;
+pub use json::*;
+pub use repository::*;
+pub use sqlite::*;
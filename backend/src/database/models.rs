@@ -1,6 +1,11 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+use super::schema::CURRENT_SCHEMA_VERSION;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
 pub struct ChannelData {
     pub id: i64,
     pub title: Option<String>,
@@ -10,15 +15,429 @@ pub struct ChannelData {
     pub description: Option<String>,
     pub subscribers: Option<i64>,
     pub geo: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SavedList {
+    pub id: i64,
+    pub name: String,
+    /// Ordered and free of duplicates.
+    pub channel_ids: Vec<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlacklistEntry {
+    pub id: i64,
+    pub channel_id: Option<i64>,
+    /// Stored lowercase without the leading `@`.
+    pub username: Option<String>,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BlacklistEntry {
+    pub fn matches(&self, channel_id: i64, username: Option<&str>) -> bool {
+        self.channel_id == Some(channel_id)
+            || self.username.as_ref().is_some_and(|blocked| {
+                username.is_some_and(|u| blocked == &u.trim_start_matches('@').to_lowercase())
+            })
+    }
+}
+
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+pub fn dedup_channel_ids(channel_ids: &[i64]) -> Vec<i64> {
+    let mut unique: Vec<i64> = vec![];
+    for id in channel_ids {
+        if !unique.contains(id) {
+            unique.push(*id);
+        }
+    }
+    unique
+}
+
+/// The similar channels Telegram returned for one seed, i.e. the seed's
+/// similar-to edges. Kept so a crawl rerun can walk past the seed without
+/// asking Telegram again, and to rank channels by relevance.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CrawlExpansion {
+    pub expanded_at: DateTime<Utc>,
+    /// In Telegram's order, most similar first.
+    pub similar_ids: Vec<i64>,
+}
+
+/// A file uploaded to the ads platform, referenced by `media` when an ad is
+/// created.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MediaRecord {
+    /// The id the ads platform returned for the upload.
+    pub id: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// An ad from the ads account with its latest statistics. Kept after the ad
+/// disappears from the account, with `removed_at` set.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct AdRecord {
+    /// The ads platform's ad id.
+    pub id: i64,
+    pub title: Option<String>,
+    pub text: Option<String>,
+    pub promote_url: Option<String>,
+    pub status: String,
+    pub views: i64,
+    pub clicks: i64,
+    /// TON spent so far.
+    pub spent: f64,
+    /// TON per 1000 views.
+    pub cpm: f64,
+    pub first_seen_at: DateTime<Utc>,
+    pub synced_at: DateTime<Utc>,
+    pub removed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SubscriberSnapshot {
+    pub observed_at: DateTime<Utc>,
+    pub subscribers: i64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelSortField {
+    Subscribers,
+    Title,
+    Username,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ChannelFilter {
+    pub category: Option<String>,
+    pub geo: Option<String>,
+    pub min_growth_7d: Option<f64>,
+    pub max_growth_7d: Option<f64>,
+    pub min_growth_30d: Option<f64>,
+    pub max_growth_30d: Option<f64>,
+    /// Case-insensitive substring matched against title, username and description.
+    pub search: Option<String>,
+    pub min_subscribers: Option<i64>,
+    pub max_subscribers: Option<i64>,
+    pub tag: Option<String>,
+    pub sort_by: Option<ChannelSortField>,
+    pub order: SortOrder,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChannelPage {
+    pub channels: Vec<ChannelData>,
+    /// Number of channels matching the filter before `limit`/`offset`.
+    pub total: usize,
+}
+
+impl ChannelFilter {
+    pub fn search_term(&self) -> Option<String> {
+        self.search
+            .as_ref()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+    }
+
+    pub fn matches(&self, channel: &ChannelData) -> bool {
+        let matches_category = self
+            .category
+            .as_ref()
+            .is_none_or(|cat| channel.category.as_ref() == Some(&cat.to_lowercase()));
+
+        let matches_geo = self
+            .geo
+            .as_ref()
+            .is_none_or(|g| channel.geo.as_ref() == Some(&g.to_lowercase()));
+
+        let matches_subscribers = (self.min_subscribers.is_none()
+            && self.max_subscribers.is_none())
+            || channel.subscribers.is_some_and(|s| {
+                self.min_subscribers.is_none_or(|min| s >= min)
+                    && self.max_subscribers.is_none_or(|max| s <= max)
+            });
+
+        let matches_search = self.search_term().is_none_or(|term| {
+            [
+                &channel.title,
+                &Some(channel.username.clone()),
+                &channel.description,
+            ]
+            .iter()
+            .any(|field| {
+                field
+                    .as_ref()
+                    .is_some_and(|value| value.to_lowercase().contains(&term))
+            })
+        });
+
+        let matches_tag = self
+            .tag
+            .as_ref()
+            .is_none_or(|tag| channel.tags.contains(&tag.trim().to_lowercase()));
+
+        matches_category && matches_geo && matches_subscribers && matches_search && matches_tag
+    }
+
+    /// Sorts in place; channels without a value for the sort field go last
+    /// in either order.
+    pub fn sort(&self, channels: &mut [ChannelData]) {
+        let Some(sort_by) = self.sort_by else {
+            return;
+        };
+
+        channels.sort_by(|a, b| {
+            let (a, b) = match sort_by {
+                ChannelSortField::Subscribers => {
+                    return compare_nulls_last(a.subscribers, b.subscribers, self.order);
+                }
+                ChannelSortField::Title => (
+                    a.title.as_ref().map(|t| t.to_lowercase()),
+                    b.title.as_ref().map(|t| t.to_lowercase()),
+                ),
+                ChannelSortField::Username => (
+                    Some(a.username.to_lowercase()),
+                    Some(b.username.to_lowercase()),
+                ),
+            };
+            compare_nulls_last(a, b, self.order)
+        });
+    }
+
+    pub fn paginate<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    pub fn has_growth_filter(&self) -> bool {
+        self.min_growth_7d.is_some()
+            || self.max_growth_7d.is_some()
+            || self.min_growth_30d.is_some()
+            || self.max_growth_30d.is_some()
+    }
+}
+
+fn compare_nulls_last<T: Ord>(a: Option<T>, b: Option<T>, order: SortOrder) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => match order {
+            SortOrder::Asc => a.cmp(&b),
+            SortOrder::Desc => b.cmp(&a),
+        },
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Database {
+    pub version: u32,
     pub channels: Vec<ChannelData>,
+    pub subscriber_history: BTreeMap<i64, Vec<SubscriberSnapshot>>,
+    pub saved_lists: Vec<SavedList>,
+    pub blacklist: Vec<BlacklistEntry>,
+    pub crawl_expansions: BTreeMap<i64, CrawlExpansion>,
+    pub media: Vec<MediaRecord>,
+    pub ads: Vec<AdRecord>,
 }
 
 impl Default for Database {
     fn default() -> Self {
-        Self { channels: vec![] }
+        Self {
+            version: CURRENT_SCHEMA_VERSION,
+            channels: vec![],
+            subscriber_history: BTreeMap::new(),
+            saved_lists: vec![],
+            blacklist: vec![],
+            crawl_expansions: BTreeMap::new(),
+            media: vec![],
+            ads: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(
+        id: i64,
+        username: &str,
+        title: Option<&str>,
+        subscribers: Option<i64>,
+    ) -> ChannelData {
+        ChannelData {
+            title: title.map(str::to_string),
+            category: Some("tech".to_string()),
+            description: Some("Новости технологий".to_string()),
+            subscribers,
+            geo: Some("ru".to_string()),
+            ..crate::test_support::channel(id, username)
+        }
+    }
+
+    fn ids(channels: &[ChannelData]) -> Vec<i64> {
+        channels.iter().map(|c| c.id).collect()
+    }
+
+    #[test]
+    fn matches_category_and_geo_case_insensitively() {
+        let channel = channel(1, "techru", None, None);
+        let filter = |category: &str, geo: &str| ChannelFilter {
+            category: Some(category.to_string()),
+            geo: Some(geo.to_string()),
+            ..Default::default()
+        };
+
+        assert!(filter("Tech", "RU").matches(&channel));
+        assert!(!filter("news", "ru").matches(&channel));
+        assert!(!filter("tech", "kz").matches(&channel));
+    }
+
+    #[test]
+    fn matches_search_in_title_username_and_description() {
+        let channel = channel(1, "GadgetHub", Some("Gadgets"), None);
+        let search = |term: &str| ChannelFilter {
+            search: Some(term.to_string()),
+            ..Default::default()
+        };
+
+        assert!(search("gadgethub").matches(&channel));
+        assert!(search(" GADGETS ").matches(&channel));
+        assert!(search("технологий").matches(&channel));
+        assert!(!search("sport").matches(&channel));
+        assert!(search("   ").matches(&channel));
+    }
+
+    #[test]
+    fn subscriber_bounds_reject_channels_without_count() {
+        let filter = ChannelFilter {
+            min_subscribers: Some(100),
+            max_subscribers: Some(1000),
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&channel(1, "a", None, Some(100))));
+        assert!(!filter.matches(&channel(2, "b", None, Some(1001))));
+        assert!(!filter.matches(&channel(3, "c", None, None)));
+    }
+
+    #[test]
+    fn sort_puts_missing_values_last_in_both_orders() {
+        let mut channels = vec![
+            channel(1, "b", Some("beta"), None),
+            channel(2, "a", None, Some(10)),
+            channel(3, "c", Some("Alpha"), Some(30)),
+        ];
+        let mut filter = ChannelFilter {
+            sort_by: Some(ChannelSortField::Subscribers),
+            order: SortOrder::Desc,
+            ..Default::default()
+        };
+
+        filter.sort(&mut channels);
+        assert_eq!(ids(&channels), vec![3, 2, 1]);
+
+        filter.order = SortOrder::Asc;
+        filter.sort(&mut channels);
+        assert_eq!(ids(&channels), vec![2, 3, 1]);
+
+        filter.sort_by = Some(ChannelSortField::Title);
+        filter.sort(&mut channels);
+        assert_eq!(ids(&channels), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn normalize_tags_trims_lowercases_and_dedups() {
+        let tags = vec![
+            " Crypto ".to_string(),
+            "crypto".to_string(),
+            "".to_string(),
+            "NFT".to_string(),
+        ];
+
+        assert_eq!(normalize_tags(&tags), vec!["crypto", "nft"]);
+    }
+
+    #[test]
+    fn dedup_channel_ids_keeps_first_occurrence_order() {
+        assert_eq!(dedup_channel_ids(&[3, 1, 3, 2, 1]), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn matches_normalized_tag() {
+        let mut channel = channel(1, "a", None, None);
+        channel.tags = vec!["crypto".to_string()];
+        let filter = |tag: &str| ChannelFilter {
+            tag: Some(tag.to_string()),
+            ..Default::default()
+        };
+
+        assert!(filter(" Crypto").matches(&channel));
+        assert!(!filter("nft").matches(&channel));
+    }
+
+    #[test]
+    fn blacklist_entry_matches_id_or_normalized_username() {
+        let entry = |channel_id: Option<i64>, username: Option<&str>| BlacklistEntry {
+            id: 1,
+            channel_id,
+            username: username.map(str::to_string),
+            reason: "spam".to_string(),
+            created_at: Utc::now(),
+        };
+
+        assert!(entry(Some(7), None).matches(7, Some("other")));
+        assert!(!entry(Some(7), None).matches(8, None));
+        assert!(entry(None, Some("spammer")).matches(1, Some("@SpamMer")));
+        assert!(!entry(None, Some("spammer")).matches(1, Some("spammer2")));
+        assert!(!entry(None, Some("spammer")).matches(1, None));
+    }
+
+    #[test]
+    fn paginate_applies_offset_and_limit() {
+        let filter = ChannelFilter {
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.paginate(vec![1, 2, 3, 4]), vec![2, 3]);
+        assert_eq!(
+            ChannelFilter::default().paginate(vec![1, 2, 3]),
+            vec![1, 2, 3]
+        );
     }
 }
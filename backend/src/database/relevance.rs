@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use super::models::{ChannelData, CrawlExpansion};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Relevance {
+    pub score: f64,
+    /// Seeds that list the channel as similar.
+    pub seeds: Vec<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedChannel {
+    #[serde(flatten)]
+    pub channel: ChannelData,
+    pub relevance: Relevance,
+}
+
+/// How strongly a seed points at the channel in `position` of its similar
+/// list: 1.0 for Telegram's top pick, falling linearly towards the end.
+pub(crate) fn edge_strength(position: usize, list_len: usize) -> f64 {
+    (list_len - position) as f64 / list_len as f64
+}
+
+/// Sums edge strengths over every seed pointing at a channel, so channels
+/// recommended by many seeds, and near the top of their lists, score highest.
+/// With `seeds` set only those seeds' edges count.
+pub fn score_channels(
+    expansions: &BTreeMap<i64, CrawlExpansion>,
+    seeds: Option<&HashSet<i64>>,
+) -> HashMap<i64, Relevance> {
+    let mut scores: HashMap<i64, Relevance> = HashMap::new();
+
+    for (seed_id, expansion) in expansions {
+        if seeds.is_some_and(|seeds| !seeds.contains(seed_id)) {
+            continue;
+        }
+
+        let list_len = expansion.similar_ids.len();
+        for (position, channel_id) in expansion.similar_ids.iter().enumerate() {
+            if channel_id == seed_id {
+                continue;
+            }
+            let relevance = scores.entry(*channel_id).or_default();
+            relevance.score += edge_strength(position, list_len);
+            relevance.seeds.push(*seed_id);
+        }
+    }
+    scores
+}
+
+/// Pairs channels with their scores and sorts the best lookalikes first;
+/// channels no seed points at are dropped.
+pub fn rank_channels(
+    channels: Vec<ChannelData>,
+    scores: &HashMap<i64, Relevance>,
+) -> Vec<RankedChannel> {
+    let mut ranked: Vec<RankedChannel> = channels
+        .into_iter()
+        .filter_map(|channel| {
+            scores.get(&channel.id).map(|relevance| RankedChannel {
+                relevance: relevance.clone(),
+                channel,
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.relevance
+            .score
+            .total_cmp(&a.relevance.score)
+            .then(b.relevance.seeds.len().cmp(&a.relevance.seeds.len()))
+            .then(b.channel.subscribers.cmp(&a.channel.subscribers))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn expansions(edges: &[(i64, &[i64])]) -> BTreeMap<i64, CrawlExpansion> {
+        edges
+            .iter()
+            .map(|(seed_id, similar_ids)| {
+                (
+                    *seed_id,
+                    CrawlExpansion {
+                        expanded_at: Utc::now(),
+                        similar_ids: similar_ids.to_vec(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn channel(id: i64, subscribers: Option<i64>) -> ChannelData {
+        ChannelData {
+            subscribers,
+            ..crate::test_support::channel(id, &format!("channel{}", id))
+        }
+    }
+
+    #[test]
+    fn edge_strength_falls_with_position() {
+        assert_eq!(edge_strength(0, 4), 1.0);
+        assert_eq!(edge_strength(1, 4), 0.75);
+        assert_eq!(edge_strength(3, 4), 0.25);
+    }
+
+    #[test]
+    fn scores_sum_over_seeds_and_skip_self_edges() {
+        let expansions = expansions(&[(1, &[10, 11]), (2, &[11, 2])]);
+
+        let scores = score_channels(&expansions, None);
+
+        assert_eq!(scores[&10].score, 1.0);
+        assert_eq!(scores[&10].seeds, vec![1]);
+        assert_eq!(scores[&11].score, 1.5);
+        assert_eq!(scores[&11].seeds, vec![1, 2]);
+        assert!(!scores.contains_key(&2));
+    }
+
+    #[test]
+    fn scores_only_count_selected_seeds() {
+        let expansions = expansions(&[(1, &[10]), (2, &[11])]);
+        let seeds = HashSet::from([2]);
+
+        let scores = score_channels(&expansions, Some(&seeds));
+
+        assert_eq!(scores.keys().collect::<Vec<_>>(), vec![&11]);
+    }
+
+    #[test]
+    fn rank_breaks_ties_by_seed_count_then_subscribers() {
+        // 10 and 11 both score 1.0, but 11 is recommended by two seeds; 12
+        // and 13 tie on both, so the bigger channel comes first.
+        let expansions = expansions(&[
+            (1, &[10, 12]),
+            (2, &[20, 11]),
+            (3, &[21, 11]),
+            (4, &[22, 13]),
+        ]);
+        let scores = score_channels(&expansions, None);
+        let channels = vec![
+            channel(10, None),
+            channel(11, None),
+            channel(12, Some(100)),
+            channel(13, Some(500)),
+            channel(99, None),
+        ];
+
+        let ranked: Vec<i64> = rank_channels(channels, &scores)
+            .iter()
+            .map(|r| r.channel.id)
+            .collect();
+
+        assert_eq!(ranked, vec![11, 10, 13, 12]);
+    }
+}
@@ -0,0 +1,727 @@
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use async_trait::async_trait;
+
+use super::{
+    JsonDatabase, SqliteDatabase,
+    backup::BackupInfo,
+    models::{
+        AdRecord, BlacklistEntry, ChannelData, ChannelFilter, ChannelPage, CrawlExpansion,
+        MediaRecord, SavedList, SubscriberSnapshot,
+    },
+};
+use crate::config::{DatabaseBackend, DatabaseConfig};
+
+/// Failure of a repository call that callers handle by kind, e.g. to pick an
+/// HTTP status. Every variant carries the message shown to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepositoryError {
+    /// The record the call refers to isn't stored.
+    NotFound(String),
+    /// Clashes with a stored record, e.g. a name that is already taken.
+    Conflict(String),
+    /// The arguments can't work together, e.g. merging a channel into itself.
+    Invalid(String),
+    /// The backend can't do this at all.
+    Unsupported(String),
+    /// Reading or writing the store failed.
+    Storage(String),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::NotFound(message)
+            | RepositoryError::Conflict(message)
+            | RepositoryError::Invalid(message)
+            | RepositoryError::Unsupported(message)
+            | RepositoryError::Storage(message) => f.write_str(message),
+        }
+    }
+}
+
+impl RepositoryError {
+    pub(super) fn channel_not_found(id: i64) -> Self {
+        RepositoryError::NotFound(format!("Channel with id {} not found", id))
+    }
+}
+
+impl From<String> for RepositoryError {
+    fn from(message: String) -> Self {
+        RepositoryError::Storage(message)
+    }
+}
+
+impl From<RepositoryError> for String {
+    fn from(error: RepositoryError) -> Self {
+        error.to_string()
+    }
+}
+
+#[async_trait]
+pub trait ChannelRepository: Send + Sync {
+    async fn filter_channels(&self, filter: &ChannelFilter) -> Result<ChannelPage, String>;
+
+    async fn get_channel_by_username(&self, username: &str) -> Result<Option<ChannelData>, String>;
+
+    async fn get_channel_by_id(&self, id: i64) -> Result<Option<ChannelData>, String>;
+
+    /// Fails if a channel with the same id is already stored.
+    async fn add_channel(&self, channel: ChannelData) -> Result<(), String>;
+
+    /// Replaces the channel with the same id, or adds it. Matching is by id
+    /// only, so a username taken over by another channel leaves two records
+    /// that show up as a username collision instead of overwriting one.
+    async fn add_or_update_channel(&self, channel: ChannelData) -> Result<(), String>;
+
+    /// `add_or_update_channel` for many channels, written in one go instead of
+    /// once per channel.
+    async fn add_or_update_channels(&self, channels: Vec<ChannelData>) -> Result<(), String>;
+
+    /// Removes the channel with its history, tags and saved-list memberships.
+    /// Blacklist entries are kept.
+    async fn delete_channel(&self, id: i64) -> Result<(), RepositoryError>;
+
+    /// Folds `source_id` into `target_id` (see `merge::merge_channel_data`),
+    /// moving its history, saved-list memberships and blacklist entries over,
+    /// then deletes the source.
+    async fn merge_channels(
+        &self,
+        source_id: i64,
+        target_id: i64,
+    ) -> Result<ChannelData, RepositoryError>;
+
+    async fn update_channel_by_id(
+        &self,
+        id: i64,
+        update_fn: Box<dyn for<'c> FnOnce(&'c mut ChannelData) + Send>,
+    ) -> Result<(), RepositoryError>;
+
+    /// Appends observed subscriber counts as `(channel id, snapshot)` pairs,
+    /// written in one go. A snapshot with the same `observed_at` as an
+    /// existing one for the channel is ignored.
+    async fn record_subscriber_snapshots(
+        &self,
+        snapshots: Vec<(i64, SubscriberSnapshot)>,
+    ) -> Result<(), String>;
+
+    async fn get_subscriber_history(&self, id: i64) -> Result<Vec<SubscriberSnapshot>, String>;
+
+    async fn list_saved_lists(&self) -> Result<Vec<SavedList>, String>;
+
+    async fn get_saved_list(&self, id: i64) -> Result<Option<SavedList>, String>;
+
+    /// Fails if a list with the same name already exists.
+    async fn create_saved_list(
+        &self,
+        name: &str,
+        channel_ids: &[i64],
+    ) -> Result<SavedList, RepositoryError>;
+
+    async fn update_saved_list(
+        &self,
+        id: i64,
+        name: &str,
+        channel_ids: &[i64],
+    ) -> Result<SavedList, RepositoryError>;
+
+    async fn delete_saved_list(&self, id: i64) -> Result<(), RepositoryError>;
+
+    async fn list_blacklist(&self) -> Result<Vec<BlacklistEntry>, String>;
+
+    /// `id` and `created_at` of `entry` are assigned by the store.
+    async fn add_blacklist_entry(&self, entry: BlacklistEntry) -> Result<BlacklistEntry, String>;
+
+    async fn remove_blacklist_entry(&self, id: i64) -> Result<(), RepositoryError>;
+
+    /// Every stored expansion, keyed by seed id.
+    async fn list_crawl_expansions(&self) -> Result<BTreeMap<i64, CrawlExpansion>, String>;
+
+    async fn get_crawl_expansion(&self, seed_id: i64) -> Result<Option<CrawlExpansion>, String>;
+
+    /// Replaces any expansion previously stored for `seed_id`.
+    async fn save_crawl_expansion(
+        &self,
+        seed_id: i64,
+        expansion: CrawlExpansion,
+    ) -> Result<(), String>;
+
+    async fn list_media(&self) -> Result<Vec<MediaRecord>, String>;
+
+    async fn get_media(&self, id: &str) -> Result<Option<MediaRecord>, String>;
+
+    /// Replaces any record with the same media id.
+    async fn save_media(&self, media: MediaRecord) -> Result<(), String>;
+
+    /// Ordered by `ads::sort_ads`.
+    async fn list_ads(&self) -> Result<Vec<AdRecord>, String>;
+
+    async fn get_ad(&self, id: i64) -> Result<Option<AdRecord>, String>;
+
+    /// Inserts or replaces each ad by id; ads not in `ads` are left as they are.
+    async fn save_ads(&self, ads: Vec<AdRecord>) -> Result<(), String>;
+
+    async fn list_backups(&self) -> Result<Vec<BackupInfo>, RepositoryError> {
+        Err(backups_unsupported())
+    }
+
+    async fn restore_backup(&self, _name: &str) -> Result<(), RepositoryError> {
+        Err(backups_unsupported())
+    }
+}
+
+fn backups_unsupported() -> RepositoryError {
+    RepositoryError::Unsupported("Backups are not supported by this database backend".to_string())
+}
+
+pub async fn connect(config: DatabaseConfig) -> Result<Arc<dyn ChannelRepository>, String> {
+    match config.backend {
+        DatabaseBackend::Json => Ok(Arc::new(JsonDatabase::new(config).await?)),
+        DatabaseBackend::Sqlite => Ok(Arc::new(SqliteDatabase::new(config).await?)),
+    }
+}
+
+/// Checks every backend must pass, run against both the JSON and the SQLite
+/// store. Filtering, sorting and paging are compared with `ChannelFilter`'s
+/// in-memory `matches`/`sort`/`paginate` over the same channels.
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use super::*;
+    use crate::{
+        database::models::{ChannelSortField, SortOrder},
+        test_support::{all_backends, channel, json_db, sqlite_db},
+    };
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, day, 12, 0, 0).unwrap()
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    /// Stored the way the API stores them: category, geo and tags lowercase.
+    fn catalog() -> Vec<ChannelData> {
+        vec![
+            ChannelData {
+                title: Some("Крипто Новости".to_string()),
+                category: Some("crypto".to_string()),
+                geo: Some("ru".to_string()),
+                subscribers: Some(1000),
+                tags: tags(&["crypto", "news"]),
+                ..channel(1, "cryptonews")
+            },
+            ChannelData {
+                title: Some("Tech Daily".to_string()),
+                category: Some("tech".to_string()),
+                geo: Some("us".to_string()),
+                subscribers: Some(5000),
+                tags: tags(&["tech"]),
+                ..channel(2, "TechDaily")
+            },
+            ChannelData {
+                description: Some("Обзоры гаджетов".to_string()),
+                category: Some("tech".to_string()),
+                geo: Some("ru".to_string()),
+                ..channel(3, "gadgets")
+            },
+            ChannelData {
+                title: Some("Bitcoin RU".to_string()),
+                category: Some("crypto".to_string()),
+                geo: Some("ru".to_string()),
+                subscribers: Some(250),
+                tags: tags(&["crypto"]),
+                ..channel(4, "bitcoin_ru")
+            },
+            ChannelData {
+                title: Some("misc".to_string()),
+                subscribers: Some(5000),
+                ..channel(5, "misc")
+            },
+        ]
+    }
+
+    async fn seed_catalog(db: &dyn ChannelRepository) -> Vec<ChannelData> {
+        let channels = catalog();
+        for channel in &channels {
+            db.add_channel(channel.clone()).await.unwrap();
+        }
+        channels
+    }
+
+    fn expected_page(channels: &[ChannelData], filter: &ChannelFilter) -> (usize, Vec<i64>) {
+        let mut matching: Vec<ChannelData> = channels
+            .iter()
+            .filter(|c| filter.matches(c))
+            .cloned()
+            .collect();
+        filter.sort(&mut matching);
+        let total = matching.len();
+        (
+            total,
+            filter.paginate(matching).iter().map(|c| c.id).collect(),
+        )
+    }
+
+    async fn page_ids(db: &dyn ChannelRepository, filter: &ChannelFilter) -> (usize, Vec<i64>) {
+        let page = db.filter_channels(filter).await.unwrap();
+        (page.total, page.channels.iter().map(|c| c.id).collect())
+    }
+
+    fn sorted(sort_by: ChannelSortField, order: SortOrder) -> ChannelFilter {
+        ChannelFilter {
+            sort_by: Some(sort_by),
+            order,
+            ..Default::default()
+        }
+    }
+
+    async fn check_filters_match_in_memory_filter(db: &dyn ChannelRepository) {
+        let channels = seed_catalog(db).await;
+
+        let filters = vec![
+            ChannelFilter::default(),
+            ChannelFilter {
+                category: Some("Crypto".to_string()),
+                ..Default::default()
+            },
+            ChannelFilter {
+                geo: Some("RU".to_string()),
+                ..Default::default()
+            },
+            ChannelFilter {
+                tag: Some(" Crypto ".to_string()),
+                ..Default::default()
+            },
+            ChannelFilter {
+                search: Some("НОВОСТИ".to_string()),
+                ..Default::default()
+            },
+            ChannelFilter {
+                search: Some("гаджет".to_string()),
+                ..Default::default()
+            },
+            ChannelFilter {
+                search: Some(" tech ".to_string()),
+                ..Default::default()
+            },
+            ChannelFilter {
+                min_subscribers: Some(500),
+                max_subscribers: Some(4000),
+                ..Default::default()
+            },
+            sorted(ChannelSortField::Subscribers, SortOrder::Asc),
+            sorted(ChannelSortField::Subscribers, SortOrder::Desc),
+            sorted(ChannelSortField::Title, SortOrder::Asc),
+            sorted(ChannelSortField::Title, SortOrder::Desc),
+            sorted(ChannelSortField::Username, SortOrder::Desc),
+            ChannelFilter {
+                limit: Some(2),
+                offset: 1,
+                ..sorted(ChannelSortField::Username, SortOrder::Asc)
+            },
+            ChannelFilter {
+                limit: Some(0),
+                ..Default::default()
+            },
+            ChannelFilter {
+                offset: 10,
+                ..Default::default()
+            },
+            ChannelFilter {
+                category: Some("crypto".to_string()),
+                geo: Some("ru".to_string()),
+                limit: Some(1),
+                ..sorted(ChannelSortField::Subscribers, SortOrder::Desc)
+            },
+        ];
+
+        for filter in &filters {
+            assert_eq!(
+                page_ids(db, filter).await,
+                expected_page(&channels, filter),
+                "{:?}",
+                filter
+            );
+        }
+
+        let page = db.filter_channels(&ChannelFilter::default()).await.unwrap();
+        assert_eq!(page.channels, channels);
+    }
+
+    async fn check_growth_filter_pages_after_filtering(db: &dyn ChannelRepository) {
+        seed_catalog(db).await;
+        let snapshot = |day, subscribers| SubscriberSnapshot {
+            observed_at: at(day),
+            subscribers,
+        };
+        db.record_subscriber_snapshots(vec![
+            (1, snapshot(1, 1000)),
+            (1, snapshot(20, 1200)),
+            (2, snapshot(1, 1000)),
+            (2, snapshot(20, 900)),
+            (4, snapshot(18, 250)),
+        ])
+        .await
+        .unwrap();
+        // Same time as an existing snapshot, so it is ignored.
+        db.record_subscriber_snapshots(vec![(1, snapshot(20, 1))])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.get_subscriber_history(1).await.unwrap(),
+            vec![snapshot(1, 1000), snapshot(20, 1200)]
+        );
+
+        let growing = ChannelFilter {
+            min_growth_7d: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(page_ids(db, &growing).await, (1, vec![1]));
+
+        let shrinking = ChannelFilter {
+            max_growth_7d: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(page_ids(db, &shrinking).await, (1, vec![2]));
+
+        // Channels without enough history never match a growth bound.
+        let paged = ChannelFilter {
+            min_growth_7d: Some(-1.0),
+            limit: Some(1),
+            offset: 1,
+            ..sorted(ChannelSortField::Subscribers, SortOrder::Desc)
+        };
+        assert_eq!(page_ids(db, &paged).await, (2, vec![1]));
+    }
+
+    async fn check_add_channel_rejects_duplicate_id(db: &dyn ChannelRepository) {
+        db.add_channel(channel(1, "first")).await.unwrap();
+
+        let error = db.add_channel(channel(1, "second")).await.unwrap_err();
+        assert_eq!(error, "Channel with id 1 already exists");
+
+        let page = db.filter_channels(&ChannelFilter::default()).await.unwrap();
+        assert_eq!(page.channels, vec![channel(1, "first")]);
+    }
+
+    async fn check_missing_channel_is_not_found(db: &dyn ChannelRepository) {
+        db.add_channel(channel(1, "first")).await.unwrap();
+
+        assert!(matches!(
+            db.update_channel_by_id(2, Box::new(|c| c.tags = tags(&["news"])))
+                .await,
+            Err(RepositoryError::NotFound(_))
+        ));
+
+        assert!(matches!(
+            db.delete_channel(2).await,
+            Err(RepositoryError::NotFound(_))
+        ));
+        assert!(matches!(
+            db.merge_channels(2, 1).await,
+            Err(RepositoryError::NotFound(_))
+        ));
+        assert!(matches!(
+            db.merge_channels(1, 2).await,
+            Err(RepositoryError::NotFound(_))
+        ));
+        assert!(matches!(
+            db.merge_channels(1, 1).await,
+            Err(RepositoryError::Invalid(_))
+        ));
+        assert!(db.get_channel_by_id(1).await.unwrap().is_some());
+    }
+
+    async fn check_saved_lists(db: &dyn ChannelRepository) {
+        let list = db.create_saved_list("crypto", &[2, 1, 2]).await.unwrap();
+        assert_eq!(list.channel_ids, vec![2, 1]);
+        assert!(matches!(
+            db.create_saved_list("crypto", &[3]).await,
+            Err(RepositoryError::Conflict(_))
+        ));
+
+        let news = db.create_saved_list("news", &[]).await.unwrap();
+        assert!(matches!(
+            db.update_saved_list(list.id, "news", &[1]).await,
+            Err(RepositoryError::Conflict(_))
+        ));
+        assert!(matches!(
+            db.update_saved_list(99, "other", &[1]).await,
+            Err(RepositoryError::NotFound(_))
+        ));
+
+        let updated = db
+            .update_saved_list(list.id, "crypto", &[3, 3, 1])
+            .await
+            .unwrap();
+        assert_eq!(updated.channel_ids, vec![3, 1]);
+        assert_eq!(
+            db.get_saved_list(list.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .channel_ids,
+            vec![3, 1]
+        );
+
+        db.delete_saved_list(news.id).await.unwrap();
+        assert!(matches!(
+            db.delete_saved_list(news.id).await,
+            Err(RepositoryError::NotFound(_))
+        ));
+        assert!(db.get_saved_list(news.id).await.unwrap().is_none());
+        let names: Vec<String> = db
+            .list_saved_lists()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|l| l.name)
+            .collect();
+        assert_eq!(names, vec!["crypto"]);
+    }
+
+    async fn check_blacklist(db: &dyn ChannelRepository) {
+        let entry = |channel_id, username: Option<&str>| BlacklistEntry {
+            id: 0,
+            channel_id,
+            username: username.map(str::to_string),
+            reason: "spam".to_string(),
+            created_at: at(1),
+        };
+
+        let first = db.add_blacklist_entry(entry(Some(7), None)).await.unwrap();
+        let second = db
+            .add_blacklist_entry(entry(None, Some("scam")))
+            .await
+            .unwrap();
+        assert_ne!(first.id, second.id);
+        assert!(first.created_at > at(1));
+
+        let listed: Vec<(Option<i64>, Option<String>)> = db
+            .list_blacklist()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.channel_id, e.username))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![(Some(7), None), (None, Some("scam".to_string()))]
+        );
+
+        db.remove_blacklist_entry(first.id).await.unwrap();
+        assert!(matches!(
+            db.remove_blacklist_entry(first.id).await,
+            Err(RepositoryError::NotFound(_))
+        ));
+        let remaining = db.list_blacklist().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, second.id);
+    }
+
+    async fn check_media(db: &dyn ChannelRepository) {
+        let media = |size| MediaRecord {
+            id: "photo:1".to_string(),
+            file_name: "ad.png".to_string(),
+            content_type: "image/png".to_string(),
+            size,
+            uploaded_at: at(1),
+        };
+
+        assert!(db.get_media("photo:1").await.unwrap().is_none());
+        db.save_media(media(10)).await.unwrap();
+        db.save_media(media(20)).await.unwrap();
+
+        let stored = db.get_media("photo:1").await.unwrap().unwrap();
+        assert_eq!(stored.size, 20);
+        assert_eq!(stored.uploaded_at, at(1));
+        assert_eq!(db.list_media().await.unwrap().len(), 1);
+    }
+
+    async fn check_ads(db: &dyn ChannelRepository) {
+        let ad = |id, removed_at| AdRecord {
+            id,
+            title: Some(format!("Ad {}", id)),
+            text: None,
+            promote_url: Some("https://t.me/channel".to_string()),
+            status: "Active".to_string(),
+            views: 100,
+            clicks: 3,
+            spent: 0.25,
+            cpm: 0.1,
+            first_seen_at: at(1),
+            synced_at: at(2),
+            removed_at,
+        };
+
+        db.save_ads(vec![ad(1, None), ad(3, Some(at(3))), ad(2, None)])
+            .await
+            .unwrap();
+        let mut paused = ad(1, None);
+        paused.status = "On Hold".to_string();
+        db.save_ads(vec![paused.clone()]).await.unwrap();
+
+        let ids: Vec<i64> = db.list_ads().await.unwrap().iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![2, 1, 3]);
+        assert_eq!(db.get_ad(1).await.unwrap(), Some(paused));
+        assert_eq!(db.get_ad(3).await.unwrap(), Some(ad(3, Some(at(3)))));
+        assert!(db.get_ad(99).await.unwrap().is_none());
+    }
+
+    /// Channels 1-4 with 1 -> [2, 3], 2 -> [1, 3, 4] and 3 -> [4] crawled.
+    async fn seed_crawl(db: &dyn ChannelRepository) {
+        for id in 1..=4 {
+            db.add_channel(channel(id, &format!("channel{}", id)))
+                .await
+                .unwrap();
+        }
+        for (seed_id, similar_ids) in [(1, vec![2, 3]), (2, vec![1, 3, 4]), (3, vec![4])] {
+            db.save_crawl_expansion(
+                seed_id,
+                CrawlExpansion {
+                    expanded_at: at(1) + Duration::hours(seed_id),
+                    similar_ids,
+                },
+            )
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn similar_ids(db: &dyn ChannelRepository) -> Vec<(i64, Vec<i64>)> {
+        db.list_crawl_expansions()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(seed_id, expansion)| (seed_id, expansion.similar_ids))
+            .collect()
+    }
+
+    async fn check_delete_drops_crawl_edges(db: &dyn ChannelRepository) {
+        seed_crawl(db).await;
+
+        db.delete_channel(3).await.unwrap();
+
+        assert_eq!(similar_ids(db).await, vec![(1, vec![2]), (2, vec![1, 4])]);
+    }
+
+    async fn check_merge_remaps_crawl_edges(db: &dyn ChannelRepository) {
+        seed_crawl(db).await;
+
+        // 4 was never expanded, so it takes over 2's crawl.
+        db.merge_channels(2, 4).await.unwrap();
+        assert_eq!(
+            similar_ids(db).await,
+            vec![(1, vec![4, 3]), (3, vec![4]), (4, vec![1, 3])]
+        );
+
+        // 1 keeps its own crawl, without pointing at itself.
+        db.merge_channels(3, 1).await.unwrap();
+        assert_eq!(similar_ids(db).await, vec![(1, vec![4]), (4, vec![1])]);
+    }
+
+    #[tokio::test]
+    async fn filters_match_in_memory_filter() {
+        for (db, dir) in all_backends("repo-filters").await {
+            check_filters_match_in_memory_filter(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn growth_filter_pages_after_filtering() {
+        for (db, dir) in all_backends("repo-growth").await {
+            check_growth_filter_pages_after_filtering(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn add_channel_rejects_duplicate_id() {
+        for (db, dir) in all_backends("repo-duplicate-id").await {
+            check_add_channel_rejects_duplicate_id(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_channel_is_not_found() {
+        for (db, dir) in all_backends("repo-missing-channel").await {
+            check_missing_channel_is_not_found(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn saved_lists_reject_duplicate_names_and_dedup_channels() {
+        for (db, dir) in all_backends("repo-saved-lists").await {
+            check_saved_lists(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn blacklist_assigns_ids_and_removes_entries() {
+        for (db, dir) in all_backends("repo-blacklist").await {
+            check_blacklist(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn media_is_replaced_by_id() {
+        for (db, dir) in all_backends("repo-media").await {
+            check_media(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn ads_are_replaced_by_id_and_sorted() {
+        for (db, dir) in all_backends("repo-ads").await {
+            check_ads(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_channel_drops_crawl_edges() {
+        for (db, dir) in all_backends("repo-crawl-delete").await {
+            check_delete_drops_crawl_edges(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_channels_remaps_crawl_edges() {
+        for (db, dir) in all_backends("repo-crawl-merge").await {
+            check_merge_remaps_crawl_edges(db.as_ref()).await;
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn backup_errors_tell_unknown_names_from_unsupported_backends() {
+        let (json, json_dir) = json_db("repo-backup-errors-json").await;
+        assert!(matches!(
+            json.restore_backup("missing.json").await,
+            Err(RepositoryError::NotFound(_))
+        ));
+        std::fs::remove_dir_all(json_dir).ok();
+
+        let (sqlite, sqlite_dir) = sqlite_db("repo-backup-errors-sqlite").await;
+        assert!(matches!(
+            sqlite.list_backups().await,
+            Err(RepositoryError::Unsupported(_))
+        ));
+        assert!(matches!(
+            sqlite.restore_backup("missing.json").await,
+            Err(RepositoryError::Unsupported(_))
+        ));
+        std::fs::remove_dir_all(sqlite_dir).ok();
+    }
+}
@@ -0,0 +1,385 @@
+use log::info;
+use serde_json::{Value, json};
+
+use super::models::Database;
+
+pub const CURRENT_SCHEMA_VERSION: u32 = 8;
+
+type Upgrade = fn(&mut Value) -> Result<(), String>;
+
+/// Upgrade steps indexed by the version they upgrade from; `UPGRADES[0]`
+/// turns a version 1 document into version 2 and so on.
+const UPGRADES: [Upgrade; (CURRENT_SCHEMA_VERSION - 1) as usize] = [
+    upgrade_v1_to_v2,
+    upgrade_v2_to_v3,
+    upgrade_v3_to_v4,
+    upgrade_v4_to_v5,
+    upgrade_v5_to_v6,
+    upgrade_v6_to_v7,
+    upgrade_v7_to_v8,
+];
+
+/// Files written before the schema was versioned have no `version` field and
+/// are treated as version 1.
+pub fn stored_version(doc: &Value) -> Result<u32, String> {
+    match doc.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .filter(|v| *v >= 1)
+            .map(|v| v as u32)
+            .ok_or_else(|| format!("Invalid schema version in DB file: {}", version)),
+    }
+}
+
+/// Parses a stored document, running every upgrade between its version and
+/// `CURRENT_SCHEMA_VERSION`. Returns the database and the version it was
+/// stored with.
+pub fn load(contents: &str) -> Result<(Database, u32), String> {
+    let mut doc: Value =
+        serde_json::from_str(contents).map_err(|e| format!("Invalid JSON in DB file: {}", e))?;
+
+    if !doc.is_object() {
+        return Err("Invalid JSON in DB file: expected an object".to_string());
+    }
+
+    let version = stored_version(&doc)?;
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "DB file schema version {} is newer than supported version {}",
+            version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    for from in version..CURRENT_SCHEMA_VERSION {
+        UPGRADES[(from - 1) as usize](&mut doc)?;
+        doc["version"] = json!(from + 1);
+        info!("Upgraded DB schema from version {} to {}", from, from + 1);
+    }
+
+    let data =
+        serde_json::from_value(doc).map_err(|e| format!("Invalid JSON in DB file: {}", e))?;
+    Ok((data, version))
+}
+
+fn upgrade_v1_to_v2(doc: &mut Value) -> Result<(), String> {
+    let channels = doc
+        .as_object_mut()
+        .ok_or("Expected a JSON object")?
+        .entry("channels")
+        .or_insert_with(|| json!([]));
+
+    if !channels.is_array() {
+        return Err("Expected 'channels' to be an array".to_string());
+    }
+    Ok(())
+}
+
+fn upgrade_v2_to_v3(doc: &mut Value) -> Result<(), String> {
+    doc.as_object_mut()
+        .ok_or("Expected a JSON object")?
+        .entry("subscriber_history")
+        .or_insert_with(|| json!({}));
+    Ok(())
+}
+
+fn upgrade_v3_to_v4(doc: &mut Value) -> Result<(), String> {
+    let doc = doc.as_object_mut().ok_or("Expected a JSON object")?;
+
+    for channel in doc
+        .get_mut("channels")
+        .and_then(|c| c.as_array_mut())
+        .ok_or("Expected 'channels' to be an array")?
+    {
+        channel
+            .as_object_mut()
+            .ok_or("Expected channel to be an object")?
+            .entry("tags")
+            .or_insert_with(|| json!([]));
+    }
+
+    doc.entry("saved_lists").or_insert_with(|| json!([]));
+    Ok(())
+}
+
+fn upgrade_v4_to_v5(doc: &mut Value) -> Result<(), String> {
+    doc.as_object_mut()
+        .ok_or("Expected a JSON object")?
+        .entry("blacklist")
+        .or_insert_with(|| json!([]));
+    Ok(())
+}
+
+fn upgrade_v5_to_v6(doc: &mut Value) -> Result<(), String> {
+    doc.as_object_mut()
+        .ok_or("Expected a JSON object")?
+        .entry("crawl_expansions")
+        .or_insert_with(|| json!({}));
+    Ok(())
+}
+
+fn upgrade_v6_to_v7(doc: &mut Value) -> Result<(), String> {
+    doc.as_object_mut()
+        .ok_or("Expected a JSON object")?
+        .entry("media")
+        .or_insert_with(|| json!([]));
+    Ok(())
+}
+
+fn upgrade_v7_to_v8(doc: &mut Value) -> Result<(), String> {
+    doc.as_object_mut()
+        .ok_or("Expected a JSON object")?
+        .entry("ads")
+        .or_insert_with(|| json!([]));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_unversioned_v1_file() {
+        let contents = r#"{
+            "channels": [
+                {
+                    "id": 1001,
+                    "title": "Tech News",
+                    "username": "technews",
+                    "photo_element": null,
+                    "category": "tech",
+                    "description": "Daily tech news",
+                    "subscribers": 1500,
+                    "geo": "ru"
+                }
+            ]
+        }"#;
+
+        let (data, version) = load(contents).unwrap();
+
+        assert_eq!(version, 1);
+        assert_eq!(data.version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(data.channels.len(), 1);
+        assert_eq!(data.channels[0].username, "technews");
+        assert_eq!(data.channels[0].category.as_deref(), Some("tech"));
+        assert_eq!(data.channels[0].subscribers, Some(1500));
+        assert!(data.channels[0].tags.is_empty());
+        assert!(data.saved_lists.is_empty());
+    }
+
+    #[test]
+    fn loads_v1_channels_with_missing_optional_fields() {
+        let contents = r#"{"channels": [{"id": 7, "username": "minimal"}]}"#;
+
+        let (data, _) = load(contents).unwrap();
+
+        assert_eq!(data.channels[0].id, 7);
+        assert!(data.channels[0].title.is_none());
+        assert!(data.channels[0].geo.is_none());
+    }
+
+    #[test]
+    fn loads_v1_file_without_channels() {
+        let (data, version) = load("{}").unwrap();
+
+        assert_eq!(version, 1);
+        assert!(data.channels.is_empty());
+    }
+
+    #[test]
+    fn loads_v2_file_without_subscriber_history() {
+        let contents = r#"{
+            "version": 2,
+            "channels": [{"id": 5, "username": "growing", "subscribers": 900}]
+        }"#;
+
+        let (data, version) = load(contents).unwrap();
+
+        assert_eq!(version, 2);
+        assert_eq!(data.channels[0].subscribers, Some(900));
+        assert!(data.subscriber_history.is_empty());
+    }
+
+    #[test]
+    fn loads_v3_file_without_tags_and_lists() {
+        let contents = r#"{
+            "version": 3,
+            "channels": [{"id": 9, "username": "tagless", "category": "news"}],
+            "subscriber_history": {
+                "9": [{"observed_at": "2025-03-01T12:00:00Z", "subscribers": 50}]
+            }
+        }"#;
+
+        let (data, version) = load(contents).unwrap();
+
+        assert_eq!(version, 3);
+        assert!(data.channels[0].tags.is_empty());
+        assert_eq!(data.channels[0].category.as_deref(), Some("news"));
+        assert_eq!(data.subscriber_history[&9].len(), 1);
+        assert!(data.saved_lists.is_empty());
+    }
+
+    #[test]
+    fn loads_v4_file_without_blacklist() {
+        let contents = r#"{
+            "version": 4,
+            "channels": [{"id": 3, "username": "listed", "tags": ["tested"]}],
+            "subscriber_history": {},
+            "saved_lists": [{
+                "id": 1,
+                "name": "good ctr",
+                "channel_ids": [3],
+                "created_at": "2025-04-01T00:00:00Z",
+                "updated_at": "2025-04-01T00:00:00Z"
+            }]
+        }"#;
+
+        let (data, version) = load(contents).unwrap();
+
+        assert_eq!(version, 4);
+        assert_eq!(data.saved_lists[0].channel_ids, vec![3]);
+        assert!(data.blacklist.is_empty());
+    }
+
+    #[test]
+    fn loads_v5_file_without_crawl_expansions() {
+        let contents = r#"{
+            "version": 5,
+            "channels": [{"id": 4, "username": "blocked", "tags": []}],
+            "subscriber_history": {},
+            "saved_lists": [],
+            "blacklist": [{
+                "id": 1,
+                "channel_id": 4,
+                "username": "blocked",
+                "reason": "spam",
+                "created_at": "2025-05-01T00:00:00Z"
+            }]
+        }"#;
+
+        let (data, version) = load(contents).unwrap();
+
+        assert_eq!(version, 5);
+        assert_eq!(data.blacklist[0].channel_id, Some(4));
+        assert!(data.crawl_expansions.is_empty());
+    }
+
+    #[test]
+    fn loads_v6_file_without_media() {
+        let contents = r#"{
+            "version": 6,
+            "channels": [{"id": 2, "username": "seed", "tags": []}],
+            "subscriber_history": {},
+            "saved_lists": [],
+            "blacklist": [],
+            "crawl_expansions": {
+                "2": {"expanded_at": "2025-06-01T00:00:00Z", "similar_ids": [5]}
+            }
+        }"#;
+
+        let (data, version) = load(contents).unwrap();
+
+        assert_eq!(version, 6);
+        assert_eq!(data.crawl_expansions[&2].similar_ids, vec![5]);
+        assert!(data.media.is_empty());
+    }
+
+    #[test]
+    fn loads_v7_file_without_ads() {
+        let contents = r#"{
+            "version": 7,
+            "channels": [],
+            "subscriber_history": {},
+            "saved_lists": [],
+            "blacklist": [],
+            "crawl_expansions": {},
+            "media": [{
+                "id": "m1",
+                "file_name": "banner.png",
+                "content_type": "image/png",
+                "size": 2048,
+                "uploaded_at": "2025-07-01T00:00:00Z"
+            }]
+        }"#;
+
+        let (data, version) = load(contents).unwrap();
+
+        assert_eq!(version, 7);
+        assert_eq!(data.media[0].size, 2048);
+        assert!(data.ads.is_empty());
+    }
+
+    #[test]
+    fn loads_current_version_unchanged() {
+        let contents = format!(
+            r#"{{
+                "version": {},
+                "channels": [{{"id": 1, "username": "a", "tags": ["tested"]}}],
+                "subscriber_history": {{
+                    "1": [{{"observed_at": "2025-01-01T00:00:00Z", "subscribers": 10}}]
+                }},
+                "saved_lists": [],
+                "blacklist": [],
+                "crawl_expansions": {{
+                    "1": {{"expanded_at": "2025-01-02T00:00:00Z", "similar_ids": [2, 3]}}
+                }},
+                "media": [{{
+                    "id": "m1",
+                    "file_name": "banner.png",
+                    "content_type": "image/png",
+                    "size": 2048,
+                    "uploaded_at": "2025-01-03T00:00:00Z"
+                }}],
+                "ads": [{{
+                    "id": 77,
+                    "title": "Spring sale",
+                    "text": "Up to 50% off",
+                    "promote_url": "https://t.me/shop",
+                    "status": "Active",
+                    "views": 1200,
+                    "clicks": 30,
+                    "spent": 0.6,
+                    "cpm": 0.5,
+                    "first_seen_at": "2025-01-04T00:00:00Z",
+                    "synced_at": "2025-01-05T00:00:00Z",
+                    "removed_at": null
+                }}]
+            }}"#,
+            CURRENT_SCHEMA_VERSION
+        );
+
+        let (data, version) = load(&contents).unwrap();
+
+        assert_eq!(version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(data.channels.len(), 1);
+        assert_eq!(data.subscriber_history[&1][0].subscribers, 10);
+        assert_eq!(data.channels[0].tags, vec!["tested".to_string()]);
+        assert_eq!(data.crawl_expansions[&1].similar_ids, vec![2, 3]);
+        assert_eq!(data.media[0].id, "m1");
+        assert_eq!(data.ads[0].views, 1200);
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let contents = format!(
+            r#"{{"version": {}, "channels": []}}"#,
+            CURRENT_SCHEMA_VERSION + 1
+        );
+
+        let error = load(&contents).unwrap_err();
+
+        assert!(error.contains("newer than supported"));
+    }
+
+    #[test]
+    fn rejects_invalid_version() {
+        assert!(load(r#"{"version": "two", "channels": []}"#).is_err());
+        assert!(load(r#"{"version": 0, "channels": []}"#).is_err());
+    }
+
+    #[test]
+    fn rejects_non_object_document() {
+        assert!(load("[]").is_err());
+        assert!(load(r#"{"channels": {}}"#).is_err());
+    }
+}
@@ -0,0 +1,1071 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use super::{
+    ChannelRepository, RepositoryError,
+    growth::ChannelGrowth,
+    merge::{merge_channel_data, replace_channel_id},
+    models::{
+        AdRecord, BlacklistEntry, ChannelData, ChannelFilter, ChannelPage, ChannelSortField,
+        CrawlExpansion, MediaRecord, SavedList, SortOrder, SubscriberSnapshot, dedup_channel_ids,
+    },
+};
+use crate::config::DatabaseConfig;
+use async_trait::async_trait;
+use chrono::Utc;
+use log::info;
+use rusqlite::{Connection, OptionalExtension, Row, functions::FunctionFlags, params};
+use tokio::sync::Mutex;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS channels (
+        id INTEGER NOT NULL,
+        title TEXT,
+        username TEXT NOT NULL,
+        photo_element TEXT,
+        category TEXT,
+        description TEXT,
+        subscribers INTEGER,
+        geo TEXT
+    );
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_channels_id ON channels (id);
+    CREATE INDEX IF NOT EXISTS idx_channels_username ON channels (username);
+    CREATE INDEX IF NOT EXISTS idx_channels_category ON channels (category);
+    CREATE INDEX IF NOT EXISTS idx_channels_geo ON channels (geo);
+    CREATE TABLE IF NOT EXISTS subscriber_history (
+        channel_id INTEGER NOT NULL,
+        observed_at TEXT NOT NULL,
+        subscribers INTEGER NOT NULL,
+        UNIQUE (channel_id, observed_at)
+    );
+    CREATE TABLE IF NOT EXISTS channel_tags (
+        channel_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        UNIQUE (channel_id, tag)
+    );
+    CREATE INDEX IF NOT EXISTS idx_channel_tags_tag ON channel_tags (tag);
+    CREATE TABLE IF NOT EXISTS saved_lists (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS saved_list_channels (
+        list_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL,
+        PRIMARY KEY (list_id, position),
+        UNIQUE (list_id, channel_id)
+    );
+    CREATE TABLE IF NOT EXISTS blacklist (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        channel_id INTEGER,
+        username TEXT,
+        reason TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_blacklist_channel_id ON blacklist (channel_id);
+    CREATE INDEX IF NOT EXISTS idx_blacklist_username ON blacklist (username);
+    CREATE TABLE IF NOT EXISTS crawl_expansions (
+        seed_id INTEGER PRIMARY KEY,
+        expanded_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS crawl_expansion_channels (
+        seed_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL,
+        PRIMARY KEY (seed_id, position)
+    );
+    CREATE TABLE IF NOT EXISTS media (
+        id TEXT PRIMARY KEY,
+        file_name TEXT NOT NULL,
+        content_type TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        uploaded_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS ads (
+        id INTEGER PRIMARY KEY,
+        title TEXT,
+        text TEXT,
+        promote_url TEXT,
+        status TEXT NOT NULL,
+        views INTEGER NOT NULL,
+        clicks INTEGER NOT NULL,
+        spent REAL NOT NULL,
+        cpm REAL NOT NULL,
+        first_seen_at TEXT NOT NULL,
+        synced_at TEXT NOT NULL,
+        removed_at TEXT
+    );
+";
+
+const CHANNEL_COLUMNS: &str =
+    "id, title, username, photo_element, category, description, subscribers, geo";
+
+const SELECT_CHANNEL_COLUMNS: &str = "id, title, username, photo_element, category, description,
+    subscribers, geo,
+    (SELECT json_group_array(tag ORDER BY position) FROM channel_tags
+     WHERE channel_id = channels.id)";
+
+const SELECT_SAVED_LIST_COLUMNS: &str = "id, name, created_at, updated_at,
+    (SELECT json_group_array(channel_id ORDER BY position) FROM saved_list_channels
+     WHERE list_id = saved_lists.id)";
+
+const MEDIA_COLUMNS: &str = "id, file_name, content_type, size, uploaded_at";
+
+const AD_COLUMNS: &str = "id, title, text, promote_url, status, views, clicks, spent, cpm,
+    first_seen_at, synced_at, removed_at";
+
+const SELECT_CRAWL_EXPANSION_COLUMNS: &str = "expanded_at,
+    (SELECT json_group_array(channel_id ORDER BY position) FROM crawl_expansion_channels
+     WHERE seed_id = crawl_expansions.seed_id)";
+
+fn json_column<T: serde::de::DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    serde_json::from_str(&value).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct SqliteDatabase {
+    conn: Arc<Mutex<Connection>>,
+}
+
+fn saved_list_not_found(id: i64) -> RepositoryError {
+    RepositoryError::NotFound(format!("Saved list with id {} not found", id))
+}
+
+impl SqliteDatabase {
+    pub async fn new(config: DatabaseConfig) -> Result<Self, String> {
+        let file_path = config.file_path;
+        let conn = tokio::task::spawn_blocking(move || {
+            let conn = Connection::open(&file_path)
+                .map_err(|e| format!("Failed to open SQLite DB file: {}", e))?;
+            conn.execute_batch(SCHEMA)
+                .map_err(|e| format!("Failed to create SQLite schema: {}", e))?;
+            // SQLite's lower() only folds ASCII, which misses Cyrillic titles.
+            conn.create_scalar_function(
+                "unicode_lower",
+                1,
+                FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+                |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|s| s.to_lowercase())),
+            )
+            .map_err(|e| format!("Failed to register SQLite functions: {}", e))?;
+            info!("SQLite database opened at {:?}", &file_path);
+            Ok::<_, String>(conn)
+        })
+        .await
+        .map_err(|e| format!("SQLite init task failed: {}", e))??;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let mut conn = self.conn.clone().lock_owned().await;
+        tokio::task::spawn_blocking(move || {
+            f(&mut conn).map_err(|e| format!("SQLite error: {}", e))
+        })
+        .await
+        .map_err(|e| format!("SQLite task failed: {}", e))?
+    }
+
+    fn row_to_channel(row: &Row) -> rusqlite::Result<ChannelData> {
+        Ok(ChannelData {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            username: row.get(2)?,
+            photo_element: row.get(3)?,
+            category: row.get(4)?,
+            description: row.get(5)?,
+            subscribers: row.get(6)?,
+            geo: row.get(7)?,
+            tags: json_column(row, 8)?,
+        })
+    }
+
+    fn row_to_saved_list(row: &Row) -> rusqlite::Result<SavedList> {
+        Ok(SavedList {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            created_at: row.get(2)?,
+            updated_at: row.get(3)?,
+            channel_ids: json_column(row, 4)?,
+        })
+    }
+
+    fn row_to_blacklist_entry(row: &Row) -> rusqlite::Result<BlacklistEntry> {
+        Ok(BlacklistEntry {
+            id: row.get(0)?,
+            channel_id: row.get(1)?,
+            username: row.get(2)?,
+            reason: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }
+
+    fn row_to_crawl_expansion(row: &Row, offset: usize) -> rusqlite::Result<CrawlExpansion> {
+        Ok(CrawlExpansion {
+            expanded_at: row.get(offset)?,
+            similar_ids: json_column(row, offset + 1)?,
+        })
+    }
+
+    fn row_to_media(row: &Row) -> rusqlite::Result<MediaRecord> {
+        Ok(MediaRecord {
+            id: row.get(0)?,
+            file_name: row.get(1)?,
+            content_type: row.get(2)?,
+            size: row.get(3)?,
+            uploaded_at: row.get(4)?,
+        })
+    }
+
+    fn row_to_ad(row: &Row) -> rusqlite::Result<AdRecord> {
+        Ok(AdRecord {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            text: row.get(2)?,
+            promote_url: row.get(3)?,
+            status: row.get(4)?,
+            views: row.get(5)?,
+            clicks: row.get(6)?,
+            spent: row.get(7)?,
+            cpm: row.get(8)?,
+            first_seen_at: row.get(9)?,
+            synced_at: row.get(10)?,
+            removed_at: row.get(11)?,
+        })
+    }
+
+    fn write_tags(conn: &Connection, id: i64, tags: &[String]) -> rusqlite::Result<()> {
+        conn.execute("DELETE FROM channel_tags WHERE channel_id = ?1", [id])?;
+        for (position, tag) in tags.iter().enumerate() {
+            conn.execute(
+                "INSERT OR IGNORE INTO channel_tags (channel_id, position, tag) VALUES (?1, ?2, ?3)",
+                params![id, position as i64, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn find_saved_list(conn: &Connection, id: i64) -> rusqlite::Result<Option<SavedList>> {
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM saved_lists WHERE id = ?1",
+                SELECT_SAVED_LIST_COLUMNS
+            ),
+            [id],
+            Self::row_to_saved_list,
+        )
+        .optional()
+    }
+
+    fn write_saved_list_channels(
+        conn: &Connection,
+        list_id: i64,
+        channel_ids: &[i64],
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "DELETE FROM saved_list_channels WHERE list_id = ?1",
+            [list_id],
+        )?;
+        for (position, channel_id) in dedup_channel_ids(channel_ids).iter().enumerate() {
+            conn.execute(
+                "INSERT INTO saved_list_channels (list_id, position, channel_id) VALUES (?1, ?2, ?3)",
+                params![list_id, position as i64, channel_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn lists_containing(conn: &Connection, channel_id: i64) -> rusqlite::Result<Vec<i64>> {
+        let mut stmt =
+            conn.prepare("SELECT DISTINCT list_id FROM saved_list_channels WHERE channel_id = ?1")?;
+        stmt.query_map([channel_id], |row| row.get(0))?.collect()
+    }
+
+    fn write_crawl_expansion_channels(
+        conn: &Connection,
+        seed_id: i64,
+        similar_ids: &[i64],
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "DELETE FROM crawl_expansion_channels WHERE seed_id = ?1",
+            [seed_id],
+        )?;
+        for (position, channel_id) in similar_ids.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO crawl_expansion_channels (seed_id, position, channel_id)
+                 VALUES (?1, ?2, ?3)",
+                params![seed_id, position as i64, channel_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Seeds whose similar channels include `channel_id`, with those channels.
+    fn expansions_containing(
+        conn: &Connection,
+        channel_id: i64,
+    ) -> rusqlite::Result<Vec<(i64, Vec<i64>)>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT seed_id, {} FROM crawl_expansions WHERE seed_id IN
+             (SELECT seed_id FROM crawl_expansion_channels WHERE channel_id = ?1)",
+            SELECT_CRAWL_EXPANSION_COLUMNS
+        ))?;
+        stmt.query_map([channel_id], |row| {
+            Ok((
+                row.get(0)?,
+                Self::row_to_crawl_expansion(row, 1)?.similar_ids,
+            ))
+        })?
+        .collect()
+    }
+
+    fn touch_saved_list(conn: &Connection, list_id: i64) -> rusqlite::Result<()> {
+        conn.execute(
+            "UPDATE saved_lists SET updated_at = ?1 WHERE id = ?2",
+            params![Utc::now(), list_id],
+        )?;
+        Ok(())
+    }
+
+    fn saved_list_name_taken(
+        conn: &Connection,
+        name: &str,
+        except_id: Option<i64>,
+    ) -> rusqlite::Result<bool> {
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM saved_lists WHERE name = ?1 AND id IS NOT ?2)",
+            params![name, except_id],
+            |row| row.get(0),
+        )
+    }
+
+    fn find_channel(
+        conn: &Connection,
+        condition: &str,
+        param: impl rusqlite::ToSql,
+    ) -> rusqlite::Result<Option<ChannelData>> {
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM channels WHERE {} LIMIT 1",
+                SELECT_CHANNEL_COLUMNS, condition
+            ),
+            [param],
+            Self::row_to_channel,
+        )
+        .optional()
+    }
+
+    /// Several statements; callers run it inside a transaction.
+    fn insert_channel(conn: &Connection, channel: &ChannelData) -> rusqlite::Result<()> {
+        conn.execute(
+            &format!(
+                "INSERT INTO channels ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                CHANNEL_COLUMNS
+            ),
+            params![
+                channel.id,
+                channel.title,
+                channel.username,
+                channel.photo_element,
+                channel.category,
+                channel.description,
+                channel.subscribers,
+                channel.geo,
+            ],
+        )?;
+        Self::write_tags(conn, channel.id, &channel.tags)
+    }
+
+    fn row_to_snapshot(row: &Row) -> rusqlite::Result<SubscriberSnapshot> {
+        Ok(SubscriberSnapshot {
+            observed_at: row.get(0)?,
+            subscribers: row.get(1)?,
+        })
+    }
+
+    fn load_all_histories(
+        conn: &Connection,
+    ) -> rusqlite::Result<HashMap<i64, Vec<SubscriberSnapshot>>> {
+        let mut stmt = conn.prepare(
+            "SELECT observed_at, subscribers, channel_id FROM subscriber_history
+             ORDER BY channel_id, observed_at",
+        )?;
+        let mut histories: HashMap<i64, Vec<SubscriberSnapshot>> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get(2)?, Self::row_to_snapshot(row)?)))?;
+        for row in rows {
+            let (channel_id, snapshot) = row?;
+            histories.entry(channel_id).or_default().push(snapshot);
+        }
+        Ok(histories)
+    }
+
+    /// Several statements; callers run it inside a transaction.
+    fn upsert_channel(conn: &Connection, channel: &ChannelData) -> rusqlite::Result<()> {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM channels WHERE id = ?1)",
+            [channel.id],
+            |row| row.get(0),
+        )?;
+
+        if exists {
+            Self::replace_channel(conn, channel.id, channel)
+        } else {
+            Self::insert_channel(conn, channel)
+        }
+    }
+
+    /// Several statements; callers run it inside a transaction.
+    fn replace_channel(
+        conn: &Connection,
+        existing_id: i64,
+        channel: &ChannelData,
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "UPDATE channels SET id = ?1, title = ?2, username = ?3, photo_element = ?4,
+                category = ?5, description = ?6, subscribers = ?7, geo = ?8
+             WHERE id = ?9",
+            params![
+                channel.id,
+                channel.title,
+                channel.username,
+                channel.photo_element,
+                channel.category,
+                channel.description,
+                channel.subscribers,
+                channel.geo,
+                existing_id,
+            ],
+        )?;
+        if existing_id != channel.id {
+            conn.execute(
+                "DELETE FROM channel_tags WHERE channel_id = ?1",
+                [existing_id],
+            )?;
+        }
+        Self::write_tags(conn, channel.id, &channel.tags)
+    }
+}
+
+#[async_trait]
+impl ChannelRepository for SqliteDatabase {
+    async fn filter_channels(&self, filter: &ChannelFilter) -> Result<ChannelPage, String> {
+        let filter = filter.clone();
+
+        self.with_conn(move |conn| {
+            let where_clause = "WHERE (?1 IS NULL OR category = ?1)
+                    AND (?2 IS NULL OR geo = ?2)
+                    AND (?3 IS NULL OR subscribers >= ?3)
+                    AND (?4 IS NULL OR subscribers <= ?4)
+                    AND (?5 IS NULL
+                        OR instr(unicode_lower(title), ?5) > 0
+                        OR instr(unicode_lower(username), ?5) > 0
+                        OR instr(unicode_lower(description), ?5) > 0)
+                    AND (?6 IS NULL OR EXISTS (
+                        SELECT 1 FROM channel_tags
+                        WHERE channel_id = channels.id AND tag = ?6))";
+            let direction = match filter.order {
+                SortOrder::Asc => "ASC",
+                SortOrder::Desc => "DESC",
+            };
+            let order_clause = match filter.sort_by {
+                None => "ORDER BY rowid".to_string(),
+                Some(ChannelSortField::Subscribers) => format!(
+                    "ORDER BY subscribers IS NULL, subscribers {}, rowid",
+                    direction
+                ),
+                Some(ChannelSortField::Title) => format!(
+                    "ORDER BY title IS NULL, unicode_lower(title) {}, rowid",
+                    direction
+                ),
+                Some(ChannelSortField::Username) => {
+                    format!("ORDER BY unicode_lower(username) {}, rowid", direction)
+                }
+            };
+            let params = params![
+                filter.category.as_ref().map(|c| c.to_lowercase()),
+                filter.geo.as_ref().map(|g| g.to_lowercase()),
+                filter.min_subscribers,
+                filter.max_subscribers,
+                filter.search_term(),
+                filter.tag.as_ref().map(|t| t.trim().to_lowercase()),
+            ];
+
+            // Growth rates are computed from the history in Rust, so with a
+            // growth filter the page has to be cut after filtering here.
+            if filter.has_growth_filter() {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM channels {} {}",
+                    SELECT_CHANNEL_COLUMNS, where_clause, order_clause
+                ))?;
+                let channels: Vec<ChannelData> = stmt
+                    .query_map(params, Self::row_to_channel)?
+                    .collect::<rusqlite::Result<_>>()?;
+
+                let histories = Self::load_all_histories(conn)?;
+                let channels: Vec<ChannelData> = channels
+                    .into_iter()
+                    .filter(|channel| {
+                        ChannelGrowth::from_history(
+                            histories.get(&channel.id).map_or(&[], |h| h.as_slice()),
+                        )
+                        .matches(&filter)
+                    })
+                    .collect();
+
+                return Ok(ChannelPage {
+                    total: channels.len(),
+                    channels: filter.paginate(channels),
+                });
+            }
+
+            let total: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM channels {}", where_clause),
+                params,
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM channels {} {} LIMIT ?7 OFFSET ?8",
+                SELECT_CHANNEL_COLUMNS, where_clause, order_clause
+            ))?;
+            let limit = filter.limit.map_or(-1, |l| l.min(i64::MAX as usize) as i64);
+            let offset = filter.offset.min(i64::MAX as usize) as i64;
+            let channels = stmt
+                .query_map(
+                    rusqlite::params_from_iter(
+                        params
+                            .iter()
+                            .copied()
+                            .chain([&limit as &dyn rusqlite::ToSql, &offset]),
+                    ),
+                    Self::row_to_channel,
+                )?
+                .collect::<rusqlite::Result<_>>()?;
+
+            Ok(ChannelPage {
+                channels,
+                total: total as usize,
+            })
+        })
+        .await
+    }
+
+    async fn get_channel_by_username(&self, username: &str) -> Result<Option<ChannelData>, String> {
+        let username = username.to_string();
+        self.with_conn(move |conn| Self::find_channel(conn, "username = ?1", username))
+            .await
+    }
+
+    async fn get_channel_by_id(&self, id: i64) -> Result<Option<ChannelData>, String> {
+        self.with_conn(move |conn| Self::find_channel(conn, "id = ?1", id))
+            .await
+    }
+
+    async fn add_channel(&self, channel: ChannelData) -> Result<(), String> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            if Self::find_channel(&tx, "id = ?1", channel.id)?.is_some() {
+                return Ok(Err(format!(
+                    "Channel with id {} already exists",
+                    channel.id
+                )));
+            }
+            Self::insert_channel(&tx, &channel)?;
+            tx.commit().map(Ok)
+        })
+        .await?
+    }
+
+    async fn add_or_update_channel(&self, channel: ChannelData) -> Result<(), String> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            Self::upsert_channel(&tx, &channel)?;
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn add_or_update_channels(&self, channels: Vec<ChannelData>) -> Result<(), String> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            for channel in &channels {
+                Self::upsert_channel(&tx, channel)?;
+            }
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn delete_channel(&self, id: i64) -> Result<(), RepositoryError> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let deleted = tx.execute("DELETE FROM channels WHERE id = ?1", [id])?;
+            if deleted == 0 {
+                return Ok(Err(RepositoryError::channel_not_found(id)));
+            }
+
+            tx.execute("DELETE FROM channel_tags WHERE channel_id = ?1", [id])?;
+            tx.execute("DELETE FROM subscriber_history WHERE channel_id = ?1", [id])?;
+            for list_id in Self::lists_containing(&tx, id)? {
+                let list = Self::find_saved_list(&tx, list_id)?;
+                let channel_ids: Vec<i64> = list
+                    .map(|l| l.channel_ids)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|channel_id| *channel_id != id)
+                    .collect();
+                Self::write_saved_list_channels(&tx, list_id, &channel_ids)?;
+                Self::touch_saved_list(&tx, list_id)?;
+            }
+
+            tx.execute("DELETE FROM crawl_expansions WHERE seed_id = ?1", [id])?;
+            tx.execute(
+                "DELETE FROM crawl_expansion_channels WHERE seed_id = ?1 OR channel_id = ?1",
+                [id],
+            )?;
+            tx.commit()?;
+            Ok(Ok(()))
+        })
+        .await?
+    }
+
+    async fn merge_channels(
+        &self,
+        source_id: i64,
+        target_id: i64,
+    ) -> Result<ChannelData, RepositoryError> {
+        if source_id == target_id {
+            return Err(RepositoryError::Invalid(format!(
+                "Cannot merge channel {} into itself",
+                source_id
+            )));
+        }
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let Some(source) = Self::find_channel(&tx, "id = ?1", source_id)? else {
+                return Ok(Err(RepositoryError::channel_not_found(source_id)));
+            };
+            let Some(target) = Self::find_channel(&tx, "id = ?1", target_id)? else {
+                return Ok(Err(RepositoryError::channel_not_found(target_id)));
+            };
+
+            let merged = merge_channel_data(&target, &source);
+            tx.execute("DELETE FROM channels WHERE id = ?1", [source_id])?;
+            tx.execute(
+                "DELETE FROM channel_tags WHERE channel_id = ?1",
+                [source_id],
+            )?;
+            Self::replace_channel(&tx, target_id, &merged)?;
+
+            // The target's snapshot wins when both have one at the same time.
+            tx.execute(
+                "INSERT OR IGNORE INTO subscriber_history (channel_id, observed_at, subscribers)
+                 SELECT ?2, observed_at, subscribers FROM subscriber_history
+                 WHERE channel_id = ?1",
+                [source_id, target_id],
+            )?;
+            tx.execute(
+                "DELETE FROM subscriber_history WHERE channel_id = ?1",
+                [source_id],
+            )?;
+
+            for list_id in Self::lists_containing(&tx, source_id)? {
+                let list = Self::find_saved_list(&tx, list_id)?;
+                let channel_ids = list.map(|l| l.channel_ids).unwrap_or_default();
+                Self::write_saved_list_channels(
+                    &tx,
+                    list_id,
+                    &replace_channel_id(&channel_ids, source_id, target_id),
+                )?;
+                Self::touch_saved_list(&tx, list_id)?;
+            }
+
+            tx.execute(
+                "UPDATE blacklist SET channel_id = ?2 WHERE channel_id = ?1",
+                [source_id, target_id],
+            )?;
+
+            // The target's own crawl wins; the source's is only kept when the
+            // target was never expanded.
+            tx.execute(
+                "INSERT OR IGNORE INTO crawl_expansions (seed_id, expanded_at)
+                 SELECT ?2, expanded_at FROM crawl_expansions WHERE seed_id = ?1",
+                [source_id, target_id],
+            )?;
+            if tx.changes() > 0 {
+                tx.execute(
+                    "UPDATE crawl_expansion_channels SET seed_id = ?2 WHERE seed_id = ?1",
+                    [source_id, target_id],
+                )?;
+                tx.execute(
+                    "DELETE FROM crawl_expansion_channels WHERE seed_id = ?1 AND channel_id = ?1",
+                    [target_id],
+                )?;
+            }
+            tx.execute(
+                "DELETE FROM crawl_expansions WHERE seed_id = ?1",
+                [source_id],
+            )?;
+            tx.execute(
+                "DELETE FROM crawl_expansion_channels WHERE seed_id = ?1",
+                [source_id],
+            )?;
+            for (seed_id, similar_ids) in Self::expansions_containing(&tx, source_id)? {
+                let mut similar_ids = replace_channel_id(&similar_ids, source_id, target_id);
+                similar_ids.retain(|similar_id| *similar_id != seed_id);
+                Self::write_crawl_expansion_channels(&tx, seed_id, &similar_ids)?;
+            }
+            tx.commit()?;
+            Ok(Ok(merged))
+        })
+        .await?
+    }
+
+    async fn update_channel_by_id(
+        &self,
+        id: i64,
+        update_fn: Box<dyn for<'c> FnOnce(&'c mut ChannelData) + Send>,
+    ) -> Result<(), RepositoryError> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let Some(mut channel) = Self::find_channel(&tx, "id = ?1", id)? else {
+                return Ok(Err(RepositoryError::channel_not_found(id)));
+            };
+
+            update_fn(&mut channel);
+
+            Self::replace_channel(&tx, id, &channel)?;
+            tx.commit()?;
+            Ok(Ok(()))
+        })
+        .await?
+    }
+
+    async fn record_subscriber_snapshots(
+        &self,
+        snapshots: Vec<(i64, SubscriberSnapshot)>,
+    ) -> Result<(), String> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            for (id, snapshot) in &snapshots {
+                tx.execute(
+                    "INSERT OR IGNORE INTO subscriber_history (channel_id, observed_at, subscribers)
+                     VALUES (?1, ?2, ?3)",
+                    params![id, snapshot.observed_at, snapshot.subscribers],
+                )?;
+            }
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn get_subscriber_history(&self, id: i64) -> Result<Vec<SubscriberSnapshot>, String> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT observed_at, subscribers FROM subscriber_history
+                 WHERE channel_id = ?1 ORDER BY observed_at",
+            )?;
+            stmt.query_map([id], Self::row_to_snapshot)?.collect()
+        })
+        .await
+    }
+
+    async fn list_saved_lists(&self) -> Result<Vec<SavedList>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM saved_lists ORDER BY id",
+                SELECT_SAVED_LIST_COLUMNS
+            ))?;
+            stmt.query_map([], Self::row_to_saved_list)?.collect()
+        })
+        .await
+    }
+
+    async fn get_saved_list(&self, id: i64) -> Result<Option<SavedList>, String> {
+        self.with_conn(move |conn| Self::find_saved_list(conn, id))
+            .await
+    }
+
+    async fn create_saved_list(
+        &self,
+        name: &str,
+        channel_ids: &[i64],
+    ) -> Result<SavedList, RepositoryError> {
+        let name = name.to_string();
+        let channel_ids = channel_ids.to_vec();
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            if Self::saved_list_name_taken(&tx, &name, None)? {
+                return Ok(Err(RepositoryError::Conflict(format!(
+                    "Saved list '{}' already exists",
+                    name
+                ))));
+            }
+
+            let now = Utc::now();
+            tx.execute(
+                "INSERT INTO saved_lists (name, created_at, updated_at) VALUES (?1, ?2, ?2)",
+                params![name, now],
+            )?;
+            let id = tx.last_insert_rowid();
+            Self::write_saved_list_channels(&tx, id, &channel_ids)?;
+            let list = Self::find_saved_list(&tx, id)?;
+            tx.commit()?;
+            Ok(list.ok_or_else(|| saved_list_not_found(id)))
+        })
+        .await?
+    }
+
+    async fn update_saved_list(
+        &self,
+        id: i64,
+        name: &str,
+        channel_ids: &[i64],
+    ) -> Result<SavedList, RepositoryError> {
+        let name = name.to_string();
+        let channel_ids = channel_ids.to_vec();
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            if Self::saved_list_name_taken(&tx, &name, Some(id))? {
+                return Ok(Err(RepositoryError::Conflict(format!(
+                    "Saved list '{}' already exists",
+                    name
+                ))));
+            }
+
+            let updated = tx.execute(
+                "UPDATE saved_lists SET name = ?1, updated_at = ?2 WHERE id = ?3",
+                params![name, Utc::now(), id],
+            )?;
+            if updated == 0 {
+                return Ok(Err(saved_list_not_found(id)));
+            }
+            Self::write_saved_list_channels(&tx, id, &channel_ids)?;
+            let list = Self::find_saved_list(&tx, id)?;
+            tx.commit()?;
+            Ok(list.ok_or_else(|| saved_list_not_found(id)))
+        })
+        .await?
+    }
+
+    async fn delete_saved_list(&self, id: i64) -> Result<(), RepositoryError> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM saved_list_channels WHERE list_id = ?1", [id])?;
+            let deleted = tx.execute("DELETE FROM saved_lists WHERE id = ?1", [id])?;
+            tx.commit()?;
+            Ok(if deleted == 0 {
+                Err(saved_list_not_found(id))
+            } else {
+                Ok(())
+            })
+        })
+        .await?
+    }
+
+    async fn list_blacklist(&self) -> Result<Vec<BlacklistEntry>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel_id, username, reason, created_at FROM blacklist ORDER BY id",
+            )?;
+            stmt.query_map([], Self::row_to_blacklist_entry)?.collect()
+        })
+        .await
+    }
+
+    async fn add_blacklist_entry(
+        &self,
+        mut entry: BlacklistEntry,
+    ) -> Result<BlacklistEntry, String> {
+        self.with_conn(move |conn| {
+            entry.created_at = Utc::now();
+            conn.execute(
+                "INSERT INTO blacklist (channel_id, username, reason, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    entry.channel_id,
+                    entry.username,
+                    entry.reason,
+                    entry.created_at
+                ],
+            )?;
+            entry.id = conn.last_insert_rowid();
+            Ok(entry)
+        })
+        .await
+    }
+
+    async fn remove_blacklist_entry(&self, id: i64) -> Result<(), RepositoryError> {
+        let deleted = self
+            .with_conn(move |conn| conn.execute("DELETE FROM blacklist WHERE id = ?1", [id]))
+            .await?;
+
+        if deleted == 0 {
+            return Err(RepositoryError::NotFound(format!(
+                "Blacklist entry with id {} not found",
+                id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn list_crawl_expansions(&self) -> Result<BTreeMap<i64, CrawlExpansion>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT seed_id, {} FROM crawl_expansions",
+                SELECT_CRAWL_EXPANSION_COLUMNS
+            ))?;
+            stmt.query_map([], |row| {
+                Ok((row.get(0)?, Self::row_to_crawl_expansion(row, 1)?))
+            })?
+            .collect()
+        })
+        .await
+    }
+
+    async fn get_crawl_expansion(&self, seed_id: i64) -> Result<Option<CrawlExpansion>, String> {
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!(
+                    "SELECT {} FROM crawl_expansions WHERE seed_id = ?1",
+                    SELECT_CRAWL_EXPANSION_COLUMNS
+                ),
+                [seed_id],
+                |row| Self::row_to_crawl_expansion(row, 0),
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn save_crawl_expansion(
+        &self,
+        seed_id: i64,
+        expansion: CrawlExpansion,
+    ) -> Result<(), String> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT OR REPLACE INTO crawl_expansions (seed_id, expanded_at) VALUES (?1, ?2)",
+                params![seed_id, expansion.expanded_at],
+            )?;
+            Self::write_crawl_expansion_channels(&tx, seed_id, &expansion.similar_ids)?;
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn list_media(&self) -> Result<Vec<MediaRecord>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM media ORDER BY rowid",
+                MEDIA_COLUMNS
+            ))?;
+            stmt.query_map([], Self::row_to_media)?.collect()
+        })
+        .await
+    }
+
+    async fn get_media(&self, id: &str) -> Result<Option<MediaRecord>, String> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM media WHERE id = ?1", MEDIA_COLUMNS),
+                [id],
+                Self::row_to_media,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn save_media(&self, media: MediaRecord) -> Result<(), String> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO media ({}) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    MEDIA_COLUMNS
+                ),
+                params![
+                    media.id,
+                    media.file_name,
+                    media.content_type,
+                    media.size,
+                    media.uploaded_at
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_ads(&self) -> Result<Vec<AdRecord>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM ads ORDER BY removed_at IS NOT NULL, id DESC",
+                AD_COLUMNS
+            ))?;
+            stmt.query_map([], Self::row_to_ad)?.collect()
+        })
+        .await
+    }
+
+    async fn get_ad(&self, id: i64) -> Result<Option<AdRecord>, String> {
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM ads WHERE id = ?1", AD_COLUMNS),
+                [id],
+                Self::row_to_ad,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn save_ads(&self, ads: Vec<AdRecord>) -> Result<(), String> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            for ad in &ads {
+                tx.execute(
+                    &format!(
+                        "INSERT OR REPLACE INTO ads ({})
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                        AD_COLUMNS
+                    ),
+                    params![
+                        ad.id,
+                        ad.title,
+                        ad.text,
+                        ad.promote_url,
+                        ad.status,
+                        ad.views,
+                        ad.clicks,
+                        ad.spent,
+                        ad.cpm,
+                        ad.first_seen_at,
+                        ad.synced_at,
+                        ad.removed_at
+                    ],
+                )?;
+            }
+            tx.commit()
+        })
+        .await
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ChannelRepository,
+    migration::validate_channel,
+    models::{ChannelData, normalize_tags},
+};
+
+const TAG_SEPARATOR: char = ';';
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    Csv,
+    #[default]
+    Json,
+}
+
+impl TransferFormat {
+    pub fn from_name(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(TransferFormat::Csv),
+            "json" => Ok(TransferFormat::Json),
+            other => Err(format!("Unknown file format: '{}'", other)),
+        }
+    }
+}
+
+/// Flat CSV row; tags are joined with `;` so the file opens cleanly in a
+/// spreadsheet.
+#[derive(Debug, Deserialize, Serialize)]
+struct CsvChannelRow {
+    id: i64,
+    username: String,
+    title: Option<String>,
+    category: Option<String>,
+    geo: Option<String>,
+    subscribers: Option<i64>,
+    description: Option<String>,
+    #[serde(default)]
+    tags: String,
+    photo_element: Option<String>,
+}
+
+impl From<&ChannelData> for CsvChannelRow {
+    fn from(channel: &ChannelData) -> Self {
+        CsvChannelRow {
+            id: channel.id,
+            username: channel.username.clone(),
+            title: channel.title.clone(),
+            category: channel.category.clone(),
+            geo: channel.geo.clone(),
+            subscribers: channel.subscribers,
+            description: channel.description.clone(),
+            tags: channel.tags.join(&TAG_SEPARATOR.to_string()),
+            photo_element: channel.photo_element.clone(),
+        }
+    }
+}
+
+impl From<CsvChannelRow> for ChannelData {
+    fn from(row: CsvChannelRow) -> Self {
+        let tags: Vec<String> = row.tags.split(TAG_SEPARATOR).map(String::from).collect();
+        ChannelData {
+            id: row.id,
+            title: row.title,
+            username: row.username,
+            photo_element: row.photo_element,
+            category: row.category,
+            description: row.description,
+            subscribers: row.subscribers,
+            geo: row.geo,
+            tags: normalize_tags(&tags),
+        }
+    }
+}
+
+pub fn export_channels(
+    channels: &[ChannelData],
+    format: TransferFormat,
+) -> Result<Vec<u8>, String> {
+    match format {
+        TransferFormat::Json => serde_json::to_vec_pretty(channels)
+            .map_err(|e| format!("Failed to serialize channels: {}", e)),
+        TransferFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for channel in channels {
+                writer
+                    .serialize(CsvChannelRow::from(channel))
+                    .map_err(|e| format!("Failed to write CSV row: {}", e))?;
+            }
+            writer
+                .into_inner()
+                .map_err(|e| format!("Failed to write CSV: {}", e))
+        }
+    }
+}
+
+/// A channel read from an import file, or the reason its row couldn't be read.
+pub type ImportRecord = Result<ChannelData, String>;
+
+/// Parses an import file. A malformed JSON document fails as a whole, while
+/// CSV is read row by row so one bad line doesn't hide the rest.
+pub fn parse_channels(
+    contents: &[u8],
+    format: TransferFormat,
+) -> Result<Vec<ImportRecord>, String> {
+    match format {
+        TransferFormat::Json => {
+            let channels: Vec<ChannelData> = serde_json::from_slice(contents)
+                .map_err(|e| format!("Invalid JSON import file: {}", e))?;
+            Ok(channels.into_iter().map(Ok).collect())
+        }
+        TransferFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(contents);
+            Ok(reader
+                .deserialize::<CsvChannelRow>()
+                .map(|row| row.map(ChannelData::from).map_err(|e| e.to_string()))
+                .collect())
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedChannel {
+    pub row: usize,
+    pub id: i64,
+    pub username: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportIssue {
+    pub row: usize,
+    pub id: Option<i64>,
+    pub username: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub created: Vec<ImportedChannel>,
+    pub updated: Vec<ImportedChannel>,
+    pub unchanged: usize,
+    pub conflicts: Vec<ImportIssue>,
+    pub errors: Vec<ImportIssue>,
+}
+
+enum ImportAction {
+    Create,
+    Update,
+    Unchanged,
+}
+
+/// Fields left empty in the import keep their current value, so a sparse
+/// spreadsheet doesn't wipe out data gathered from Telegram.
+fn merge_into_existing(existing: &ChannelData, mut imported: ChannelData) -> ChannelData {
+    imported.title = imported.title.or_else(|| existing.title.clone());
+    imported.photo_element = imported
+        .photo_element
+        .or_else(|| existing.photo_element.clone());
+    imported.category = imported.category.or_else(|| existing.category.clone());
+    imported.description = imported
+        .description
+        .or_else(|| existing.description.clone());
+    imported.subscribers = imported.subscribers.or(existing.subscribers);
+    imported.geo = imported.geo.or_else(|| existing.geo.clone());
+    if imported.tags.is_empty() {
+        imported.tags = existing.tags.clone();
+    }
+    imported
+}
+
+fn normalize_label(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+}
+
+/// Works out what importing `channel` would do. A record whose username
+/// belongs to a different channel id is a conflict, since writing it would
+/// leave two channels with the same username.
+async fn plan_channel(
+    db: &dyn ChannelRepository,
+    channel: ChannelData,
+) -> Result<(ImportAction, ChannelData), String> {
+    let by_username = db.get_channel_by_username(&channel.username).await?;
+    if let Some(other) = by_username.filter(|c| c.id != channel.id) {
+        return Err(format!(
+            "Username '{}' already belongs to channel {}",
+            channel.username, other.id
+        ));
+    }
+
+    match db.get_channel_by_id(channel.id).await? {
+        None => Ok((ImportAction::Create, channel)),
+        Some(existing) => {
+            let merged = merge_into_existing(&existing, channel);
+            if merged == existing {
+                Ok((ImportAction::Unchanged, merged))
+            } else {
+                Ok((ImportAction::Update, merged))
+            }
+        }
+    }
+}
+
+/// Upserts every valid record with a single `add_or_update_channels` call.
+/// With `dry_run` the report is built the same way but nothing is written.
+pub async fn import_channels(
+    records: Vec<ImportRecord>,
+    db: &dyn ChannelRepository,
+    dry_run: bool,
+) -> ImportReport {
+    let mut report = ImportReport {
+        dry_run,
+        total: records.len(),
+        ..Default::default()
+    };
+    let mut seen_ids: HashMap<i64, usize> = HashMap::new();
+    let mut seen_usernames: HashMap<String, usize> = HashMap::new();
+    let mut planned = vec![];
+
+    for (index, record) in records.into_iter().enumerate() {
+        let row = index + 1;
+        let mut channel = match record {
+            Ok(channel) => channel,
+            Err(reason) => {
+                report.errors.push(ImportIssue {
+                    row,
+                    id: None,
+                    username: None,
+                    reason,
+                });
+                continue;
+            }
+        };
+        channel.username = channel.username.trim().to_string();
+        // Filters compare against lowercase values, as enrichment stores them.
+        channel.category = normalize_label(channel.category);
+        channel.geo = normalize_label(channel.geo);
+        channel.tags = normalize_tags(&channel.tags);
+
+        if let Err(reason) = validate_channel(&channel) {
+            report.errors.push(ImportIssue {
+                row,
+                id: Some(channel.id),
+                username: Some(channel.username),
+                reason,
+            });
+            continue;
+        }
+
+        let duplicate_of = seen_ids
+            .get(&channel.id)
+            .or_else(|| seen_usernames.get(&channel.username));
+        if let Some(first_row) = duplicate_of {
+            report.conflicts.push(ImportIssue {
+                row,
+                id: Some(channel.id),
+                username: Some(channel.username),
+                reason: format!("Duplicates row {} of the import", first_row),
+            });
+            continue;
+        }
+        seen_ids.insert(channel.id, row);
+        seen_usernames.insert(channel.username.clone(), row);
+
+        let id = channel.id;
+        let username = channel.username.clone();
+        let (action, channel) = match plan_channel(db, channel).await {
+            Ok(plan) => plan,
+            Err(reason) => {
+                report.conflicts.push(ImportIssue {
+                    row,
+                    id: Some(id),
+                    username: Some(username),
+                    reason,
+                });
+                continue;
+            }
+        };
+
+        if matches!(action, ImportAction::Unchanged) {
+            report.unchanged += 1;
+            continue;
+        }
+        planned.push((action, ImportedChannel { row, id, username }, channel));
+    }
+
+    let (imported, channels): (Vec<_>, Vec<_>) = planned
+        .into_iter()
+        .map(|(action, imported, channel)| ((action, imported), channel))
+        .unzip();
+    let write_result = if dry_run {
+        Ok(())
+    } else {
+        db.add_or_update_channels(channels).await
+    };
+    for (action, imported) in imported {
+        match (&write_result, action) {
+            (Err(reason), _) => report.errors.push(ImportIssue {
+                row: imported.row,
+                id: Some(imported.id),
+                username: Some(imported.username),
+                reason: format!("Failed to write channel: {}", reason),
+            }),
+            (Ok(()), ImportAction::Create) => report.created.push(imported),
+            (Ok(()), _) => report.updated.push(imported),
+        }
+    }
+
+    info!(
+        "Import{}: {} created, {} updated, {} unchanged, {} conflicts, {} errors",
+        if dry_run { " (dry run)" } else { "" },
+        report.created.len(),
+        report.updated.len(),
+        report.unchanged,
+        report.conflicts.len(),
+        report.errors.len()
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::models::ChannelFilter,
+        test_support::{channel, json_db},
+    };
+
+    fn parse_csv(csv: &str) -> Vec<ImportRecord> {
+        parse_channels(csv.as_bytes(), TransferFormat::Csv).unwrap()
+    }
+
+    #[test]
+    fn parse_csv_reports_bad_row_and_keeps_the_rest() {
+        let records = parse_csv(
+            "id,username,subscribers,tags\n1,first,100,News;TECH\nnot-a-number,second,,\n3,third,,\n",
+        );
+
+        assert_eq!(records.len(), 3);
+        let first = records[0].as_ref().unwrap();
+        assert_eq!(first.subscribers, Some(100));
+        assert_eq!(first.tags, vec!["news", "tech"]);
+        assert!(records[1].is_err());
+        assert_eq!(records[2].as_ref().unwrap().username, "third");
+    }
+
+    #[test]
+    fn parse_json_fails_as_a_whole() {
+        assert!(parse_channels(b"[{\"id\": 1}", TransferFormat::Json).is_err());
+    }
+
+    #[test]
+    fn merge_into_existing_keeps_fields_missing_from_import() {
+        let mut existing = channel(1, "name");
+        existing.title = Some("Title".to_string());
+        existing.description = Some("From Telegram".to_string());
+        existing.subscribers = Some(500);
+        existing.tags = vec!["news".to_string()];
+        let mut imported = channel(1, "name");
+        imported.title = Some("New title".to_string());
+
+        let merged = merge_into_existing(&existing, imported);
+
+        assert_eq!(merged.title.as_deref(), Some("New title"));
+        assert_eq!(merged.description.as_deref(), Some("From Telegram"));
+        assert_eq!(merged.subscribers, Some(500));
+        assert_eq!(merged.tags, vec!["news"]);
+    }
+
+    #[tokio::test]
+    async fn import_reports_errors_duplicates_and_username_conflicts() {
+        let (db, dir) = json_db("transfer-conflicts").await;
+        db.add_channel(channel(10, "taken")).await.unwrap();
+        let records =
+            parse_csv("id,username\n1,fresh\nx,broken\n1,again\n2,fresh\n3,taken\n4,bad name\n");
+
+        let report = import_channels(records, &db, false).await;
+
+        assert_eq!(report.total, 6);
+        assert_eq!(report.created.len(), 1);
+        assert_eq!(report.created[0].username, "fresh");
+        let error_rows: Vec<usize> = report.errors.iter().map(|e| e.row).collect();
+        assert_eq!(error_rows, vec![2, 6]);
+        let conflict_rows: Vec<usize> = report.conflicts.iter().map(|c| c.row).collect();
+        assert_eq!(conflict_rows, vec![3, 4, 5]);
+        assert!(report.conflicts[2].reason.contains("channel 10"));
+        assert!(db.get_channel_by_id(3).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn import_updates_sparse_rows_without_wiping_existing_data() {
+        let (db, dir) = json_db("transfer-sparse").await;
+        let mut existing = channel(1, "known");
+        existing.description = Some("From Telegram".to_string());
+        existing.subscribers = Some(500);
+        db.add_channel(existing).await.unwrap();
+
+        let report = import_channels(
+            parse_csv("id,username,category\n1,known,news\n"),
+            &db,
+            false,
+        )
+        .await;
+        assert_eq!(report.updated.len(), 1);
+
+        let stored = db.get_channel_by_id(1).await.unwrap().unwrap();
+        assert_eq!(stored.category.as_deref(), Some("news"));
+        assert_eq!(stored.description.as_deref(), Some("From Telegram"));
+        assert_eq!(stored.subscribers, Some(500));
+
+        let report = import_channels(
+            parse_csv("id,username,category\n1,known,news\n"),
+            &db,
+            false,
+        )
+        .await;
+        assert_eq!(report.unchanged, 1);
+        assert!(report.updated.is_empty());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_without_writing() {
+        let (db, dir) = json_db("transfer-dry-run").await;
+        db.add_channel(channel(1, "known")).await.unwrap();
+        let file_before = std::fs::read(dir.join("channels.json")).unwrap();
+
+        let report = import_channels(
+            parse_csv("id,username,geo\n1,known,ru\n2,new,\n"),
+            &db,
+            true,
+        )
+        .await;
+
+        assert!(report.dry_run);
+        assert_eq!(report.updated.len(), 1);
+        assert_eq!(report.created.len(), 1);
+        assert!(db.get_channel_by_id(2).await.unwrap().is_none());
+        assert_eq!(db.get_channel_by_id(1).await.unwrap().unwrap().geo, None);
+        assert_eq!(
+            std::fs::read(dir.join("channels.json")).unwrap(),
+            file_before
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn import_lowercases_category_and_geo_for_filters() {
+        let (db, dir) = json_db("transfer-labels").await;
+        let records = parse_csv("id,username,category,geo\n1,techru, Tech ,RU\n");
+
+        let report = import_channels(records, &db, false).await;
+        assert_eq!(report.created.len(), 1);
+
+        let page = db
+            .filter_channels(&ChannelFilter {
+                category: Some("Tech".to_string()),
+                geo: Some("ru".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.channels[0].category.as_deref(), Some("tech"));
+        assert_eq!(page.channels[0].geo.as_deref(), Some("ru"));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
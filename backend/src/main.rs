@@ -1,16 +1,21 @@
 mod api;
+mod cli;
 mod config;
 mod database;
 mod services;
+#[cfg(test)]
+pub(crate) mod test_support;
 mod utils;
 
 use actix_cors::Cors;
 use actix_web::middleware::Logger;
-use actix_web::{web, App, HttpServer};
-use database::JsonDatabase;
+use actix_web::{App, HttpServer, web};
+use clap::Parser;
 use dotenv::dotenv;
 use log::error;
+use services::credentials::CredentialStore;
 use services::openai::OpenAiClient;
+use services::rate_limit::RateLimiters;
 use services::telegram::TelegramService;
 
 #[actix_web::main]
@@ -18,30 +23,54 @@ async fn main() -> std::io::Result<()> {
     dotenv().ok();
     env_logger::init();
 
+    let cli = cli::Cli::parse();
     let config = config::AppConfig::new().unwrap_or_else(|e| {
         error!("Failed to load config: {}", e);
         std::process::exit(1);
     });
-    let db = JsonDatabase::new(config.database.clone())
+
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(command, config).await {
+            error!("Command failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // Held until exit, so offline commands like `backups restore` can tell the
+    // server is using the database.
+    let _db_lock =
+        database::backup::lock_database(&config.database.file_path).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1);
+        });
+    let db = database::connect(config.database.clone())
         .await
         .expect("Failed to init DB");
-    let openai_service = OpenAiClient::new(&config.openai.model, &config.openai.api_key);
-    let telegram_service = TelegramService::new(
-        config.telegram.bot_token.clone(),
-        Some(config.telegram.ads_hash.clone()),
-        Some(config.telegram.ads_stel_ssid.clone()),
-        Some(config.telegram.ads_stel_token.clone()),
-        Some(config.telegram.ads_stel_owner.clone()),
+    let rate_limiters = RateLimiters::new(&config.rate_limits);
+    let openai_service = OpenAiClient::new(&config.openai, rate_limiters.openai.clone())
+        .unwrap_or_else(|e| {
+            error!("Failed to init OpenAI client: {}", e);
+            std::process::exit(1);
+        });
+    let telegram_service = TelegramService::from_config(
+        &config.telegram,
+        &rate_limiters,
         Some(openai_service.clone()),
-    );
-
+    )
+    .unwrap_or_else(|e| {
+        error!("Failed to init Telegram service: {}", e);
+        std::process::exit(1);
+    });
+    let credential_store = CredentialStore::new(&config.credentials);
 
     HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::from(db.clone()))
             .app_data(web::Data::new(config.clone()))
             .app_data(web::Data::new(openai_service.clone()))
             .app_data(web::Data::new(telegram_service.clone()))
+            .app_data(web::Data::new(rate_limiters.clone()))
+            .app_data(web::Data::new(credential_store.clone()))
             .wrap(Logger::default())
             .wrap(
                 Cors::default()
@@ -0,0 +1,521 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::sleep;
+
+use crate::{
+    api::v1::ads::models::{AdMethodType, AdTarget, AdTargetType, CreateAdRequest},
+    database::{
+        ChannelRepository,
+        models::{BlacklistEntry, ChannelData},
+        transfer::TransferFormat,
+    },
+    utils::text::TextUtils,
+};
+
+use super::telegram::TelegramService;
+
+pub const DEFAULT_PLAN_DELAY_MS: u64 = 2000;
+
+const CHANNEL_SEPARATOR: char = ';';
+
+/// One ad of a campaign plan. Only channel targeting is supported; in CSV the
+/// channel usernames are joined with `;`, in JSON they may also be an array.
+#[derive(Debug, Deserialize)]
+struct PlanRow {
+    title: Option<String>,
+    text: String,
+    promote_url: String,
+    cpm: f32,
+    views_per_user: Option<i32>,
+    budget: f32,
+    daily_budget: f32,
+    active: Option<bool>,
+    channels: String,
+    method: Option<AdMethodType>,
+}
+
+impl From<PlanRow> for CreateAdRequest {
+    fn from(row: PlanRow) -> Self {
+        CreateAdRequest {
+            title: row.title,
+            text: row.text,
+            promote_url: row.promote_url,
+            website_name: None,
+            website_photo: None,
+            media: None,
+            ad_info: None,
+            cpm: row.cpm,
+            views_per_user: row.views_per_user.unwrap_or_default(),
+            budget: row.budget,
+            daily_budget: row.daily_budget,
+            active: row.active.unwrap_or_default(),
+            target_type: AdTargetType::Channel,
+            channels: row
+                .channels
+                .split(CHANNEL_SEPARATOR)
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect(),
+            list_id: None,
+            bots: vec![],
+            search_queries: vec![],
+            method: row.method.unwrap_or(AdMethodType::Draft),
+            split_by: None,
+            chunk_size: None,
+        }
+    }
+}
+
+/// An ad read from a plan file, or the reason its row couldn't be read.
+pub type PlanRecord = Result<CreateAdRequest, String>;
+
+fn parse_json_row(mut row: Value) -> PlanRecord {
+    if let Some(channels) = row.get_mut("channels")
+        && let Some(list) = channels.as_array()
+    {
+        let joined: Vec<&str> = list.iter().filter_map(|c| c.as_str()).collect();
+        *channels = Value::String(joined.join(&CHANNEL_SEPARATOR.to_string()));
+    }
+    serde_json::from_value::<PlanRow>(row)
+        .map(CreateAdRequest::from)
+        .map_err(|e| e.to_string())
+}
+
+/// Parses a plan file. As with channel imports, a malformed JSON document
+/// fails as a whole, while a bad row only fails that row.
+pub fn parse_plan(contents: &[u8], format: TransferFormat) -> Result<Vec<PlanRecord>, String> {
+    match format {
+        TransferFormat::Json => {
+            let rows: Vec<Value> = serde_json::from_slice(contents)
+                .map_err(|e| format!("Invalid JSON plan file: {}", e))?;
+            Ok(rows.into_iter().map(parse_json_row).collect())
+        }
+        TransferFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(contents);
+            Ok(reader
+                .deserialize::<PlanRow>()
+                .map(|row| row.map(CreateAdRequest::from).map_err(|e| e.to_string()))
+                .collect())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PlanOptions {
+    /// Validate only; nothing is sent to Telegram.
+    pub dry_run: bool,
+    /// Pause between two submitted ads.
+    pub delay: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanRowStatus {
+    /// Passed validation but was not submitted.
+    Valid,
+    Invalid,
+    Created,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanRowResult {
+    /// 1-based, counting data rows only.
+    pub row: usize,
+    pub status: PlanRowStatus,
+    pub title: Option<String>,
+    pub channels: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PlanRowResult {
+    fn fail(&mut self, status: PlanRowStatus, error_message: String) {
+        self.status = status;
+        match TextUtils::parse_validation_error(&error_message) {
+            Some((field, msg)) => {
+                self.field = Some(field);
+                self.error = Some(msg);
+            }
+            None => self.error = Some(error_message),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CampaignReport {
+    pub dry_run: bool,
+    /// False when the plan had invalid rows or was a dry run.
+    pub submitted: bool,
+    pub total: usize,
+    pub invalid: usize,
+    pub created: usize,
+    pub failed: usize,
+    pub rows: Vec<PlanRowResult>,
+}
+
+/// The catalog lookups a plan is validated against; every
+/// `ChannelRepository` provides them.
+#[async_trait]
+pub trait PlanCatalog: Sync {
+    async fn list_blacklist(&self) -> Result<Vec<BlacklistEntry>, String>;
+    async fn get_channel_by_username(&self, username: &str) -> Result<Option<ChannelData>, String>;
+}
+
+#[async_trait]
+impl<T: ChannelRepository + ?Sized> PlanCatalog for T {
+    async fn list_blacklist(&self) -> Result<Vec<BlacklistEntry>, String> {
+        ChannelRepository::list_blacklist(self).await
+    }
+
+    async fn get_channel_by_username(&self, username: &str) -> Result<Option<ChannelData>, String> {
+        ChannelRepository::get_channel_by_username(self, username).await
+    }
+}
+
+/// Where the ads of a validated plan are sent.
+#[async_trait]
+pub trait AdSubmitter: Sync {
+    async fn create_ad(&self, ad: CreateAdRequest, target: AdTarget) -> Result<String, String>;
+}
+
+#[async_trait]
+impl AdSubmitter for TelegramService {
+    async fn create_ad(&self, ad: CreateAdRequest, target: AdTarget) -> Result<String, String> {
+        TelegramService::create_ad(self, ad, target).await
+    }
+}
+
+fn validation_error(field: &str, error: impl std::fmt::Display) -> String {
+    format!("Validation error in field '{}': {}", field, error)
+}
+
+/// Checks a row against the local catalog and returns its channel ids. Every
+/// channel must be known and none may be blacklisted.
+async fn validate_row(
+    db: &(impl PlanCatalog + ?Sized),
+    blacklist: &[BlacklistEntry],
+    ad: &CreateAdRequest,
+) -> Result<Vec<i64>, String> {
+    ad.validate_lengths()
+        .map_err(|(field, error)| validation_error(field, error))?;
+
+    for (field, value) in [
+        ("cpm", ad.cpm),
+        ("budget", ad.budget),
+        ("daily_budget", ad.daily_budget),
+    ] {
+        if value <= 0.0 {
+            return Err(validation_error(field, "Must be greater than 0"));
+        }
+    }
+    if ad.daily_budget > ad.budget {
+        return Err(validation_error(
+            "daily_budget",
+            "Must not exceed the total budget",
+        ));
+    }
+    if ad.channels.is_empty() {
+        return Err(validation_error(
+            "channels",
+            "At least one channel is required",
+        ));
+    }
+
+    let mut channel_ids = vec![];
+    for username in &ad.channels {
+        let username = TextUtils::normalize_name(username);
+        let channel = db
+            .get_channel_by_username(&username)
+            .await?
+            .ok_or_else(|| {
+                validation_error(
+                    "channels",
+                    format!("Channel '{}' is not in the catalog", username),
+                )
+            })?;
+        if let Some(entry) = blacklist
+            .iter()
+            .find(|entry| entry.matches(channel.id, Some(&channel.username)))
+        {
+            return Err(validation_error(
+                "channels",
+                format!("Channel '{}' is blacklisted: {}", username, entry.reason),
+            ));
+        }
+        if !channel_ids.contains(&channel.id) {
+            channel_ids.push(channel.id);
+        }
+    }
+    Ok(channel_ids)
+}
+
+/// Validates every row first and submits the plan only when all rows pass,
+/// so a campaign is never launched half way because of a typo. Rows are sent
+/// one at a time with `options.delay` in between; a failed row doesn't stop
+/// the rest.
+pub async fn run_plan(
+    db: &(impl PlanCatalog + ?Sized),
+    submitter: &(impl AdSubmitter + ?Sized),
+    records: Vec<PlanRecord>,
+    options: &PlanOptions,
+) -> Result<CampaignReport, String> {
+    let blacklist = db.list_blacklist().await?;
+    let mut report = CampaignReport {
+        dry_run: options.dry_run,
+        total: records.len(),
+        ..Default::default()
+    };
+
+    let mut ads = vec![];
+    for (index, record) in records.into_iter().enumerate() {
+        let mut result = PlanRowResult {
+            row: index + 1,
+            status: PlanRowStatus::Valid,
+            title: None,
+            channels: vec![],
+            message: None,
+            field: None,
+            error: None,
+        };
+
+        match record {
+            Err(e) => result.fail(PlanRowStatus::Invalid, e),
+            Ok(ad) => {
+                result.title = Some(ad.title_or_default());
+                match validate_row(db, &blacklist, &ad).await {
+                    Ok(channel_ids) => {
+                        result.channels = channel_ids.clone();
+                        ads.push(Some((ad, channel_ids)));
+                    }
+                    Err(e) => result.fail(PlanRowStatus::Invalid, e),
+                }
+            }
+        }
+        if result.status == PlanRowStatus::Invalid {
+            report.invalid += 1;
+            ads.push(None);
+        }
+        report.rows.push(result);
+    }
+
+    if options.dry_run || report.invalid > 0 {
+        return Ok(report);
+    }
+
+    report.submitted = true;
+    for (result, ad) in report.rows.iter_mut().zip(ads) {
+        let Some((mut ad, channel_ids)) = ad else {
+            continue;
+        };
+        if report.created + report.failed > 0 {
+            sleep(options.delay).await;
+        }
+
+        ad.title = result.title.clone();
+        match submitter
+            .create_ad(ad, AdTarget::Channels(channel_ids))
+            .await
+        {
+            Ok(message) => {
+                result.status = PlanRowStatus::Created;
+                result.message = Some(message);
+                report.created += 1;
+            }
+            Err(e) => {
+                result.fail(PlanRowStatus::Failed, e);
+                report.failed += 1;
+            }
+        }
+    }
+
+    info!(
+        "Campaign plan submitted: {} created, {} failed",
+        report.created, report.failed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::test_support::channel;
+
+    struct FakeCatalog {
+        channels: Vec<ChannelData>,
+        blacklist: Vec<BlacklistEntry>,
+    }
+
+    #[async_trait]
+    impl PlanCatalog for FakeCatalog {
+        async fn list_blacklist(&self) -> Result<Vec<BlacklistEntry>, String> {
+            Ok(self.blacklist.clone())
+        }
+
+        async fn get_channel_by_username(
+            &self,
+            username: &str,
+        ) -> Result<Option<ChannelData>, String> {
+            Ok(self
+                .channels
+                .iter()
+                .find(|c| c.username == username)
+                .cloned())
+        }
+    }
+
+    /// Records the channels of every submitted ad.
+    #[derive(Default)]
+    struct FakeSubmitter {
+        submitted: Mutex<Vec<Vec<i64>>>,
+    }
+
+    #[async_trait]
+    impl AdSubmitter for FakeSubmitter {
+        async fn create_ad(
+            &self,
+            _ad: CreateAdRequest,
+            target: AdTarget,
+        ) -> Result<String, String> {
+            let AdTarget::Channels(channel_ids) = target else {
+                return Err("Unexpected target".to_string());
+            };
+            self.submitted.lock().unwrap().push(channel_ids);
+            Ok("Ad created".to_string())
+        }
+    }
+
+    fn catalog() -> FakeCatalog {
+        FakeCatalog {
+            channels: vec![channel(1, "crypto"), channel(2, "news"), channel(3, "spam")],
+            blacklist: vec![BlacklistEntry {
+                id: 1,
+                channel_id: Some(3),
+                username: None,
+                reason: "bought subscribers".to_string(),
+                created_at: Utc::now(),
+            }],
+        }
+    }
+
+    fn plan(channels: &[&str]) -> Vec<PlanRecord> {
+        let mut csv = "title,text,promote_url,cpm,budget,daily_budget,channels\n".to_string();
+        for (index, channels) in channels.iter().enumerate() {
+            csv.push_str(&format!(
+                "Ad {},Buy now,https://t.me/shop,1.5,10,2,{}\n",
+                index + 1,
+                channels
+            ));
+        }
+        parse_plan(csv.as_bytes(), TransferFormat::Csv).unwrap()
+    }
+
+    fn options(dry_run: bool) -> PlanOptions {
+        PlanOptions {
+            dry_run,
+            delay: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_plan_is_submitted_row_by_row() {
+        let submitter = FakeSubmitter::default();
+
+        let report = run_plan(
+            &catalog(),
+            &submitter,
+            plan(&["crypto;@news", "news"]),
+            &options(false),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.submitted);
+        assert_eq!(report.created, 2);
+        assert_eq!(
+            *submitter.submitted.lock().unwrap(),
+            vec![vec![1, 2], vec![2]]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_row_blocks_submission() {
+        let submitter = FakeSubmitter::default();
+        let mut records = plan(&["crypto", "news"]);
+        records.push(Err("missing field `text`".to_string()));
+
+        let report = run_plan(&catalog(), &submitter, records, &options(false))
+            .await
+            .unwrap();
+
+        assert!(!report.submitted);
+        assert_eq!(report.invalid, 1);
+        assert_eq!(report.rows[0].status, PlanRowStatus::Valid);
+        assert_eq!(report.rows[2].status, PlanRowStatus::Invalid);
+        assert!(submitter.submitted.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run_validates_without_submitting() {
+        let submitter = FakeSubmitter::default();
+
+        let report = run_plan(&catalog(), &submitter, plan(&["crypto"]), &options(true))
+            .await
+            .unwrap();
+
+        assert!(report.dry_run);
+        assert!(!report.submitted);
+        assert_eq!(report.rows[0].status, PlanRowStatus::Valid);
+        assert_eq!(report.rows[0].channels, vec![1]);
+        assert!(submitter.submitted.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn blacklisted_and_unknown_channels_are_invalid() {
+        let submitter = FakeSubmitter::default();
+
+        let report = run_plan(
+            &catalog(),
+            &submitter,
+            plan(&["crypto;spam", "missing"]),
+            &options(false),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.invalid, 2);
+        for row in &report.rows {
+            assert_eq!(row.status, PlanRowStatus::Invalid);
+            assert_eq!(row.field.as_deref(), Some("channels"));
+        }
+        assert!(
+            report.rows[0]
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("blacklisted")
+        );
+        assert!(
+            report.rows[1]
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("not in the catalog")
+        );
+        assert!(submitter.submitted.lock().unwrap().is_empty());
+    }
+}
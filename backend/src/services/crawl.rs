@@ -0,0 +1,682 @@
+use std::collections::{HashSet, VecDeque};
+
+use actix_web::web;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
+use serde::Serialize;
+
+use crate::{
+    database::{
+        ChannelRepository,
+        models::{ChannelData, CrawlExpansion},
+    },
+    utils::text::TextUtils,
+};
+
+use super::telegram::{TelegramService, TelegramSimilarChat};
+
+/// How long a seed's stored similar channels are reused before Telegram is
+/// asked again.
+const CRAWL_EXPANSION_MAX_AGE_DAYS: i64 = 7;
+
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    pub max_depth: usize,
+    pub max_channels: usize,
+    pub min_subscribers: Option<i64>,
+    pub force: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CrawlReport {
+    pub seeds: usize,
+    /// Seeds whose similar channels were fetched from Telegram on this run.
+    pub expanded: usize,
+    /// Seeds whose similar channels came from an earlier run.
+    pub resumed: usize,
+    pub below_floor: usize,
+    pub depth: usize,
+    pub truncated: bool,
+    /// Seeds that could not be expanded; the crawl went on without them.
+    pub failed_seeds: Vec<CrawlSeedError>,
+    /// Why the crawl stopped early, i.e. an expired ads session.
+    pub error: Option<String>,
+    pub channels: Vec<ChannelData>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrawlSeedError {
+    pub seed_id: i64,
+    pub error: String,
+}
+
+/// The Telegram calls behind a crawl: the similar channels of one seed, and
+/// the lookups that fill in and store the channels found.
+#[async_trait]
+pub trait SimilarChannelSource: Sync {
+    /// Telegram's similar channels of `seed_id`, most similar first.
+    async fn request_similar_channels(
+        &self,
+        seed_id: i64,
+    ) -> Result<Vec<TelegramSimilarChat>, String>;
+
+    /// Stores `channels` with their missing description, category and geo
+    /// filled in.
+    async fn enrich_channels(
+        &self,
+        db: web::Data<dyn ChannelRepository>,
+        channels: Vec<TelegramSimilarChat>,
+        categories: &[String],
+        geos: &[String],
+    ) -> Result<Vec<ChannelData>, String>;
+}
+
+#[async_trait]
+impl SimilarChannelSource for TelegramService {
+    async fn request_similar_channels(
+        &self,
+        seed_id: i64,
+    ) -> Result<Vec<TelegramSimilarChat>, String> {
+        TelegramService::request_similar_channels(self, &[seed_id]).await
+    }
+
+    async fn enrich_channels(
+        &self,
+        db: web::Data<dyn ChannelRepository>,
+        channels: Vec<TelegramSimilarChat>,
+        categories: &[String],
+        geos: &[String],
+    ) -> Result<Vec<ChannelData>, String> {
+        self.enrich_channels_with_missing_data(db, channels, categories.to_vec(), geos.to_vec())
+            .await
+    }
+}
+
+/// Stores a seed's similar channels, in Telegram's order, as its similarity
+/// edges.
+async fn save_similarity_edges(
+    db: &web::Data<dyn ChannelRepository>,
+    seed_id: i64,
+    similar_channels: &[TelegramSimilarChat],
+) -> Result<(), String> {
+    db.save_crawl_expansion(
+        seed_id,
+        CrawlExpansion {
+            expanded_at: Utc::now(),
+            similar_ids: similar_channels.iter().map(|c| c.id).collect(),
+        },
+    )
+    .await
+}
+
+async fn drop_blacklisted(
+    db: &web::Data<dyn ChannelRepository>,
+    channels: Vec<TelegramSimilarChat>,
+) -> Result<Vec<TelegramSimilarChat>, String> {
+    let blacklist = db.list_blacklist().await?;
+    Ok(channels
+        .into_iter()
+        .filter(|channel| {
+            let blocked = blacklist
+                .iter()
+                .any(|entry| entry.matches(channel.id, channel.username.as_deref()));
+            if blocked {
+                info!("Skipping blacklisted channel {:?}", channel.username);
+            }
+            !blocked
+        })
+        .collect())
+}
+
+/// The stored similar channels of `seed_id`, or `None` when the seed was
+/// never expanded or its expansion is older than
+/// `CRAWL_EXPANSION_MAX_AGE_DAYS`. Blacklisted channels and channels deleted
+/// since the expansion was stored are skipped.
+async fn stored_expansion(
+    db: &web::Data<dyn ChannelRepository>,
+    seed_id: i64,
+) -> Result<Option<Vec<ChannelData>>, String> {
+    let Some(expansion) = db.get_crawl_expansion(seed_id).await? else {
+        return Ok(None);
+    };
+    if Utc::now() - expansion.expanded_at > Duration::days(CRAWL_EXPANSION_MAX_AGE_DAYS) {
+        return Ok(None);
+    }
+
+    let blacklist = db.list_blacklist().await?;
+    let mut channels = vec![];
+    for id in expansion.similar_ids {
+        if let Some(channel) = db.get_channel_by_id(id).await?
+            && !blacklist
+                .iter()
+                .any(|entry| entry.matches(channel.id, Some(&channel.username)))
+        {
+            channels.push(channel);
+        }
+    }
+    Ok(Some(channels))
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SimilarChannels {
+    /// Seeds whose similar channels were fetched from Telegram.
+    pub expanded: usize,
+    /// Seeds answered from their stored similar channels.
+    pub resumed: usize,
+    /// Seeds that could not be expanded; the others were still fetched.
+    pub failed_seeds: Vec<CrawlSeedError>,
+    /// Why fetching stopped early, i.e. an expired ads session.
+    pub error: Option<String>,
+    pub channels: Vec<ChannelData>,
+}
+
+/// Similar channels of the seeds, one request per seed, so every result can
+/// be stored as an edge from the seed that returned it. Seeds expanded
+/// recently are answered from the stored edges, like a crawl does, and a seed
+/// that fails is reported without losing what the others returned.
+pub async fn fetch_similar_channels(
+    source: &dyn SimilarChannelSource,
+    db: web::Data<dyn ChannelRepository>,
+    channels: Vec<ChannelData>,
+    categories: Vec<String>,
+    geos: Vec<String>,
+) -> Result<SimilarChannels, String> {
+    info!(
+        "Fetching similar channels for: {}",
+        channels
+            .iter()
+            .map(|c| c.username.clone())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let mut result = SimilarChannels::default();
+    let mut edges = vec![];
+    let mut similar_channels: Vec<TelegramSimilarChat> = vec![];
+    let mut stored_channels: Vec<ChannelData> = vec![];
+    let mut seen_seeds = HashSet::new();
+    for channel in &channels {
+        if !seen_seeds.insert(channel.id) {
+            continue;
+        }
+        if let Some(stored) = stored_expansion(&db, channel.id).await? {
+            result.resumed += 1;
+            stored_channels.extend(stored);
+            continue;
+        }
+
+        let seed_similar = match source.request_similar_channels(channel.id).await {
+            Ok(seed_similar) => seed_similar,
+            Err(e) if TextUtils::is_session_expired_error(&e) => {
+                error!("Stopped fetching similar channels at {}: {}", channel.id, e);
+                result.error = Some(e);
+                break;
+            }
+            Err(e) => {
+                warn!("Skipping seed {}: {}", channel.id, e);
+                result.failed_seeds.push(CrawlSeedError {
+                    seed_id: channel.id,
+                    error: e,
+                });
+                continue;
+            }
+        };
+        result.expanded += 1;
+        for similar in &seed_similar {
+            if !similar_channels.iter().any(|c| c.id == similar.id) {
+                similar_channels.push(similar.clone());
+            }
+        }
+        edges.push((channel.id, seed_similar));
+    }
+    let similar_channels = drop_blacklisted(&db, similar_channels).await?;
+
+    result.channels = source
+        .enrich_channels(db.clone(), similar_channels, &categories, &geos)
+        .await?;
+
+    // Saved once the channels are in the DB, so every edge resolves.
+    for (seed_id, seed_similar) in edges {
+        save_similarity_edges(&db, seed_id, &seed_similar).await?;
+    }
+
+    for channel in stored_channels {
+        if !result.channels.iter().any(|c| c.id == channel.id) {
+            result.channels.push(channel);
+        }
+    }
+    Ok(result)
+}
+
+/// Similar channels of a single crawler seed. A recent stored expansion is
+/// reused unless `force` is set; otherwise Telegram is asked and the result
+/// saved, so an interrupted crawl picks up from the seeds it already
+/// expanded.
+async fn expand_seed(
+    source: &dyn SimilarChannelSource,
+    db: &web::Data<dyn ChannelRepository>,
+    seed_id: i64,
+    force: bool,
+    categories: &[String],
+    geos: &[String],
+) -> Result<(Vec<ChannelData>, bool), String> {
+    if !force && let Some(channels) = stored_expansion(db, seed_id).await? {
+        return Ok((channels, true));
+    }
+
+    let similar_channels = source.request_similar_channels(seed_id).await?;
+    let allowed_channels = drop_blacklisted(db, similar_channels.clone()).await?;
+    let channels = source
+        .enrich_channels(db.clone(), allowed_channels, categories, geos)
+        .await?;
+
+    save_similarity_edges(db, seed_id, &similar_channels).await?;
+    Ok((channels, false))
+}
+
+/// Breadth-first walk over similar channels: every channel found is fed back
+/// in as a seed until `max_depth` hops, `max_channels` results or the end of
+/// the graph is reached. Channels under `min_subscribers` are neither
+/// returned nor expanded.
+pub async fn crawl_similar_channels(
+    source: &dyn SimilarChannelSource,
+    db: web::Data<dyn ChannelRepository>,
+    seeds: Vec<ChannelData>,
+    options: &CrawlOptions,
+    categories: Vec<String>,
+    geos: Vec<String>,
+) -> CrawlReport {
+    let mut report = CrawlReport {
+        seeds: seeds.len(),
+        ..Default::default()
+    };
+    let mut visited: HashSet<i64> = seeds.iter().map(|c| c.id).collect();
+    let mut queue: VecDeque<(i64, usize)> = seeds.iter().map(|c| (c.id, 0)).collect();
+
+    'crawl: while let Some((seed_id, depth)) = queue.pop_front() {
+        if depth >= options.max_depth {
+            continue;
+        }
+
+        let similar =
+            match expand_seed(source, &db, seed_id, options.force, &categories, &geos).await {
+                Ok((similar, true)) => {
+                    report.resumed += 1;
+                    similar
+                }
+                Ok((similar, false)) => {
+                    report.expanded += 1;
+                    similar
+                }
+                Err(e) if TextUtils::is_session_expired_error(&e) => {
+                    error!("Crawl stopped while expanding {}: {}", seed_id, e);
+                    report.error = Some(e);
+                    break;
+                }
+                Err(e) => {
+                    warn!("Skipping crawl seed {}: {}", seed_id, e);
+                    report
+                        .failed_seeds
+                        .push(CrawlSeedError { seed_id, error: e });
+                    continue;
+                }
+            };
+
+        for channel in similar {
+            if !visited.insert(channel.id) {
+                continue;
+            }
+            if channel
+                .subscribers
+                .zip(options.min_subscribers)
+                .is_some_and(|(subscribers, floor)| subscribers < floor)
+            {
+                report.below_floor += 1;
+                continue;
+            }
+            if report.channels.len() >= options.max_channels {
+                report.truncated = true;
+                break 'crawl;
+            }
+
+            report.depth = report.depth.max(depth + 1);
+            queue.push_back((channel.id, depth + 1));
+            report.channels.push(channel);
+        }
+    }
+
+    info!(
+        "Crawl finished: {} channels, {} seeds expanded, {} resumed, {} failed",
+        report.channels.len(),
+        report.expanded,
+        report.resumed,
+        report.failed_seeds.len()
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{
+        database::models::BlacklistEntry,
+        test_support::{channel, json_db},
+        utils::text::SESSION_EXPIRED_ERROR,
+    };
+
+    /// Answers from a fixed similarity graph. A seed missing from `graph`
+    /// fails, one in `expired` fails with an expired session.
+    #[derive(Default)]
+    struct FakeTelegram {
+        graph: HashMap<i64, Vec<i64>>,
+        subscribers: HashMap<i64, i64>,
+        expired: HashSet<i64>,
+        requested: Mutex<Vec<i64>>,
+    }
+
+    impl FakeTelegram {
+        fn new(graph: &[(i64, &[i64])]) -> Self {
+            Self {
+                graph: graph
+                    .iter()
+                    .map(|(seed, similar)| (*seed, similar.to_vec()))
+                    .collect(),
+                ..Default::default()
+            }
+        }
+
+        fn requested(&self) -> Vec<i64> {
+            self.requested.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl SimilarChannelSource for FakeTelegram {
+        async fn request_similar_channels(
+            &self,
+            seed_id: i64,
+        ) -> Result<Vec<TelegramSimilarChat>, String> {
+            self.requested.lock().unwrap().push(seed_id);
+            if self.expired.contains(&seed_id) {
+                return Err(format!("{}: log in again", SESSION_EXPIRED_ERROR));
+            }
+            let similar = self
+                .graph
+                .get(&seed_id)
+                .ok_or_else(|| format!("Request for {} failed", seed_id))?;
+            Ok(similar
+                .iter()
+                .map(|id| TelegramSimilarChat {
+                    id: *id,
+                    title: None,
+                    photo: None,
+                    username: Some(format!("channel{}", id)),
+                    cb_item: None,
+                })
+                .collect())
+        }
+
+        async fn enrich_channels(
+            &self,
+            db: web::Data<dyn ChannelRepository>,
+            channels: Vec<TelegramSimilarChat>,
+            _categories: &[String],
+            _geos: &[String],
+        ) -> Result<Vec<ChannelData>, String> {
+            let channels: Vec<ChannelData> = channels
+                .into_iter()
+                .map(|chat| ChannelData {
+                    subscribers: self.subscribers.get(&chat.id).copied(),
+                    ..channel(chat.id, &chat.username.unwrap_or_default())
+                })
+                .collect();
+            db.add_or_update_channels(channels.clone()).await?;
+            Ok(channels)
+        }
+    }
+
+    async fn temp_db(name: &str) -> (web::Data<dyn ChannelRepository>, PathBuf) {
+        let (db, dir) = json_db(name).await;
+        let db: Arc<dyn ChannelRepository> = Arc::new(db);
+        (web::Data::from(db), dir)
+    }
+
+    fn options(max_depth: usize, max_channels: usize) -> CrawlOptions {
+        CrawlOptions {
+            max_depth,
+            max_channels,
+            min_subscribers: None,
+            force: false,
+        }
+    }
+
+    async fn crawl(
+        source: &FakeTelegram,
+        db: &web::Data<dyn ChannelRepository>,
+        options: &CrawlOptions,
+    ) -> CrawlReport {
+        crawl_similar_channels(
+            source,
+            db.clone(),
+            vec![channel(1, "channel1")],
+            options,
+            vec![],
+            vec![],
+        )
+        .await
+    }
+
+    fn ids(channels: &[ChannelData]) -> Vec<i64> {
+        channels.iter().map(|c| c.id).collect()
+    }
+
+    #[tokio::test]
+    async fn crawl_stops_at_max_depth() {
+        let (db, dir) = temp_db("crawl-depth").await;
+        let source = FakeTelegram::new(&[(1, &[2, 3]), (2, &[4, 1]), (3, &[]), (4, &[5])]);
+
+        let report = crawl(&source, &db, &options(2, 100)).await;
+
+        assert_eq!(ids(&report.channels), vec![2, 3, 4]);
+        assert_eq!(report.depth, 2);
+        assert_eq!(report.expanded, 3);
+        assert!(!report.truncated);
+        assert_eq!(source.requested(), vec![1, 2, 3]);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn crawl_stops_at_max_channels() {
+        let (db, dir) = temp_db("crawl-size").await;
+        let source = FakeTelegram::new(&[(1, &[2, 3]), (2, &[4]), (3, &[5])]);
+
+        let report = crawl(&source, &db, &options(5, 2)).await;
+
+        assert_eq!(ids(&report.channels), vec![2, 3]);
+        assert!(report.truncated);
+        assert_eq!(source.requested(), vec![1, 2]);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn crawl_skips_failing_seed_and_stops_on_expired_session() {
+        let (db, dir) = temp_db("crawl-failures").await;
+        let source = FakeTelegram::new(&[(1, &[2, 3]), (3, &[4]), (4, &[])]);
+
+        let report = crawl(&source, &db, &options(5, 100)).await;
+
+        assert_eq!(ids(&report.channels), vec![2, 3, 4]);
+        let failed: Vec<i64> = report.failed_seeds.iter().map(|f| f.seed_id).collect();
+        assert_eq!(failed, vec![2]);
+        assert!(report.error.is_none());
+        std::fs::remove_dir_all(dir).ok();
+
+        let (db, dir) = temp_db("crawl-expired").await;
+        let mut source = FakeTelegram::new(&[(1, &[2, 3]), (3, &[4])]);
+        source.expired.insert(2);
+
+        let report = crawl(&source, &db, &options(5, 100)).await;
+
+        assert_eq!(ids(&report.channels), vec![2, 3]);
+        assert!(report.failed_seeds.is_empty());
+        assert!(report.error.unwrap().starts_with(SESSION_EXPIRED_ERROR));
+        assert_eq!(source.requested(), vec![1, 2]);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn crawl_drops_blacklisted_and_small_channels() {
+        let (db, dir) = temp_db("crawl-floor").await;
+        let mut source = FakeTelegram::new(&[(1, &[2, 3, 4]), (2, &[]), (4, &[5])]);
+        source.subscribers = HashMap::from([(2, 500), (4, 10)]);
+        db.add_blacklist_entry(BlacklistEntry {
+            id: 0,
+            channel_id: None,
+            username: Some("channel3".to_string()),
+            reason: "spam".to_string(),
+            created_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+        let report = crawl(
+            &source,
+            &db,
+            &CrawlOptions {
+                min_subscribers: Some(100),
+                ..options(5, 100)
+            },
+        )
+        .await;
+
+        assert_eq!(ids(&report.channels), vec![2]);
+        assert_eq!(report.below_floor, 1);
+        assert_eq!(source.requested(), vec![1, 2]);
+        // Blacklisted channels are not stored, but the edge to them is.
+        assert!(db.get_channel_by_id(3).await.unwrap().is_none());
+        assert_eq!(
+            db.get_crawl_expansion(1)
+                .await
+                .unwrap()
+                .unwrap()
+                .similar_ids,
+            vec![2, 3, 4]
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn crawl_resumes_from_stored_expansions() {
+        let (db, dir) = temp_db("crawl-resume").await;
+        let source = FakeTelegram::new(&[(1, &[9]), (2, &[]), (3, &[])]);
+        db.add_channel(channel(2, "channel2")).await.unwrap();
+        db.add_channel(channel(3, "channel3")).await.unwrap();
+        db.save_crawl_expansion(
+            1,
+            CrawlExpansion {
+                expanded_at: Utc::now(),
+                similar_ids: vec![2],
+            },
+        )
+        .await
+        .unwrap();
+        // Too old to be reused.
+        db.save_crawl_expansion(
+            2,
+            CrawlExpansion {
+                expanded_at: Utc::now() - Duration::days(CRAWL_EXPANSION_MAX_AGE_DAYS + 1),
+                similar_ids: vec![3],
+            },
+        )
+        .await
+        .unwrap();
+
+        let report = crawl(&source, &db, &options(2, 100)).await;
+
+        assert_eq!(ids(&report.channels), vec![2]);
+        assert_eq!((report.resumed, report.expanded), (1, 1));
+        assert_eq!(source.requested(), vec![2]);
+
+        let forced = crawl(
+            &source,
+            &db,
+            &CrawlOptions {
+                force: true,
+                ..options(1, 100)
+            },
+        )
+        .await;
+
+        assert_eq!(ids(&forced.channels), vec![9]);
+        assert_eq!((forced.resumed, forced.expanded), (0, 1));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn fetch_similar_keeps_seeds_after_a_failing_one() {
+        let (db, dir) = temp_db("fetch-similar-failures").await;
+        let source = FakeTelegram::new(&[(1, &[3, 4]), (5, &[4, 6])]);
+        let seeds = vec![
+            channel(1, "channel1"),
+            channel(2, "channel2"),
+            channel(5, "channel5"),
+        ];
+
+        let result = fetch_similar_channels(&source, db.clone(), seeds, vec![], vec![])
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&result.channels), vec![3, 4, 6]);
+        assert_eq!(result.expanded, 2);
+        let failed: Vec<i64> = result.failed_seeds.iter().map(|f| f.seed_id).collect();
+        assert_eq!(failed, vec![2]);
+        assert!(result.error.is_none());
+        assert_eq!(
+            db.get_crawl_expansion(5)
+                .await
+                .unwrap()
+                .unwrap()
+                .similar_ids,
+            vec![4, 6]
+        );
+        assert!(db.get_crawl_expansion(2).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn fetch_similar_stops_on_expired_session_and_keeps_earlier_seeds() {
+        let (db, dir) = temp_db("fetch-similar-expired").await;
+        let mut source = FakeTelegram::new(&[(1, &[3]), (5, &[6])]);
+        source.expired.insert(2);
+        let seeds = vec![
+            channel(1, "channel1"),
+            channel(2, "channel2"),
+            channel(5, "channel5"),
+        ];
+
+        let result = fetch_similar_channels(&source, db.clone(), seeds, vec![], vec![])
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&result.channels), vec![3]);
+        assert!(result.error.unwrap().starts_with(SESSION_EXPIRED_ERROR));
+        assert_eq!(source.requested(), vec![1, 2]);
+        assert!(db.get_crawl_expansion(1).await.unwrap().is_some());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,331 @@
+use std::{fs, io::Write, num::NonZeroU32, path::PathBuf, sync::Arc};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use log::info;
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::telegram::{AdsCredentials, TelegramService};
+
+const FILE_VERSION: u32 = 1;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialsConfig {
+    /// Encrypted file holding the ads credentials set at runtime.
+    pub path: PathBuf,
+    /// Passphrase the file key is derived from; without it the file is
+    /// neither read nor written.
+    pub key: Option<String>,
+    /// Bearer token required by `PUT /telegram/credentials` and
+    /// `POST /backups/{name}/restore`; both are disabled when unset.
+    pub admin_token: Option<String>,
+}
+
+/// New ads credentials; unset values keep their current value.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CredentialsUpdate {
+    pub hash: Option<String>,
+    pub stel_ssid: Option<String>,
+    pub stel_token: Option<String>,
+    pub stel_owner: Option<String>,
+}
+
+impl CredentialsUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.hash.is_none()
+            && self.stel_ssid.is_none()
+            && self.stel_token.is_none()
+            && self.stel_owner.is_none()
+    }
+
+    /// `current` with the values of this update, checked to be usable in the
+    /// API URL and the cookie header.
+    pub fn apply_to(&self, current: &AdsCredentials) -> Result<AdsCredentials, String> {
+        let pick = |field: &str, new: &Option<String>, current: &str| match new {
+            None => Ok(current.to_string()),
+            Some(value) => {
+                let value = value.trim();
+                if value.is_empty() {
+                    Err(format!(
+                        "Validation error in field '{}': Must not be empty",
+                        field
+                    ))
+                } else if !value.chars().all(|c| c.is_ascii_graphic() && c != ';') {
+                    Err(format!(
+                        "Validation error in field '{}': Must be printable ASCII without ';'",
+                        field
+                    ))
+                } else {
+                    Ok(value.to_string())
+                }
+            }
+        };
+
+        Ok(AdsCredentials {
+            hash: pick("hash", &self.hash, &current.hash)?,
+            stel_ssid: pick("stel_ssid", &self.stel_ssid, &current.stel_ssid)?,
+            stel_token: pick("stel_token", &self.stel_token, &current.stel_token)?,
+            stel_owner: pick("stel_owner", &self.stel_owner, &current.stel_owner)?,
+        })
+    }
+}
+
+/// On-disk format: the credentials as JSON, sealed with AES-256-GCM under a
+/// key derived from `CredentialsConfig::key` with PBKDF2-HMAC-SHA256.
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey, String> {
+    let iterations =
+        NonZeroU32::new(iterations).ok_or("Invalid credentials file: zero iterations")?;
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| "Failed to create cipher key")?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Reads and writes the encrypted credentials file.
+#[derive(Debug, Clone)]
+pub struct CredentialStore {
+    path: PathBuf,
+    key: Option<String>,
+    /// Held across writing the file and swapping the service's credentials,
+    /// so both always end up with the same values.
+    update_lock: Arc<Mutex<()>>,
+}
+
+impl CredentialStore {
+    pub fn new(config: &CredentialsConfig) -> Self {
+        CredentialStore {
+            path: config.path.clone(),
+            key: config.key.clone(),
+            update_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    fn passphrase(&self) -> Result<&str, String> {
+        self.key
+            .as_deref()
+            .ok_or_else(|| format!("APP_CREDENTIALS_KEY is required to use {:?}", self.path))
+    }
+
+    /// The stored credentials, or `None` if nothing was saved yet.
+    pub fn load(&self) -> Result<Option<AdsCredentials>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let contents =
+            fs::read(&self.path).map_err(|e| format!("Failed to read {:?}: {}", self.path, e))?;
+        let file: EncryptedFile = serde_json::from_slice(&contents)
+            .map_err(|e| format!("Invalid credentials file {:?}: {}", self.path, e))?;
+        if file.version != FILE_VERSION {
+            return Err(format!(
+                "Unsupported credentials file version {} in {:?}",
+                file.version, self.path
+            ));
+        }
+
+        let decode = |value: &str| {
+            BASE64
+                .decode(value)
+                .map_err(|e| format!("Invalid credentials file {:?}: {}", self.path, e))
+        };
+        let salt = decode(&file.salt)?;
+        let nonce = Nonce::try_assume_unique_for_key(&decode(&file.nonce)?)
+            .map_err(|_| format!("Invalid credentials file {:?}: bad nonce", self.path))?;
+        let mut data = decode(&file.ciphertext)?;
+
+        let key = derive_key(self.passphrase()?, &salt, file.iterations)?;
+        let plaintext = key
+            .open_in_place(nonce, Aad::empty(), &mut data)
+            .map_err(|_| {
+                format!(
+                    "Failed to decrypt {:?}: wrong APP_CREDENTIALS_KEY or a damaged file",
+                    self.path
+                )
+            })?;
+        serde_json::from_slice(plaintext)
+            .map(Some)
+            .map_err(|e| format!("Invalid credentials in {:?}: {}", self.path, e))
+    }
+
+    /// Encrypts `credentials` with a fresh salt and nonce and replaces the
+    /// file through a temporary one, so a crash never leaves half a file.
+    pub fn save(&self, credentials: &AdsCredentials) -> Result<(), String> {
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut salt)
+            .and_then(|_| rng.fill(&mut nonce))
+            .map_err(|_| "Failed to generate random bytes")?;
+
+        let key = derive_key(self.passphrase()?, &salt, PBKDF2_ITERATIONS)?;
+        let mut data = serde_json::to_vec(credentials)
+            .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| "Failed to encrypt credentials")?;
+
+        let file = EncryptedFile {
+            version: FILE_VERSION,
+            iterations: PBKDF2_ITERATIONS,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(&data),
+        };
+        let contents = serde_json::to_vec_pretty(&file)
+            .map_err(|e| format!("Failed to serialize credentials file: {}", e))?;
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&tmp_path)
+            .and_then(|mut f| f.write_all(&contents).and_then(|_| f.sync_all()))
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| format!("Failed to write {:?}: {}", self.path, e))
+    }
+
+    /// Applies `update` to the service's current credentials, saves the
+    /// result and hands it to the service. Calls already in flight finish
+    /// with the credentials they started with.
+    pub async fn update(
+        &self,
+        telegram_service: &TelegramService,
+        update: &CredentialsUpdate,
+    ) -> Result<AdsCredentials, String> {
+        let _guard = self.update_lock.lock().await;
+
+        let credentials = update.apply_to(&telegram_service.ads_credentials())?;
+        let store = self.clone();
+        let to_save = credentials.clone();
+        tokio::task::spawn_blocking(move || store.save(&to_save))
+            .await
+            .map_err(|e| format!("Failed to save credentials: {}", e))??;
+
+        telegram_service.set_ads_credentials(credentials.clone());
+        info!("Saved Telegram Ads credentials to {:?}", self.path);
+        Ok(credentials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn temp_store(name: &str, key: Option<&str>) -> (CredentialStore, PathBuf) {
+        let dir = temp_dir(&format!("credentials-{}", name));
+
+        let store = CredentialStore::new(&CredentialsConfig {
+            path: dir.join("credentials.enc"),
+            key: key.map(str::to_string),
+            admin_token: None,
+        });
+        (store, dir)
+    }
+
+    fn credentials() -> AdsCredentials {
+        AdsCredentials {
+            hash: "abc123".to_string(),
+            stel_ssid: "ssid".to_string(),
+            stel_token: "token".to_string(),
+            stel_owner: "owner".to_string(),
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let (store, dir) = temp_store("round-trip", Some("passphrase"));
+        assert_eq!(store.load().unwrap(), None);
+
+        store.save(&credentials()).unwrap();
+
+        let contents = std::fs::read_to_string(dir.join("credentials.enc")).unwrap();
+        assert!(!contents.contains("abc123"));
+        assert_eq!(store.load().unwrap(), Some(credentials()));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn wrong_or_missing_key_is_an_error() {
+        let (store, dir) = temp_store("wrong-key", Some("passphrase"));
+        store.save(&credentials()).unwrap();
+
+        let (other, other_dir) = temp_store("wrong-key-other", Some("another passphrase"));
+        std::fs::copy(
+            dir.join("credentials.enc"),
+            other_dir.join("credentials.enc"),
+        )
+        .unwrap();
+        let error = other.load().unwrap_err();
+        assert!(error.contains("wrong APP_CREDENTIALS_KEY"), "{}", error);
+
+        let (keyless, keyless_dir) = temp_store("no-key", None);
+        std::fs::copy(
+            dir.join("credentials.enc"),
+            keyless_dir.join("credentials.enc"),
+        )
+        .unwrap();
+        assert!(
+            keyless
+                .load()
+                .unwrap_err()
+                .contains("APP_CREDENTIALS_KEY is required")
+        );
+        assert!(keyless.save(&credentials()).is_err());
+
+        for dir in [dir, other_dir, keyless_dir] {
+            std::fs::remove_dir_all(&dir).ok();
+        }
+    }
+
+    #[test]
+    fn update_keeps_unset_values_and_rejects_bad_ones() {
+        let update = CredentialsUpdate {
+            hash: Some(" newhash ".to_string()),
+            ..Default::default()
+        };
+        let updated = update.apply_to(&credentials()).unwrap();
+        assert_eq!(updated.hash, "newhash");
+        assert_eq!(updated.stel_token, "token");
+
+        for value in ["", "a;b", "tab\tvalue"] {
+            let update = CredentialsUpdate {
+                stel_ssid: Some(value.to_string()),
+                ..Default::default()
+            };
+            let error = update.apply_to(&credentials()).unwrap_err();
+            assert!(
+                error.starts_with("Validation error in field 'stel_ssid'"),
+                "{}",
+                error
+            );
+        }
+    }
+}
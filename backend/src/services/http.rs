@@ -0,0 +1,379 @@
+use std::{fmt, sync::Arc};
+
+use log::warn;
+use reqwest::{Client, Proxy, RequestBuilder, Response, StatusCode, header::RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, sleep};
+
+use super::rate_limit::RateLimiter;
+
+pub const DEFAULT_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Settings of the client a service builds once and reuses for all of its
+/// requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    pub connect_timeout_secs: u64,
+    /// Longest wait for the next chunk of a response, so a hung upstream
+    /// can't hold a worker forever.
+    pub read_timeout_secs: u64,
+    /// `http://`, `https://`, `socks5://` or `socks5h://` URL that every
+    /// request goes through.
+    pub proxy: Option<String>,
+    pub user_agent: String,
+}
+
+impl HttpClientConfig {
+    pub fn build_client(&self) -> Result<Client, String> {
+        let mut builder = Client::builder()
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(self.read_timeout_secs))
+            .user_agent(&self.user_agent);
+        if let Some(proxy) = &self.proxy {
+            builder = builder
+                .proxy(Proxy::all(proxy).map_err(|e| format!("Invalid proxy '{}': {}", proxy, e))?);
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }
+}
+
+/// How often and how patiently a request is retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first one included.
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled on every further one.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Longest `retry_after` worth waiting for; a longer flood wait fails the
+    /// request right away instead of blocking the caller.
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with jitter over the upper half of the delay, so
+    /// channels fetched concurrently don't retry in lockstep.
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = delay / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    /// Retrying won't help, e.g. "chat not found".
+    Permanent(String),
+    /// The upstream refused the credentials (401 or 403).
+    Unauthorized(String),
+    /// Network failures, rate limits and server errors that outlasted the
+    /// retries.
+    Transient(String),
+}
+
+impl RequestError {
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            RequestError::Permanent(_) | RequestError::Unauthorized(_)
+        )
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Permanent(message)
+            | RequestError::Unauthorized(message)
+            | RequestError::Transient(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<RequestError> for String {
+    fn from(error: RequestError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Error body of the Bot API, e.g.
+/// `{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 5","parameters":{"retry_after":5}}`.
+#[derive(Deserialize, Debug)]
+struct BotApiError {
+    description: Option<String>,
+    parameters: Option<BotApiErrorParameters>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BotApiErrorParameters {
+    retry_after: Option<u64>,
+}
+
+/// What to do after one attempt.
+enum Outcome {
+    Done(String),
+    Retry {
+        error: RequestError,
+        wait: Option<Duration>,
+    },
+    Fail(RequestError),
+}
+
+/// Sends requests to one upstream with a shared client, waiting for its rate
+/// limiter before every attempt and retrying the transient failures.
+#[derive(Debug, Clone)]
+pub struct HttpLayer {
+    client: Client,
+    policy: RetryPolicy,
+    limiter: Arc<RateLimiter>,
+}
+
+impl HttpLayer {
+    pub fn new(client: Client, policy: RetryPolicy, limiter: Arc<RateLimiter>) -> Self {
+        HttpLayer {
+            client,
+            policy,
+            limiter,
+        }
+    }
+
+    /// Sends the request made by `build` and returns the body of a successful
+    /// response; `context` names the call in logs and errors.
+    ///
+    /// Rate limits and connection failures are always retried, as the request
+    /// never reached the handler. Server errors and timeouts are retried only
+    /// when `idempotent` is set, so an ad is never created twice.
+    pub async fn send(
+        &self,
+        context: &str,
+        idempotent: bool,
+        build: impl Fn(&Client) -> RequestBuilder,
+    ) -> Result<String, RequestError> {
+        let mut attempt = 1;
+        loop {
+            self.limiter.acquire().await;
+            let outcome = match build(&self.client).send().await {
+                Ok(response) => Self::classify_response(context, idempotent, response).await,
+                Err(e) => Self::classify_send_error(context, idempotent, e),
+            };
+
+            let (error, wait) = match outcome {
+                Outcome::Done(body) => return Ok(body),
+                Outcome::Fail(error) => return Err(error),
+                Outcome::Retry { error, wait } => (error, wait),
+            };
+            if attempt >= self.policy.max_attempts {
+                return Err(error);
+            }
+
+            let wait = match wait {
+                Some(wait) if wait > self.policy.max_retry_after => return Err(error),
+                Some(wait) => wait,
+                None => self.policy.backoff(attempt),
+            };
+            warn!(
+                "{} (attempt {}/{}), retrying in {:?}",
+                error, attempt, self.policy.max_attempts, wait
+            );
+            sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    fn classify_send_error(context: &str, idempotent: bool, e: reqwest::Error) -> Outcome {
+        let message = format!("{}: request failed: {}", context, e);
+        if e.is_builder() {
+            return Outcome::Fail(RequestError::Permanent(message));
+        }
+
+        let error = RequestError::Transient(message);
+        if e.is_connect() || (idempotent && e.is_timeout()) {
+            Outcome::Retry { error, wait: None }
+        } else {
+            Outcome::Fail(error)
+        }
+    }
+
+    async fn classify_response(context: &str, idempotent: bool, response: Response) -> Outcome {
+        let status = response.status();
+        let retry_after_header = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        let body = match response.text().await {
+            Ok(body) => body,
+            Err(e) if status.is_success() => {
+                let error =
+                    RequestError::Transient(format!("{}: failed to read response: {}", context, e));
+                return if idempotent {
+                    Outcome::Retry { error, wait: None }
+                } else {
+                    Outcome::Fail(error)
+                };
+            }
+            Err(_) => String::new(),
+        };
+
+        Self::classify_status(context, idempotent, status, retry_after_header, body)
+    }
+
+    /// Decides on a response that was read in full: 429 waits for the
+    /// upstream's `retry_after` (body first, then header), 5xx retries only
+    /// idempotent calls, 401 and 403 mean the credentials were refused.
+    fn classify_status(
+        context: &str,
+        idempotent: bool,
+        status: StatusCode,
+        retry_after_header: Option<u64>,
+        body: String,
+    ) -> Outcome {
+        if status.is_success() {
+            return Outcome::Done(body);
+        }
+
+        let api_error = serde_json::from_str::<BotApiError>(&body).ok();
+        let description = api_error
+            .as_ref()
+            .and_then(|e| e.description.clone())
+            .unwrap_or(body);
+        let message = format!("{}: {} {}", context, status.as_u16(), description.trim());
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = api_error
+                .and_then(|e| e.parameters)
+                .and_then(|p| p.retry_after)
+                .or(retry_after_header);
+            Outcome::Retry {
+                error: RequestError::Transient(message),
+                wait: retry_after.map(Duration::from_secs),
+            }
+        } else if status.is_server_error() {
+            let error = RequestError::Transient(message);
+            if idempotent {
+                Outcome::Retry { error, wait: None }
+            } else {
+                Outcome::Fail(error)
+            }
+        } else if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            Outcome::Fail(RequestError::Unauthorized(message))
+        } else {
+            Outcome::Fail(RequestError::Permanent(message))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(
+        status: u16,
+        idempotent: bool,
+        retry_after_header: Option<u64>,
+        body: &str,
+    ) -> Outcome {
+        HttpLayer::classify_status(
+            "Test call",
+            idempotent,
+            StatusCode::from_u16(status).unwrap(),
+            retry_after_header,
+            body.to_string(),
+        )
+    }
+
+    #[test]
+    fn success_returns_the_body() {
+        assert!(matches!(classify(200, false, None, "ok"), Outcome::Done(body) if body == "ok"));
+    }
+
+    #[test]
+    fn too_many_requests_waits_for_retry_after() {
+        let body = r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 5","parameters":{"retry_after":5}}"#;
+        match classify(429, false, Some(30), body) {
+            Outcome::Retry {
+                error: RequestError::Transient(message),
+                wait,
+            } => {
+                assert_eq!(wait, Some(Duration::from_secs(5)));
+                assert_eq!(message, "Test call: 429 Too Many Requests: retry after 5");
+            }
+            _ => panic!("429 should be retried"),
+        }
+
+        assert!(matches!(
+            classify(429, false, Some(30), ""),
+            Outcome::Retry { wait: Some(wait), .. } if wait == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            classify(429, false, None, ""),
+            Outcome::Retry { wait: None, .. }
+        ));
+    }
+
+    #[test]
+    fn server_errors_are_retried_only_when_idempotent() {
+        assert!(matches!(
+            classify(502, true, None, "Bad Gateway"),
+            Outcome::Retry {
+                error: RequestError::Transient(_),
+                wait: None
+            }
+        ));
+        assert!(matches!(
+            classify(502, false, None, "Bad Gateway"),
+            Outcome::Fail(RequestError::Transient(_))
+        ));
+    }
+
+    #[test]
+    fn refused_credentials_and_client_errors_fail() {
+        for status in [401, 403] {
+            assert!(matches!(
+                classify(status, true, None, ""),
+                Outcome::Fail(RequestError::Unauthorized(_))
+            ));
+        }
+        assert!(matches!(
+            classify(400, true, None, r#"{"ok":false,"description":"Bad Request: chat not found"}"#),
+            Outcome::Fail(RequestError::Permanent(message)) if message == "Test call: 400 Bad Request: chat not found"
+        ));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            max_retry_after: Duration::from_secs(1),
+        };
+        for (retry, full) in [(1, 100), (2, 200), (3, 400), (6, 500)] {
+            let delay = policy.backoff(retry);
+            let full = Duration::from_millis(full);
+            assert!(
+                delay >= full / 2 && delay <= full,
+                "retry {}: {:?}",
+                retry,
+                delay
+            );
+        }
+    }
+}
@@ -1,2 +1,7 @@
-pub mod telegram;
+pub mod campaign;
+pub mod crawl;
+pub mod credentials;
+pub mod http;
 pub mod openai;
+pub mod rate_limit;
+pub mod telegram;
@@ -1,12 +1,17 @@
+use std::sync::Arc;
+
 use log::{debug, error, info, warn};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+use super::{http::HttpClientConfig, rate_limit::RateLimiter};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAiConfig {
     pub api_key: String,
     pub model: String,
+    pub http: HttpClientConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,18 +40,20 @@ pub struct OpenAiClient {
     client: Client,
     api_key: String,
     model: String,
+    limiter: Arc<RateLimiter>,
 }
 
 impl OpenAiClient {
-    pub fn new(model: &str, api_key: &str) -> Self {
-        if model.is_empty() {
-            panic!("OpenAI model must be specified");
-        }
-        Self {
-            client: Client::new(),
-            api_key: api_key.to_string(),
-            model: model.to_string(),
+    pub fn new(config: &OpenAiConfig, limiter: Arc<RateLimiter>) -> Result<Self, String> {
+        if config.model.is_empty() {
+            return Err("OpenAI model must be specified".to_string());
         }
+        Ok(Self {
+            client: config.http.build_client()?,
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            limiter,
+        })
     }
 
     async fn send_chat_completion(
@@ -64,6 +71,7 @@ impl OpenAiClient {
             "temperature": temperature.unwrap_or(0.0),
         });
 
+        self.limiter.acquire().await;
         let response = self
             .client
             .post("https://api.openai.com/v1/chat/completions")
@@ -173,9 +181,7 @@ impl OpenAiClient {
         found_description: &String,
         product_description: &String,
     ) -> Result<String, String> {
-        let system_prompt = format!(
-            "Ты — профессиональный маркетолог в telegram ads. Твоя задача — предоставить максимально релевантное рекламное сообщения для каналов с описанием. Используй небольшое количество эмоджи. Сообщение должно быть не более 160 символов."
-        );
+        let system_prompt = "Ты — профессиональный маркетолог в telegram ads. Твоя задача — предоставить максимально релевантное рекламное сообщения для каналов с описанием. Используй небольшое количество эмоджи. Сообщение должно быть не более 160 символов.".to_string();
 
         let user_prompt = format!(
             "Рекламируется продукт: {}. На каналах с описнаием ```{}```",
@@ -0,0 +1,240 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::Mutex,
+    time::{Duration, Instant, sleep},
+};
+
+/// Requests per second allowed to one upstream, with `burst` requests that
+/// may go out at once after a quiet period.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Parses a rate like "20" or "0.5" requests per second; an empty value
+    /// gives `default`. The burst is one second's worth of requests.
+    pub fn from_env_value(value: &str, default: f64) -> Result<Self, String> {
+        let per_second = match value.trim() {
+            "" => default,
+            value => value
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| rate.is_finite() && *rate > 0.0)
+                .ok_or_else(|| format!("Invalid rate limit: '{}'", value))?,
+        };
+        Ok(RateLimit {
+            per_second,
+            burst: (per_second.ceil() as u32).max(1),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub bot_api: RateLimit,
+    pub ads_api: RateLimit,
+    pub openai: RateLimit,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// A token bucket shared by every caller of one upstream.
+#[derive(Debug)]
+pub struct RateLimiter {
+    name: &'static str,
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+    queued: AtomicUsize,
+}
+
+/// Leaves the queue when the wait ends, also when the waiting request is
+/// dropped.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimiterStatus {
+    pub upstream: &'static str,
+    pub per_second: f64,
+    pub burst: u32,
+    /// Requests waiting for a token right now; above zero means throttled.
+    pub queued: usize,
+}
+
+impl RateLimiter {
+    pub fn new(name: &'static str, limit: RateLimit) -> Self {
+        RateLimiter {
+            name,
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+            }),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits until a request may be sent to the upstream.
+    pub async fn acquire(&self) {
+        let queued = self.queued.fetch_add(1, Ordering::SeqCst) + 1;
+        let _queued = QueuedGuard(&self.queued);
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens =
+                    (bucket.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    break;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.limit.per_second)
+            };
+            debug!(
+                "Rate limit of {} reached, {} queued, waiting {:?}",
+                self.name, queued, wait
+            );
+            sleep(wait).await;
+        }
+    }
+
+    pub fn status(&self) -> RateLimiterStatus {
+        RateLimiterStatus {
+            upstream: self.name,
+            per_second: self.limit.per_second,
+            burst: self.limit.burst,
+            queued: self.queued.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// One limiter per upstream, created once per process and shared by every
+/// client talking to that upstream.
+#[derive(Debug, Clone)]
+pub struct RateLimiters {
+    pub bot_api: Arc<RateLimiter>,
+    pub ads_api: Arc<RateLimiter>,
+    pub openai: Arc<RateLimiter>,
+}
+
+impl RateLimiters {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        RateLimiters {
+            bot_api: Arc::new(RateLimiter::new("bot_api", config.bot_api)),
+            ads_api: Arc::new(RateLimiter::new("ads_api", config.ads_api)),
+            openai: Arc::new(RateLimiter::new("openai", config.openai)),
+        }
+    }
+
+    pub fn status(&self) -> Vec<RateLimiterStatus> {
+        vec![
+            self.bot_api.status(),
+            self.ads_api.status(),
+            self.openai.status(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_second: f64, burst: u32) -> Arc<RateLimiter> {
+        Arc::new(RateLimiter::new("test", RateLimit { per_second, burst }))
+    }
+
+    #[test]
+    fn from_env_value_sets_burst_to_one_second() {
+        let limit = RateLimit::from_env_value("2.5", 1.0).unwrap();
+        assert_eq!((limit.per_second, limit.burst), (2.5, 3));
+
+        let limit = RateLimit::from_env_value(" ", 0.5).unwrap();
+        assert_eq!((limit.per_second, limit.burst), (0.5, 1));
+
+        for value in ["0", "-1", "fast", "inf"] {
+            assert!(RateLimit::from_env_value(value, 1.0).is_err(), "{}", value);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_goes_out_at_once_then_tokens_refill() {
+        let limiter = limiter(10.0, 2);
+        let start = Instant::now();
+
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+
+        // A quiet period refills the bucket, but never above the burst.
+        sleep(Duration::from_secs(5)).await;
+        let start = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn status_counts_waiting_requests() {
+        let limiter = limiter(1.0, 1);
+        limiter.acquire().await;
+        assert_eq!(limiter.status().queued, 0);
+
+        let waiting: Vec<_> = (0..3)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move { limiter.acquire().await })
+            })
+            .collect();
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.status().queued, 3);
+
+        let start = Instant::now();
+        for task in waiting {
+            task.await.unwrap();
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+        assert_eq!(limiter.status().queued, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn dropped_waiter_leaves_the_queue() {
+        let limiter = limiter(1.0, 1);
+        limiter.acquire().await;
+
+        let waiting = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await })
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.status().queued, 1);
+
+        waiting.abort();
+        let _ = waiting.await;
+        assert_eq!(limiter.status().queued, 0);
+    }
+}
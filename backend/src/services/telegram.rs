@@ -1,30 +1,99 @@
+use chrono::Utc;
 use futures::stream::{self, StreamExt};
 use std::collections::HashMap;
-use tokio::time::{Duration, sleep};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, RwLock};
 
 use actix_web::web;
+use mime::Mime;
 use reqwest::{
-    Client,
     header::{ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE, COOKIE, HeaderMap, HeaderValue},
+    multipart::{Form, Part},
 };
 
 use crate::{
-    api::v1::ads::models::CreateAdRequest,
-    database::{JsonDatabase, models::ChannelData},
-    utils::html_parser::extract_subscribers,
+    api::v1::ads::models::{AdTarget, CreateAdRequest, UpdateAdRequest},
+    database::{
+        ChannelRepository,
+        ads::merge_synced_ads,
+        models::{AdRecord, ChannelData, ChannelFilter, SubscriberSnapshot},
+    },
+    utils::{
+        html_parser::extract_subscribers,
+        text::{SESSION_EXPIRED_ERROR, TextUtils},
+    },
 };
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 
-use super::openai::OpenAiClient;
+use super::{
+    http::{HttpClientConfig, HttpLayer, RequestError, RetryPolicy},
+    openai::OpenAiClient,
+    rate_limit::RateLimiters,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
     pub bot_token: String,
-    pub ads_hash: String,
-    pub ads_stel_ssid: String,
-    pub ads_stel_token: String,
-    pub ads_stel_owner: String,
+    pub ads: AdsCredentials,
+    pub media_upload: MediaUploadMode,
+    pub http: HttpClientConfig,
+}
+
+/// Session of the ads platform web UI: the API hash plus the cookies and the
+/// account owner id that go with it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdsCredentials {
+    pub hash: String,
+    pub stel_ssid: String,
+    pub stel_token: String,
+    pub stel_owner: String,
+}
+
+impl AdsCredentials {
+    /// Names of the values that are empty.
+    pub fn missing(&self) -> Vec<&'static str> {
+        [
+            ("hash", &self.hash),
+            ("stel_ssid", &self.stel_ssid),
+            ("stel_token", &self.stel_token),
+            ("stel_owner", &self.stel_owner),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.trim().is_empty())
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    fn cookie(&self) -> String {
+        format!(
+            "stel_ssid={}; stel_token={}",
+            self.stel_ssid, self.stel_token
+        )
+    }
+
+    fn api_url(&self) -> String {
+        format!("https://ads.telegram.org/api?hash={}", self.hash)
+    }
+}
+
+/// Where ad media goes: the ads platform, or a local mock that hands out ids
+/// without any network call so uploads can be tried offline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaUploadMode {
+    Telegram,
+    Mock,
+}
+
+impl MediaUploadMode {
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "telegram" => Ok(MediaUploadMode::Telegram),
+            "mock" => Ok(MediaUploadMode::Mock),
+            other => Err(format!("Unknown media upload mode: '{}'", other)),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -41,13 +110,241 @@ pub enum TelegramCreateAdResponse {
     ValidationError { field: String, error: String },
 }
 
+/// Reply to the calls that change an existing ad. Errors are tried first, as
+/// a failed call may also carry `ok: false`.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum TelegramEditAdResponse {
+    ValidationError {
+        field: String,
+        error: String,
+    },
+    Error {
+        error: String,
+    },
+    Success {
+        ok: bool,
+        /// The ad's status after the change, when the platform reports it.
+        status: Option<String>,
+    },
+}
+
+// Statuses the ads list shows, used when an edit reply doesn't carry one.
+pub const AD_STATUS_ACTIVE: &str = "Active";
+pub const AD_STATUS_ON_HOLD: &str = "On Hold";
+pub const AD_STATUS_DELETED: &str = "Deleted";
+
+fn active_status(active: bool) -> String {
+    if active {
+        AD_STATUS_ACTIVE
+    } else {
+        AD_STATUS_ON_HOLD
+    }
+    .to_string()
+}
+
 #[derive(Deserialize, Debug, Clone)]
-struct TelegramSimilarChat {
+pub struct TelegramSimilarChat {
+    pub id: i64,
+    pub title: Option<String>,
+    pub photo: Option<String>,
+    pub username: Option<String>,
+    /// Card HTML the subscriber count is read from.
+    pub cb_item: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TelegramBotSearchResponse {
+    ok: bool,
+    bots: Option<Vec<TelegramFoundBot>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TelegramFoundBot {
     id: i64,
-    title: Option<String>,
-    photo: Option<String>,
     username: Option<String>,
-    cb_item: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TelegramMediaUploadResponse {
+    media: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TelegramAdsListResponse {
+    ok: bool,
+    items: Option<Vec<TelegramAd>>,
+    next_offset_id: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TelegramAd {
+    #[serde(alias = "id")]
+    ad_id: i64,
+    title: Option<String>,
+    text: Option<String>,
+    promote_url: Option<String>,
+    #[serde(default)]
+    status: String,
+    #[serde(default, deserialize_with = "deserialize_amount")]
+    views: f64,
+    #[serde(default, deserialize_with = "deserialize_amount")]
+    clicks: f64,
+    #[serde(default, deserialize_with = "deserialize_amount")]
+    spent: f64,
+    #[serde(default, deserialize_with = "deserialize_amount")]
+    cpm: f64,
+}
+
+impl TelegramAd {
+    fn into_record(self, seen_at: chrono::DateTime<Utc>) -> AdRecord {
+        AdRecord {
+            id: self.ad_id,
+            title: self.title,
+            text: self.text,
+            promote_url: self.promote_url,
+            status: self.status,
+            views: self.views as i64,
+            clicks: self.clicks as i64,
+            spent: self.spent,
+            cpm: self.cpm,
+            first_seen_at: seen_at,
+            synced_at: seen_at,
+            removed_at: None,
+        }
+    }
+}
+
+/// The ads API sends numbers either as JSON numbers or as display strings
+/// such as "1,250" or "0.35 TON".
+fn deserialize_amount<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::Number(n)) => n.as_f64().unwrap_or_default(),
+        Some(serde_json::Value::String(s)) => s
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '.')
+            .collect::<String>()
+            .parse()
+            .unwrap_or_default(),
+        _ => 0.0,
+    })
+}
+
+/// Error reply of the ads API that isn't tied to a form field.
+#[derive(Deserialize, Debug)]
+struct TelegramAdsError {
+    error: String,
+    field: Option<String>,
+}
+
+/// Whole `error` texts the ads API answers with when it no longer accepts
+/// the hash or the cookies, compared case-insensitively. Other errors that
+/// merely mention a session, e.g. about an ad's schedule, are not included.
+const SESSION_ERRORS: [&str; 4] = [
+    "invalid hash",
+    "session expired",
+    "unauthorized",
+    "please log in",
+];
+
+/// Turns replies showing that the ads platform no longer accepts the session
+/// into a `SESSION_EXPIRED_ERROR`: a 401 or 403, the HTML login page served
+/// in place of JSON, or one of the `SESSION_ERRORS`.
+fn check_ads_session(response: Result<String, RequestError>) -> Result<String, String> {
+    let body = match response {
+        Ok(body) => body,
+        Err(RequestError::Unauthorized(e)) => {
+            return Err(format!("{}: {}", SESSION_EXPIRED_ERROR, e));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if body.trim_start().starts_with('<') {
+        return Err(format!(
+            "{}: the ads platform answered with its login page",
+            SESSION_EXPIRED_ERROR
+        ));
+    }
+    if let Ok(TelegramAdsError { error, field: None }) = serde_json::from_str(&body) {
+        let normalized = error.trim().trim_end_matches('.').to_lowercase();
+        if SESSION_ERRORS.contains(&normalized.as_str()) {
+            return Err(format!("{}: {}", SESSION_EXPIRED_ERROR, error));
+        }
+    }
+    Ok(body)
+}
+
+/// Reads the reply to one of the calls that change an existing ad and
+/// returns the status the platform reported for it.
+fn parse_edit_ad_response(
+    ad_id: i64,
+    method: &str,
+    response_body: &str,
+) -> Result<Option<String>, String> {
+    match serde_json::from_str::<TelegramEditAdResponse>(response_body) {
+        Ok(TelegramEditAdResponse::Success { ok: true, status }) => {
+            info!("Ad {}: {} succeeded", ad_id, method);
+            Ok(status)
+        }
+        Ok(TelegramEditAdResponse::Success { ok: false, .. }) => Err(format!(
+            "Telegram ADS API - {} response indicates failure for ad {}",
+            method, ad_id
+        )),
+        Ok(TelegramEditAdResponse::ValidationError { field, error }) => {
+            Err(format!("Validation error in field '{}': {}", field, error))
+        }
+        Ok(TelegramEditAdResponse::Error { error }) => Err(format!(
+            "Telegram ADS API - {} failed for ad {}: {}",
+            method, ad_id, error
+        )),
+        Err(e) => {
+            error!(
+                "Error parsing JSON response from Telegram ADS API - {}: {}: {}",
+                method, e, response_body
+            );
+            Err("Failed to parse response".to_string())
+        }
+    }
+}
+
+/// Form fields of an `editAd` call, holding only the values being changed.
+fn update_ad_fields(changes: &UpdateAdRequest) -> Vec<(&'static str, String)> {
+    let mut fields = vec![];
+    if let Some(cpm) = changes.cpm {
+        fields.push(("cpm", cpm.to_string()));
+    }
+    if let Some(views_per_user) = changes.views_per_user {
+        fields.push(("views_per_user", views_per_user.to_string()));
+    }
+    if let Some(budget) = changes.budget {
+        fields.push(("budget", budget.to_string()));
+    }
+    if let Some(daily_budget) = changes.daily_budget {
+        fields.push(("daily_budget", daily_budget.to_string()));
+    }
+    if let Some(active) = changes.active {
+        fields.push(("active", if active { "1" } else { "0" }.to_string()));
+    }
+    fields
+}
+
+/// Result of `TelegramService::check_session`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SessionStatus {
+    Valid,
+    Expired {
+        error: String,
+    },
+    /// Some of the ads credentials are not set.
+    Misconfigured {
+        missing: Vec<&'static str>,
+    },
 }
 
 #[derive(Deserialize, Debug)]
@@ -67,35 +364,69 @@ struct TelegramChat {
 #[derive(Clone, Debug)]
 pub struct TelegramService {
     pub bot_token: String,
-    pub hash: Option<String>,
-    pub stel_ssid: Option<String>,
-    pub stel_token: Option<String>,
-    pub stel_owner: Option<String>,
+    /// Shared by every clone of the service. A call takes a snapshot when it
+    /// starts, so replacing the credentials doesn't affect calls in flight.
+    ads_credentials: Arc<RwLock<Arc<AdsCredentials>>>,
+    pub media_upload: MediaUploadMode,
     openai_service: Option<OpenAiClient>,
+    bot_api: HttpLayer,
+    ads_api: HttpLayer,
 }
 
 impl TelegramService {
-    pub fn new(
-        bot_token: String,
-        hash: Option<String>,
-        stel_ssid: Option<String>,
-        stel_token: Option<String>,
-        stel_owner: Option<String>,
+    /// A service using the ads session from `config`.
+    pub fn from_config(
+        config: &TelegramConfig,
+        rate_limiters: &RateLimiters,
         openai_service: Option<OpenAiClient>,
-    ) -> Self {
-        TelegramService {
-            bot_token,
-            hash,
-            stel_ssid,
-            stel_token,
-            stel_owner,
+    ) -> Result<Self, String> {
+        let client = config.http.build_client()?;
+        Ok(TelegramService {
+            bot_token: config.bot_token.clone(),
+            ads_credentials: Arc::new(RwLock::new(Arc::new(config.ads.clone()))),
+            media_upload: config.media_upload,
             openai_service,
+            bot_api: HttpLayer::new(
+                client.clone(),
+                RetryPolicy::default(),
+                rate_limiters.bot_api.clone(),
+            ),
+            ads_api: HttpLayer::new(
+                client,
+                RetryPolicy::default(),
+                rate_limiters.ads_api.clone(),
+            ),
+        })
+    }
+
+    pub fn ads_credentials(&self) -> Arc<AdsCredentials> {
+        self.ads_credentials
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Makes every call started from now on use `credentials`.
+    pub fn set_ads_credentials(&self, credentials: AdsCredentials) {
+        *self
+            .ads_credentials
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Arc::new(credentials);
+        info!("Telegram Ads credentials replaced");
+    }
+
+    /// The current credentials, if all of them are set.
+    fn ads_session(&self) -> Result<Arc<AdsCredentials>, String> {
+        let credentials = self.ads_credentials();
+        match credentials.missing().first() {
+            Some(name) => Err(format!("Missing {}", name)),
+            None => Ok(credentials),
         }
     }
 
     pub async fn check_and_add_channels(
         &self,
-        db: web::Data<JsonDatabase>,
+        db: web::Data<dyn ChannelRepository>,
         channels: &[String],
     ) -> Result<Vec<ChannelData>, String> {
         let mut channels_data = vec![];
@@ -112,6 +443,9 @@ impl TelegramService {
                         channels_data.push(channel_data);
                         warn!("Added channel '{}' to the database.", username);
                     }
+                    Err(e) if e.is_permanent() => {
+                        warn!("Skipping '{}': {}", username, e)
+                    }
                     Err(e) => {
                         error!("Failed to fetch data for '{}': {}", username, e)
                     }
@@ -122,74 +456,58 @@ impl TelegramService {
         Ok(channels_data)
     }
 
-    async fn fetch_channel_data(&self, username: &str) -> Result<ChannelData, String> {
+    async fn fetch_channel_data(&self, username: &str) -> Result<ChannelData, RequestError> {
         info!("Fetching channel info for: {}", username);
 
         let url = format!(
             "https://api.telegram.org/bot{}/getChat?chat_id=@{}",
             self.bot_token, username
         );
-        let client = Client::new();
 
-        let response = client.get(&url).send().await.map_err(|e| {
-            error!("Error sending request to Telegram API: {}", e);
-            e.to_string()
-        })?;
-
-        let status = response.status();
-        let response_body = response.text().await.unwrap_or_else(|_| String::new());
+        let response_body = self
+            .bot_api
+            .send(
+                &format!("Telegram API getChat '{}'", username),
+                true,
+                |client| client.get(&url),
+            )
+            .await?;
 
-        if status.is_success() {
-            let api_response: TelegramChatResponse =
-                serde_json::from_str(&response_body).map_err(|e| {
-                    error!(
-                        "Error parsing JSON response from Telegram API: {}: {}",
-                        e, response_body
-                    );
-                    e.to_string()
-                })?;
+        let api_response: TelegramChatResponse =
+            serde_json::from_str(&response_body).map_err(|e| {
+                error!(
+                    "Error parsing JSON response from Telegram API: {}: {}",
+                    e, response_body
+                );
+                RequestError::Permanent(e.to_string())
+            })?;
 
-            if api_response.ok {
-                if let Some(chat) = api_response.result {
-                    let channel_data = ChannelData {
-                        id: chat
-                            .id
-                            .to_string()
-                            .strip_prefix("-100")
-                            .unwrap_or(&chat.id.to_string())
-                            .to_string()
-                            .parse()
-                            .unwrap_or_default(),
-                        title: chat.title,
-                        username: chat.username.unwrap_or_default(),
-                        photo_element: None,
-                        category: None,
-                        description: Some(chat.description.unwrap_or_default()),
-                        subscribers: None,
-                        geo: None,
-                    };
-                    Ok(channel_data)
-                } else {
-                    Err(format!(
-                        "Telegram API error for '{}': {:?}",
-                        username, api_response
-                    ))
-                }
-            } else {
-                Err(format!(
-                    "Telegram API response indicates failure for '{}': {:?}",
-                    username, api_response
-                ))
-            }
-        } else {
-            error!(
-                "Telegram API request failed for '{}' with status: {} - {}",
-                username, status, response_body
-            );
-            Err(format!(
-                "Telegram API request failed with status: {}",
-                status
-            ))
+        match api_response {
+            TelegramChatResponse {
+                ok: true,
+                result: Some(chat),
+            } => Ok(ChannelData {
+                id: chat
+                    .id
+                    .to_string()
+                    .strip_prefix("-100")
+                    .unwrap_or(&chat.id.to_string())
+                    .to_string()
+                    .parse()
+                    .unwrap_or_default(),
+                title: chat.title,
+                username: chat.username.unwrap_or_default(),
+                photo_element: None,
+                category: None,
+                description: Some(chat.description.unwrap_or_default()),
+                subscribers: None,
+                geo: None,
+                tags: vec![],
+            }),
+            api_response => Err(RequestError::Permanent(format!(
+                "Telegram API response indicates failure for '{}': {:?}",
+                username, api_response
+            ))),
         }
     }
 
@@ -200,50 +518,51 @@ impl TelegramService {
         geos: &[String],
         force: bool,
     ) -> ChannelData {
-        if channel.description.is_none() || force {
-            if let Ok(fetched) = self.fetch_channel_data(&channel.username).await {
-                channel.description = fetched.description;
-            }
+        if (channel.description.is_none() || force)
+            && let Ok(fetched) = self.fetch_channel_data(&channel.username).await
+        {
+            channel.description = fetched.description;
         }
 
         let combined_description = format!("{:?} {:?}", channel.title, channel.description);
 
-        if channel.category.is_none() || force {
-            if let Some(openai) = &self.openai_service {
-                if let Ok(category) = openai
-                    .fetch_chat_category(combined_description.clone(), categories.to_vec())
-                    .await
-                {
-                    channel.category = Some(category);
-                }
-            }
+        if (channel.category.is_none() || force)
+            && let Some(openai) = &self.openai_service
+            && let Ok(category) = openai
+                .fetch_chat_category(combined_description.clone(), categories.to_vec())
+                .await
+        {
+            channel.category = Some(category);
         }
 
-        if channel.geo.is_none() || force {
-            if let Some(openai) = &self.openai_service {
-                if let Ok(geo) = openai
-                    .fetch_chat_geo(combined_description, geos.to_vec())
-                    .await
-                {
-                    channel.geo = Some(geo);
-                }
-            }
+        if (channel.geo.is_none() || force)
+            && let Some(openai) = &self.openai_service
+            && let Ok(geo) = openai
+                .fetch_chat_geo(combined_description, geos.to_vec())
+                .await
+        {
+            channel.geo = Some(geo);
         }
 
         channel
     }
 
-    async fn enrich_channels_with_missing_data(
+    pub(super) async fn enrich_channels_with_missing_data(
         &self,
-        db: web::Data<JsonDatabase>,
+        db: web::Data<dyn ChannelRepository>,
         channels: Vec<TelegramSimilarChat>,
         categories: Vec<String>,
         geos: Vec<String>,
     ) -> Result<Vec<ChannelData>, String> {
-        let exist_channels = db.filter_channels(None, None).await;
+        let exist_channels = db
+            .filter_channels(&ChannelFilter::default())
+            .await?
+            .channels;
+        let observed_at = Utc::now();
 
         let mut need_to_update_channels = vec![];
         let mut done_channels = vec![];
+        let mut snapshots = vec![];
 
         for channel in channels {
             let exist_channel = exist_channels
@@ -256,11 +575,21 @@ impl TelegramService {
                 .as_ref()
                 .and_then(|html| extract_subscribers(html));
 
+            if let Some(subscribers) = subscribers {
+                snapshots.push((
+                    channel.id,
+                    SubscriberSnapshot {
+                        observed_at,
+                        subscribers,
+                    },
+                ));
+            }
+
             if let Some(existing) = &exist_channel {
                 let mut updated_channel = existing.clone();
                 updated_channel.title = channel.title;
                 updated_channel.photo_element = channel.photo;
-                updated_channel.subscribers = subscribers;
+                updated_channel.subscribers = subscribers.or(existing.subscribers);
 
                 if updated_channel.category.is_none()
                     || updated_channel.description.is_none()
@@ -268,7 +597,6 @@ impl TelegramService {
                 {
                     need_to_update_channels.push(updated_channel);
                 } else {
-                    db.add_or_update_channel(updated_channel.clone()).await.ok();
                     done_channels.push(updated_channel);
                 }
             } else {
@@ -281,10 +609,18 @@ impl TelegramService {
                     description: None,
                     subscribers,
                     geo: None,
+                    tags: vec![],
                 });
             }
         }
 
+        if let Err(e) = db.record_subscriber_snapshots(snapshots).await {
+            warn!("Failed to record subscribers: {}", e);
+        }
+        if let Err(e) = db.add_or_update_channels(done_channels.clone()).await {
+            warn!("Failed to update channels: {}", e);
+        }
+
         let chunk_size = 15;
         let concurrency_limit = 3;
         let chunks: Vec<Vec<ChannelData>> = need_to_update_channels
@@ -303,10 +639,12 @@ impl TelegramService {
                         let enriched = self
                             .enrich_channel_data(channel, &categories_clone, &geos_clone, false)
                             .await;
-
-                        db.add_or_update_channel(enriched.clone()).await.ok();
                         updated.push(enriched);
-                        sleep(Duration::from_secs(1)).await;
+                    }
+                    // Saved per chunk, so an interrupted run keeps the
+                    // enrichment already paid for.
+                    if let Err(e) = db.add_or_update_channels(updated.clone()).await {
+                        warn!("Failed to save enriched channels: {}", e);
                     }
                     updated
                 }
@@ -319,114 +657,114 @@ impl TelegramService {
             .collect();
 
         done_channels.extend(enrich_chunks);
-        return Ok(done_channels);
+        Ok(done_channels)
     }
 
-    pub async fn fetch_similar_channels(
+    pub(super) async fn request_similar_channels(
         &self,
-        db: web::Data<JsonDatabase>,
-        channels: Vec<ChannelData>,
-        categories: Vec<String>,
-        geos: Vec<String>,
-    ) -> Result<Vec<ChannelData>, String> {
-        info!(
-            "Fetching similar channels for: {}",
-            channels
-                .iter()
-                .map(|c| c.username.clone())
-                .collect::<Vec<_>>()
-                .join(", ")
-        );
-
-        let client = Client::new();
-
-        let hash = self.hash.as_ref().ok_or("Missing hash")?;
-        let stel_ssid = self.stel_ssid.as_ref().ok_or("Missing stel_ssid")?;
-        let stel_token = self.stel_token.as_ref().ok_or("Missing stel_token")?;
-
-        let url = format!("https://ads.telegram.org/api?hash={}", hash);
-
+        channel_ids: &[i64],
+    ) -> Result<Vec<TelegramSimilarChat>, String> {
         let mut form = HashMap::new();
         form.insert(
             "channels",
-            channels
+            channel_ids
                 .iter()
-                .map(|c| c.id.to_string())
+                .map(|id| id.to_string())
                 .collect::<Vec<_>>()
                 .join(";"),
         );
         form.insert("for", "channels".to_string());
         form.insert("method", "getSimilarChannels".to_string());
 
-        let response = client
-            .post(&url)
-            .header("Accept", "application/json, text/javascript, */*; q=0.01")
-            .header("Accept-Language", "en-US,en;q=0.9,ru;q=0.8")
-            .header(
-                "Content-Type",
-                "application/x-www-form-urlencoded; charset=UTF-8",
-            )
-            .header(
-                "Cookie",
-                format!("stel_ssid={}; stel_token={}", stel_ssid, stel_token),
-            )
-            .form(&form)
-            .send()
-            .await
+        let credentials = self.ads_session()?;
+        let response_body = self
+            .post_ads_api(&credentials, &form, "Similar channels", true)
+            .await?;
+        let api_response: TelegramSimilarChatResponse = serde_json::from_str(&response_body)
             .map_err(|e| {
                 error!(
-                    "Error sending request to Telegram ADS API - Similar channels: {}",
-                    e
+                    "Error parsing JSON response from Telegram ADS API - Similar channels: {}: {}",
+                    e, response_body
                 );
                 e.to_string()
             })?;
 
-        let status = response.status();
-        let response_body = response.text().await.unwrap_or_else(|_| String::new());
+        if api_response.ok {
+            let similar_channels = api_response.channels.unwrap_or_default();
 
-        if status.is_success() {
-            let api_response: TelegramSimilarChatResponse =
-                serde_json::from_str(&response_body).map_err(|e| {
-                    error!(
-                        "Error parsing JSON response from Telegram ADS API - Similar channels: {}: {}",
-                        e, response_body
-                    );
-                    e.to_string()
-                })?;
+            info!("Find similar channels: {}", similar_channels.len());
 
-            if api_response.ok {
-                let similar_channels = api_response.channels.unwrap_or_default();
+            Ok(similar_channels)
+        } else {
+            Err(format!(
+                "Telegram ADS API - Similar channels response indicates failure: {:?}",
+                api_response
+            ))
+        }
+    }
 
-                info!("Find similar channels: {}", similar_channels.len());
+    /// Looks a bot up through the ads platform's target search, the same one
+    /// the web UI uses when a bot is added to an ad.
+    async fn search_bot(&self, username: &str) -> Result<Option<i64>, String> {
+        let mut form = HashMap::new();
+        form.insert("query", username.to_string());
+        form.insert("method", "searchBots".to_string());
 
-                let result = &self
-                    .enrich_channels_with_missing_data(
-                        db.clone(),
-                        similar_channels,
-                        categories,
-                        geos,
-                    )
-                    .await?;
+        let credentials = self.ads_session()?;
+        let response_body = self
+            .post_ads_api(&credentials, &form, "Search bots", true)
+            .await?;
+        let api_response: TelegramBotSearchResponse = serde_json::from_str(&response_body)
+            .map_err(|e| {
+                error!(
+                    "Error parsing JSON response from Telegram ADS API - Search bots: {}: {}",
+                    e, response_body
+                );
+                e.to_string()
+            })?;
 
-                return Ok(result.to_vec());
-            } else {
-                Err(format!(
-                    "Telegram ADS API - Similar channels response indicates failure: {:?}",
-                    api_response
-                ))
-            }
-        } else {
+        if !api_response.ok {
             return Err(format!(
-                "Error from Telegram ADS API - Similar channels: {}",
-                status
+                "Telegram ADS API - Search bots response indicates failure: {:?}",
+                api_response
             ));
         }
+
+        // The search is fuzzy, so only an exact username match counts.
+        Ok(api_response
+            .bots
+            .unwrap_or_default()
+            .into_iter()
+            .find(|bot| {
+                bot.username
+                    .as_deref()
+                    .is_some_and(|u| u.eq_ignore_ascii_case(username))
+            })
+            .map(|bot| bot.id))
+    }
+
+    /// Resolves bot usernames to the ids the ads API expects. An unknown bot
+    /// is reported as a validation error on the `bots` field.
+    pub async fn resolve_bot_ids(&self, usernames: &[String]) -> Result<Vec<i64>, String> {
+        let mut bot_ids = vec![];
+        for username in usernames {
+            match self.search_bot(username).await? {
+                Some(id) => bot_ids.push(id),
+                None => {
+                    return Err(format!(
+                        "Validation error in field 'bots': Bot '@{}' not found",
+                        username
+                    ));
+                }
+            }
+        }
+        Ok(bot_ids)
     }
 
     pub async fn fetch_new_data(
         &self,
         id: i64,
-        db: web::Data<JsonDatabase>,
+        db: web::Data<dyn ChannelRepository>,
         categories: Vec<String>,
         geos: Vec<String>,
     ) -> Result<ChannelData, String> {
@@ -443,15 +781,297 @@ impl TelegramService {
         Ok(result)
     }
 
+    /// Posts `form` to the ads API with the session cookies and returns the
+    /// response body of a successful call; `context` names the call in errors.
+    /// Only `idempotent` calls are retried after a timeout or a server error,
+    /// so a change to an ad is never sent twice.
+    async fn post_ads_api(
+        &self,
+        credentials: &AdsCredentials,
+        form: &HashMap<&str, String>,
+        context: &str,
+        idempotent: bool,
+    ) -> Result<String, String> {
+        let url = credentials.api_url();
+        let cookie = credentials.cookie();
+
+        let response = self
+            .ads_api
+            .send(
+                &format!("Telegram ADS API - {}", context),
+                idempotent,
+                |client| {
+                    client
+                        .post(&url)
+                        .header("Accept", "application/json, text/javascript, */*; q=0.01")
+                        .header("Accept-Language", "en-US,en;q=0.9,ru;q=0.8")
+                        .header(
+                            "Content-Type",
+                            "application/x-www-form-urlencoded; charset=UTF-8",
+                        )
+                        .header("Cookie", &cookie)
+                        .form(form)
+                },
+            )
+            .await;
+        check_ads_session(response).inspect_err(|e| error!("{}", e))
+    }
+
+    /// Checks the ads hash and cookies with a one-page ad list request, the
+    /// cheapest call that needs a logged-in session. Fails only when the ads
+    /// platform couldn't be asked.
+    pub async fn check_session(&self) -> Result<SessionStatus, String> {
+        let credentials = self.ads_credentials();
+        let missing = credentials.missing();
+        if !missing.is_empty() {
+            return Ok(SessionStatus::Misconfigured { missing });
+        }
+
+        let mut form = HashMap::new();
+        form.insert("owner_id", credentials.stel_owner.clone());
+        form.insert("method", "getAdsList".to_string());
+
+        match self
+            .post_ads_api(&credentials, &form, "Session check", true)
+            .await
+        {
+            Ok(response_body) => {
+                match serde_json::from_str::<TelegramAdsListResponse>(&response_body) {
+                    Ok(TelegramAdsListResponse { ok: true, .. }) => Ok(SessionStatus::Valid),
+                    _ => Err(format!(
+                        "Telegram ADS API - Session check returned an unexpected reply: {}",
+                        response_body
+                    )),
+                }
+            }
+            Err(e) if TextUtils::is_session_expired_error(&e) => {
+                Ok(SessionStatus::Expired { error: e })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Lists every ad in the account with its current statistics, following
+    /// the API's pagination.
+    pub async fn fetch_ads(&self) -> Result<Vec<AdRecord>, String> {
+        // One snapshot for all pages, so the list comes from a single session.
+        let credentials = self.ads_session()?;
+        let seen_at = Utc::now();
+
+        let mut ads: Vec<AdRecord> = vec![];
+        let mut offset_id: Option<i64> = None;
+        loop {
+            let mut form = HashMap::new();
+            form.insert("owner_id", credentials.stel_owner.clone());
+            form.insert("method", "getAdsList".to_string());
+            if let Some(offset_id) = offset_id {
+                form.insert("offset_id", offset_id.to_string());
+            }
+
+            let response_body = self
+                .post_ads_api(&credentials, &form, "Ads list", true)
+                .await?;
+            let api_response: TelegramAdsListResponse = serde_json::from_str(&response_body)
+                .map_err(|e| {
+                    error!(
+                        "Error parsing JSON response from Telegram ADS API - Ads list: {}: {}",
+                        e, response_body
+                    );
+                    e.to_string()
+                })?;
+            if !api_response.ok {
+                return Err(format!(
+                    "Telegram ADS API - Ads list response indicates failure: {}",
+                    response_body
+                ));
+            }
+
+            let items = api_response.items.unwrap_or_default();
+            let page_len = items.len();
+            for item in items {
+                if !ads.iter().any(|ad| ad.id == item.ad_id) {
+                    ads.push(item.into_record(seen_at));
+                }
+            }
+
+            match api_response.next_offset_id {
+                Some(next) if page_len > 0 && offset_id != Some(next) => offset_id = Some(next),
+                _ => break,
+            }
+        }
+
+        info!("Fetched {} ads from the ads account", ads.len());
+        Ok(ads)
+    }
+
+    /// Fetches the account's ads and stores them next to the history of ads
+    /// seen before; returns the whole history.
+    pub async fn sync_ads(
+        &self,
+        db: web::Data<dyn ChannelRepository>,
+    ) -> Result<Vec<AdRecord>, String> {
+        let fetched = self.fetch_ads().await?;
+        let stored = db.list_ads().await?;
+
+        let merged = merge_synced_ads(stored, fetched, Utc::now());
+        db.save_ads(merged.clone()).await?;
+        Ok(merged)
+    }
+
+    /// Sends one of the calls that change an existing ad and returns the
+    /// status the platform reported for it; validation errors use the same
+    /// message format as `create_ad`.
+    async fn edit_ad(
+        &self,
+        ad_id: i64,
+        method: &str,
+        fields: Vec<(&'static str, String)>,
+    ) -> Result<Option<String>, String> {
+        let credentials = self.ads_session()?;
+
+        let mut form: HashMap<&str, String> = fields.into_iter().collect();
+        form.insert("owner_id", credentials.stel_owner.clone());
+        form.insert("ad_id", ad_id.to_string());
+        form.insert("method", method.to_string());
+
+        let response_body = self
+            .post_ads_api(&credentials, &form, method, false)
+            .await?;
+        parse_edit_ad_response(ad_id, method, &response_body)
+    }
+
+    /// Applies `changes` and returns the ad's new status, if it changed or
+    /// the platform reported it.
+    pub async fn update_ad(
+        &self,
+        ad_id: i64,
+        changes: &UpdateAdRequest,
+    ) -> Result<Option<String>, String> {
+        let status = self
+            .edit_ad(ad_id, "editAd", update_ad_fields(changes))
+            .await?;
+        Ok(status.or_else(|| changes.active.map(active_status)))
+    }
+
+    /// Starts or pauses the ad and returns its new status.
+    pub async fn set_ad_active(&self, ad_id: i64, active: bool) -> Result<String, String> {
+        let form_value = if active { "1" } else { "0" };
+        let status = self
+            .edit_ad(
+                ad_id,
+                "editAdStatus",
+                vec![("active", form_value.to_string())],
+            )
+            .await?;
+        Ok(status.unwrap_or_else(|| active_status(active)))
+    }
+
+    /// Deletes the ad and returns its new status.
+    pub async fn delete_ad(&self, ad_id: i64) -> Result<String, String> {
+        let status = self.edit_ad(ad_id, "deleteAd", vec![]).await?;
+        Ok(status.unwrap_or_else(|| AD_STATUS_DELETED.to_string()))
+    }
+
+    /// Uploads an ad image or video and returns the media id to put in
+    /// `CreateAdRequest::media`.
+    pub async fn upload_media(
+        &self,
+        file_name: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<String, String> {
+        match self.media_upload {
+            MediaUploadMode::Mock => {
+                let mut hasher = DefaultHasher::new();
+                bytes.hash(&mut hasher);
+                let media_id = format!("mock-{:016x}", hasher.finish());
+                info!("Mock media upload of '{}': {}", file_name, media_id);
+                Ok(media_id)
+            }
+            MediaUploadMode::Telegram => {
+                self.request_media_upload(file_name, content_type, bytes)
+                    .await
+            }
+        }
+    }
+
+    async fn request_media_upload(
+        &self,
+        file_name: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<String, String> {
+        let credentials = self.ads_session()?;
+        let url = credentials.api_url();
+        let cookie = credentials.cookie();
+
+        let invalid_content_type = |e: &dyn std::fmt::Display| {
+            format!(
+                "Validation error in field 'file': Invalid content type '{}': {}",
+                content_type, e
+            )
+        };
+        let mime: Mime = content_type.parse().map_err(|e| invalid_content_type(&e))?;
+        let mut part_headers = HeaderMap::new();
+        part_headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_str(mime.as_ref()).map_err(|e| invalid_content_type(&e))?,
+        );
+
+        // A multipart form can't be cloned, so every attempt builds its own.
+        let build_form = || {
+            let file = Part::bytes(bytes.clone())
+                .file_name(file_name.to_string())
+                .headers(part_headers.clone());
+            Form::new()
+                .text("owner_id", credentials.stel_owner.clone())
+                .text("target", "ad_media")
+                .text("method", "uploadMedia")
+                .part("file", file)
+        };
+
+        let response = self
+            .ads_api
+            .send("Telegram ADS API - Media upload", false, |client| {
+                client
+                    .post(&url)
+                    .header("Accept", "application/json, text/javascript, */*; q=0.01")
+                    .header("Accept-Language", "en-US,en;q=0.9,ru;q=0.8")
+                    .header("Cookie", &cookie)
+                    .multipart(build_form())
+            })
+            .await;
+        let response_body = check_ads_session(response).inspect_err(|e| error!("{}", e))?;
+
+        let api_response: TelegramMediaUploadResponse = serde_json::from_str(&response_body)
+            .map_err(|e| {
+                error!(
+                    "Error parsing JSON response from Telegram ADS API - Media upload: {}: {}",
+                    e, response_body
+                );
+                e.to_string()
+            })?;
+
+        match api_response {
+            TelegramMediaUploadResponse {
+                media: Some(media), ..
+            } if !media.is_empty() => Ok(media),
+            TelegramMediaUploadResponse {
+                error: Some(error), ..
+            } => Err(format!("Validation error in field 'file': {}", error)),
+            _ => Err(format!(
+                "Telegram ADS API - Media upload response has no media id: {}",
+                response_body
+            )),
+        }
+    }
+
     pub async fn create_ad(
         &self,
         ad_data: CreateAdRequest,
-        channels_ids: Vec<i64>,
+        target: AdTarget,
     ) -> Result<String, String> {
-        let hash = self.hash.as_ref().ok_or("Missing hash")?;
-        let stel_ssid = self.stel_ssid.as_ref().ok_or("Missing stel_ssid")?;
-        let stel_token = self.stel_token.as_ref().ok_or("Missing stel_token")?;
-        let stel_owner = self.stel_owner.as_ref().ok_or("Missing stel_owner")?;
+        let credentials = self.ads_session()?;
 
         let mut headers = HeaderMap::new();
         headers.insert(
@@ -467,92 +1087,191 @@ impl TelegramService {
             HeaderValue::from_static("application/x-www-form-urlencoded; charset=UTF-8"),
         );
 
-        let cookie_str = format!("stel_ssid={}; stel_token={}", stel_ssid, stel_token);
-        headers.insert(COOKIE, HeaderValue::from_str(&cookie_str).unwrap());
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_str(&credentials.cookie())
+                .map_err(|e| format!("Invalid session cookie: {}", e))?,
+        );
 
-        let title = "test title";
-        let text = ad_data.text.to_string();
+        let title = ad_data.title_or_default();
+        let text = ad_data.text.trim().to_string();
         let promote_url = ad_data.promote_url.to_string();
+        let website_name = ad_data.website_name.as_deref().unwrap_or_default().trim();
+        let website_photo = ad_data.website_photo.as_deref().unwrap_or_default();
+        let media = ad_data.media.as_deref().unwrap_or_default();
+        let ad_info = ad_data.ad_info.as_deref().unwrap_or_default().trim();
         let cpm = ad_data.cpm.to_string();
         let views_per_user = ad_data.views_per_user.to_string();
         let budget = ad_data.budget.to_string();
         let daily_budget = ad_data.daily_budget.to_string();
         let active = if ad_data.active { "1" } else { "0" };
         let target_type = ad_data.target_type.as_str();
-        let channels = channels_ids
-            .iter()
-            .map(|id| id.to_string())
-            .collect::<Vec<String>>()
-            .join(";");
+        let (channels, bots, search_queries) = target.form_fields();
         let method = ad_data.method.as_str();
 
         let mut form_data = HashMap::new();
-        form_data.insert("owner_id", stel_owner.as_str());
-        form_data.insert("title", title);
+        form_data.insert("owner_id", credentials.stel_owner.as_str());
+        form_data.insert("title", &title);
         form_data.insert("text", &text);
         form_data.insert("promote_url", &promote_url);
-        form_data.insert("website_name", "");
-        form_data.insert("website_photo", "");
-        form_data.insert("media", "");
-        form_data.insert("ad_info", "");
+        form_data.insert("website_name", website_name);
+        form_data.insert("website_photo", website_photo);
+        form_data.insert("media", media);
+        form_data.insert("ad_info", ad_info);
         form_data.insert("cpm", &cpm);
         form_data.insert("views_per_user", &views_per_user);
         form_data.insert("budget", &budget);
         form_data.insert("daily_budget", &daily_budget);
-        form_data.insert("active", &active);
+        form_data.insert("active", active);
         form_data.insert("target_type", target_type);
         form_data.insert("channels", &channels);
-        form_data.insert("bots", "");
-        form_data.insert("search_queries", "");
+        form_data.insert("bots", &bots);
+        form_data.insert("search_queries", &search_queries);
         form_data.insert("method", method);
 
-        let url = format!("https://ads.telegram.org/api?hash={}", hash);
-        let client = Client::new();
+        let url = credentials.api_url();
 
-        let response = client
-            .post(url)
-            .headers(headers)
-            .form(&form_data)
-            .send()
-            .await
-            .map_err(|e| {
-                error!("Error sending request to Telegram API: {}", e);
-                e.to_string()
-            })?;
-
-        let status = response.status();
-        let response_body = response.text().await.unwrap_or_else(|_| String::new());
+        let response = self
+            .ads_api
+            .send("Telegram ADS API - Create ad", false, |client| {
+                client.post(&url).headers(headers.clone()).form(&form_data)
+            })
+            .await;
+        let response_body = check_ads_session(response).inspect_err(|e| error!("{}", e))?;
 
-        println!("Body: {}", response_body);
+        debug!("Telegram ADS API - Create ad response: {}", response_body);
 
-        if status.is_success() {
-            match serde_json::from_str::<TelegramCreateAdResponse>(&response_body) {
-                Ok(parsed) => match parsed {
-                    TelegramCreateAdResponse::SuccessWithRedirect { redirect_to, ok } => {
-                        Ok(format!(
-                            "Ad created successfully - {}. Redirect to: {}",
-                            ok, redirect_to
-                        ))
-                    }
-                    TelegramCreateAdResponse::SuccessWithDraft { has_draft, ok } => {
-                        if has_draft {
-                            Ok(format!("Ad saved as draft - {}.", ok))
-                        } else {
-                            Ok("Ad saved successfully, but no draft detected.".to_string())
-                        }
-                    }
-                    TelegramCreateAdResponse::ValidationError { field, error } => {
-                        Err(format!("Validation error in field '{}': {}", field, error))
+        match serde_json::from_str::<TelegramCreateAdResponse>(&response_body) {
+            Ok(parsed) => match parsed {
+                TelegramCreateAdResponse::SuccessWithRedirect { redirect_to, ok } => Ok(format!(
+                    "Ad created successfully - {}. Redirect to: {}",
+                    ok, redirect_to
+                )),
+                TelegramCreateAdResponse::SuccessWithDraft { has_draft, ok } => {
+                    if has_draft {
+                        Ok(format!("Ad saved as draft - {}.", ok))
+                    } else {
+                        Ok("Ad saved successfully, but no draft detected.".to_string())
                     }
-                },
-                Err(e) => {
-                    error!("Failed to parse response JSON: {}", e);
-                    Err("Failed to parse response".to_string())
                 }
+                TelegramCreateAdResponse::ValidationError { field, error } => {
+                    Err(format!("Validation error in field '{}': {}", field, error))
+                }
+            },
+            Err(e) => {
+                error!("Failed to parse response JSON: {}", e);
+                Err("Failed to parse response".to_string())
             }
-        } else {
-            error!("Non-success HTTP status: {}", status);
-            Err(format!("HTTP error: {}. Body: {}", status, response_body))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_expired(response: Result<String, RequestError>) -> bool {
+        check_ads_session(response)
+            .err()
+            .is_some_and(|e| TextUtils::is_session_expired_error(&e))
+    }
+
+    #[test]
+    fn check_ads_session_detects_refused_sessions() {
+        assert!(is_expired(Err(RequestError::Unauthorized(
+            "Telegram ADS API - Ads list: 403 Forbidden".to_string()
+        ))));
+        assert!(is_expired(Ok(
+            "<!DOCTYPE html><html><head><title>Telegram Ads</title></head></html>".to_string()
+        )));
+        assert!(is_expired(Ok(r#"{"error":"Invalid hash"}"#.to_string())));
+        assert!(is_expired(
+            Ok(r#"{"error":"Session expired."}"#.to_string())
+        ));
+    }
+
+    #[test]
+    fn check_ads_session_passes_other_replies_through() {
+        let body = r#"{"ok":true,"items":[]}"#.to_string();
+        assert_eq!(check_ads_session(Ok(body.clone())), Ok(body));
+
+        // Errors that only mention a session or a login are about the ad.
+        for body in [
+            r#"{"error":"Ad session budget is too low"}"#,
+            r#"{"error":"Login to the promoted bot failed"}"#,
+            r#"{"field":"title","error":"Invalid session title"}"#,
+        ] {
+            assert_eq!(
+                check_ads_session(Ok(body.to_string())),
+                Ok(body.to_string())
+            );
+        }
+
+        let error = check_ads_session(Err(RequestError::Transient(
+            "Telegram ADS API - Ads list: 502 Bad Gateway".to_string(),
+        )))
+        .unwrap_err();
+        assert!(!TextUtils::is_session_expired_error(&error));
+    }
+
+    #[test]
+    fn edit_ad_replies_map_to_status_or_error() {
+        assert_eq!(
+            parse_edit_ad_response(7, "editAd", r#"{"ok":true,"status":"On Hold"}"#),
+            Ok(Some("On Hold".to_string()))
+        );
+        assert_eq!(
+            parse_edit_ad_response(7, "deleteAd", r#"{"ok":true}"#),
+            Ok(None)
+        );
+
+        assert_eq!(
+            parse_edit_ad_response(7, "editAd", r#"{"field":"cpm","error":"Too low"}"#),
+            Err("Validation error in field 'cpm': Too low".to_string())
+        );
+        // A failed call may also carry `ok: false`, the error still wins.
+        let error = parse_edit_ad_response(7, "deleteAd", r#"{"ok":false,"error":"Ad not found"}"#)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "Telegram ADS API - deleteAd failed for ad 7: Ad not found"
+        );
+        assert!(
+            parse_edit_ad_response(7, "editAd", r#"{"ok":false}"#)
+                .unwrap_err()
+                .contains("indicates failure")
+        );
+        assert_eq!(
+            parse_edit_ad_response(7, "editAd", "ok"),
+            Err("Failed to parse response".to_string())
+        );
+    }
+
+    #[test]
+    fn update_ad_fields_hold_only_the_changes() {
+        let changes: UpdateAdRequest =
+            serde_json::from_value(serde_json::json!({"cpm": 0.35, "active": false})).unwrap();
+        assert_eq!(
+            update_ad_fields(&changes),
+            vec![("cpm", "0.35".to_string()), ("active", "0".to_string())]
+        );
+
+        let changes: UpdateAdRequest =
+            serde_json::from_value(serde_json::json!({"budget": 10.0})).unwrap();
+        assert_eq!(
+            update_ad_fields(&changes),
+            vec![("budget", "10".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_upload_gives_the_same_id_for_the_same_bytes() {
+        let service = crate::test_support::mock_telegram_service();
+        let upload = |bytes: &[u8]| service.upload_media("photo.png", "image/png", bytes.to_vec());
+
+        let first = upload(b"image").await.unwrap();
+        assert!(first.starts_with("mock-"));
+        assert_eq!(upload(b"image").await.unwrap(), first);
+        assert_ne!(upload(b"other image").await.unwrap(), first);
+    }
+}
@@ -0,0 +1,96 @@
+//! Fixtures shared by the unit tests.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config::{DatabaseBackend, DatabaseConfig},
+    database::{ChannelRepository, JsonDatabase, SqliteDatabase, models::ChannelData},
+    services::{
+        http::HttpClientConfig,
+        rate_limit::{RateLimit, RateLimitConfig, RateLimiters},
+        telegram::{AdsCredentials, MediaUploadMode, TelegramConfig, TelegramService},
+    },
+};
+
+/// An empty directory under the system temp dir. `name` must be unique across
+/// the test suite, since tests run in parallel.
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tg-ads-manager-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Config for a `backend` database file inside `dir`.
+pub(crate) fn database_config(dir: &Path, backend: DatabaseBackend) -> DatabaseConfig {
+    DatabaseConfig {
+        backend,
+        file_path: dir.join(match backend {
+            DatabaseBackend::Json => "channels.json",
+            DatabaseBackend::Sqlite => "channels.db",
+        }),
+        backups: 3,
+        backup_interval_secs: 3600,
+    }
+}
+
+pub(crate) async fn json_db(name: &str) -> (JsonDatabase, PathBuf) {
+    let dir = temp_dir(name);
+    let db = JsonDatabase::new(database_config(&dir, DatabaseBackend::Json))
+        .await
+        .unwrap();
+    (db, dir)
+}
+
+pub(crate) async fn sqlite_db(name: &str) -> (SqliteDatabase, PathBuf) {
+    let dir = temp_dir(name);
+    let db = SqliteDatabase::new(database_config(&dir, DatabaseBackend::Sqlite))
+        .await
+        .unwrap();
+    (db, dir)
+}
+
+/// A channel with only its id and username set.
+pub(crate) fn channel(id: i64, username: &str) -> ChannelData {
+    ChannelData {
+        id,
+        username: username.to_string(),
+        ..Default::default()
+    }
+}
+
+/// A fresh JSON and a fresh SQLite store, for checks both backends must pass.
+/// `name` is suffixed per backend.
+pub(crate) async fn all_backends(name: &str) -> Vec<(Box<dyn ChannelRepository>, PathBuf)> {
+    let (json, json_dir) = json_db(&format!("{}-json", name)).await;
+    let (sqlite, sqlite_dir) = sqlite_db(&format!("{}-sqlite", name)).await;
+    vec![(Box::new(json), json_dir), (Box::new(sqlite), sqlite_dir)]
+}
+
+/// A service without credentials that mocks media uploads, so it never
+/// reaches the network for them.
+pub(crate) fn mock_telegram_service() -> TelegramService {
+    let config = TelegramConfig {
+        bot_token: String::new(),
+        ads: AdsCredentials {
+            hash: String::new(),
+            stel_ssid: String::new(),
+            stel_token: String::new(),
+            stel_owner: String::new(),
+        },
+        media_upload: MediaUploadMode::Mock,
+        http: HttpClientConfig {
+            connect_timeout_secs: 1,
+            read_timeout_secs: 1,
+            proxy: None,
+            user_agent: "tests".to_string(),
+        },
+    };
+    let limit = RateLimit::from_env_value("", 100.0).unwrap();
+    let rate_limiters = RateLimiters::new(&RateLimitConfig {
+        bot_api: limit,
+        ads_api: limit,
+        openai: limit,
+    });
+    TelegramService::from_config(&config, &rate_limiters, None).unwrap()
+}
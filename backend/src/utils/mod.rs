@@ -1,2 +1,2 @@
-pub mod text;
 pub mod html_parser;
+pub mod text;
@@ -1,3 +1,7 @@
+/// Start of the error returned when the ads platform no longer accepts the
+/// session cookies.
+pub const SESSION_EXPIRED_ERROR: &str = "Telegram Ads session expired";
+
 #[derive(Debug)]
 pub struct TextUtils;
 
@@ -5,8 +9,7 @@ impl TextUtils {
     pub fn normalize_name(raw_name: &str) -> String {
         raw_name
             .replace("https://t.me/", "")
-            .replace('@', "")
-            .replace('/', "")
+            .replace(['@', '/'], "")
             .trim()
             .to_string()
     }
@@ -19,16 +22,19 @@ impl TextUtils {
     }
 
     pub fn parse_validation_error(error: &str) -> Option<(String, String)> {
-        if error.starts_with("Validation error in field '") {
-            if let Some(start) = error.find('\'') {
-                if let Some(end) = error[start + 1..].find('\'') {
-                    let field = &error[start + 1..start + 1 + end];
-                    let msg_start = error.find(": ").unwrap_or(0) + 2;
-                    let msg = &error[msg_start..];
-                    return Some((field.to_string(), msg.to_string()));
-                }
-            }
+        if error.starts_with("Validation error in field '")
+            && let Some(start) = error.find('\'')
+            && let Some(end) = error[start + 1..].find('\'')
+        {
+            let field = &error[start + 1..start + 1 + end];
+            let msg_start = error.find(": ").unwrap_or(0) + 2;
+            let msg = &error[msg_start..];
+            return Some((field.to_string(), msg.to_string()));
         }
         None
     }
+
+    pub fn is_session_expired_error(error: &str) -> bool {
+        error.starts_with(SESSION_EXPIRED_ERROR)
+    }
 }
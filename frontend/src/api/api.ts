@@ -1,4 +1,10 @@
-import { Channel, CreateAdRequest } from '../types/types';
+import {
+  Channel,
+  CreateAdRequest,
+  CreateAdResponse,
+  Media,
+  TelegramSession,
+} from '../types/types';
 
 const API_BASE_URL = 'http://127.0.0.1:8080/api/v1';
 
@@ -13,8 +19,27 @@ const API_ENDPOINT = {
   updateGeoCategory: (id: number) => `${API_BASE_URL}/channels/${id}/geo`,
   generateAdMessage: `${API_BASE_URL}/ads/generate`,
   createAd: `${API_BASE_URL}/ads/`,
+  media: `${API_BASE_URL}/ads/media`,
+  telegramSession: `${API_BASE_URL}/telegram/session`,
 };
 
+export const SESSION_EXPIRED_CODE = 'session_expired';
+
+export class ApiError extends Error {
+  code?: string;
+
+  constructor(message: string, code?: string) {
+    super(message);
+    this.code = code;
+  }
+}
+
+export const SESSION_EXPIRED_MESSAGE =
+  'The Telegram Ads session has expired, please update the ads cookies.';
+
+export const isSessionExpired = (error: unknown) =>
+  error instanceof ApiError && error.code === SESSION_EXPIRED_CODE;
+
 async function apiFetch<TResponse, TBody = undefined>(
   endpoint: string,
   method: 'GET' | 'POST' | 'PUT' | 'DELETE',
@@ -38,8 +63,10 @@ async function apiFetch<TResponse, TBody = undefined>(
 
   if (!response.ok) {
     let errorMessage = `API error: ${response.status} ${response.statusText}`;
+    let errorCode: string | undefined;
     try {
       const errorBody = await response.json();
+      errorCode = errorBody?.code;
       if (errorBody?.error) {
         errorMessage = errorBody.error;
       } else if (typeof errorBody === 'string') {
@@ -52,7 +79,7 @@ async function apiFetch<TResponse, TBody = undefined>(
       }
     }
 
-    throw new Error(errorMessage);
+    throw new ApiError(errorMessage, errorCode);
   }
 
   return response.json();
@@ -76,10 +103,15 @@ export const fetchChannelsByFilter = (
   category?: string | null,
   geo?: string | null,
 ) =>
-  apiFetch<{ channels: Channel[] }>(API_ENDPOINT.channels, 'GET', undefined, {
-    category,
-    geo,
-  });
+  apiFetch<{ channels: Channel[]; total: number }>(
+    API_ENDPOINT.channels,
+    'GET',
+    undefined,
+    {
+      category,
+      geo,
+    },
+  );
 
 export const fetchChannelData = (id: number) =>
   apiFetch<Channel>(API_ENDPOINT.getChannelData(id), 'GET');
@@ -109,8 +141,26 @@ export const generateAdMessage = (
   });
 
 export const createAd = (payload: CreateAdRequest) =>
-  apiFetch<{ status: string }, CreateAdRequest>(
+  apiFetch<CreateAdResponse, CreateAdRequest>(
     API_ENDPOINT.createAd,
     'POST',
     payload,
   );
+
+export const uploadMedia = async (file: File): Promise<Media> => {
+  const body = new FormData();
+  body.append('file', file);
+
+  const response = await fetch(API_ENDPOINT.media, { method: 'POST', body });
+  const result = await response.json();
+  if (!response.ok) {
+    throw new ApiError(
+      result?.error ?? `API error: ${response.status} ${response.statusText}`,
+      result?.code,
+    );
+  }
+  return result;
+};
+
+export const fetchTelegramSession = () =>
+  apiFetch<TelegramSession>(API_ENDPOINT.telegramSession, 'GET');
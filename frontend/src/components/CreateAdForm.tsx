@@ -1,4 +1,5 @@
 import * as api from '../api/api';
+import { Media } from '../types/types';
 import { loadFromStorage, saveToStorage } from '../utils/storage';
 import { useCallback, useEffect, useState } from 'react';
 
@@ -16,6 +17,22 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
   adText,
   showToast,
 }) => {
+  const [title, setTitle] = useState<string>(() =>
+    loadFromStorage('title', ''),
+  );
+  const [websiteName, setWebsiteName] = useState<string>(() =>
+    loadFromStorage('websiteName', ''),
+  );
+  const [adInfo, setAdInfo] = useState<string>(() =>
+    loadFromStorage('adInfo', ''),
+  );
+  const [media, setMedia] = useState<Media | null>(null);
+  const [splitBy, setSplitBy] = useState<'' | 'category' | 'geo' | 'chunk'>(
+    () => loadFromStorage('splitBy', ''),
+  );
+  const [chunkSize, setChunkSize] = useState(() =>
+    loadFromStorage('chunkSize', 50),
+  );
   const [promoteUrl, setPromoteUrl] = useState<string>(() =>
     loadFromStorage('promoteUrl', ''),
   );
@@ -34,23 +51,47 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
   const [method, setMethod] = useState<'draft' | 'save'>(() =>
     loadFromStorage('method', 'draft'),
   );
+  const [targets, setTargets] = useState<string>(() =>
+    loadFromStorage('targets', ''),
+  );
 
   const sendAd = useCallback(async () => {
     try {
       console.log(adText);
-      await api.createAd({
+      const targetList = targets
+        .split('\n')
+        .map((target) => target.trim())
+        .filter((target) => target !== '');
+      const split = targetType === 'channel' && splitBy !== '';
+      const result = await api.createAd({
+        title: title.trim() || undefined,
         text: adText,
         promote_url: promoteUrl,
+        website_name: websiteName.trim() || undefined,
+        ad_info: adInfo.trim() || undefined,
+        media: media?.id,
         cpm: cpm,
         views_per_user: viewsPerUser,
         budget: budget,
         daily_budget: dailyBudget,
         active: active,
         target_type: targetType,
-        channels: channels,
+        channels: targetType === 'channel' ? channels : [],
+        bots: targetType === 'bot' ? targetList : [],
+        search_queries: targetType === 'search' ? targetList : [],
         method: method,
+        split_by: split ? splitBy : undefined,
+        chunk_size: split && splitBy === 'chunk' ? chunkSize : undefined,
       });
-      if (method === 'draft') {
+      const failed = result.ads?.filter((ad) => ad.error || ad.skipped) ?? [];
+      if (failed.length > 0) {
+        showToast(
+          `${failed.length} of ${result.ads?.length} ads failed: ${failed
+            .map((ad) => `${ad.group} (${ad.error ?? 'skipped'})`)
+            .join(', ')}`,
+          'warning',
+        );
+      } else if (method === 'draft') {
         showToast(
           'The ad has been saved as a draft. <a href="https://ads.telegram.org/account/ad/new" target="_blank" rel="noopener noreferrer">Click here to review it</a>.',
           'warning',
@@ -62,14 +103,22 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
         );
       }
     } catch (error) {
+      if (api.isSessionExpired(error)) {
+        showToast(api.SESSION_EXPIRED_MESSAGE, 'error');
+        return;
+      }
       showToast(
         `Failed to create the ad: ${(error as Error).message}`,
         'error',
       );
     }
   }, [
+    title,
     adText,
     promoteUrl,
+    websiteName,
+    adInfo,
+    media,
     cpm,
     viewsPerUser,
     budget,
@@ -77,11 +126,36 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
     active,
     channels,
     targetType,
+    targets,
     method,
+    splitBy,
+    chunkSize,
     showToast,
   ]);
 
+  const uploadMedia = useCallback(
+    async (file: File | undefined) => {
+      if (!file) {
+        setMedia(null);
+        return;
+      }
+      try {
+        setMedia(await api.uploadMedia(file));
+      } catch (error) {
+        setMedia(null);
+        showToast(
+          `Failed to upload the media: ${(error as Error).message}`,
+          'error',
+        );
+      }
+    },
+    [showToast],
+  );
+
   useEffect(() => {
+    saveToStorage('title', title);
+    saveToStorage('websiteName', websiteName);
+    saveToStorage('adInfo', adInfo);
     saveToStorage('promoteUrl', promoteUrl);
     saveToStorage('cpm', cpm);
     saveToStorage('viewsPerUser', viewsPerUser);
@@ -90,6 +164,9 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
     saveToStorage('active', active);
     saveToStorage('method', method);
     saveToStorage('targetType', targetType);
+    saveToStorage('targets', targets);
+    saveToStorage('splitBy', splitBy);
+    saveToStorage('chunkSize', chunkSize);
   }, [
     promoteUrl,
     cpm,
@@ -99,6 +176,12 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
     active,
     method,
     targetType,
+    targets,
+    title,
+    websiteName,
+    adInfo,
+    splitBy,
+    chunkSize,
   ]);
 
   return (
@@ -125,21 +208,69 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
             value='bot'
             checked={targetType === 'bot'}
             onChange={() => setTargetType('bot')}
-            disabled
           />
           <input
-            className={`join-item btn ${targetType === 'bot' ? 'btn-active' : ''}`}
+            className={`join-item btn ${targetType === 'search' ? 'btn-active' : ''}`}
             type='radio'
             name='targetType'
             aria-label='Search'
             value='search'
             checked={targetType === 'search'}
             onChange={() => setTargetType('search')}
-            disabled
           />
         </div>
       </fieldset>
 
+      {targetType === 'channel' && (
+        <div className='flex gap-2'>
+          <label className='floating-label w-full'>
+            <span>Split into ads</span>
+            <select
+              className='select select-bordered w-full'
+              value={splitBy}
+              onChange={(e) =>
+                setSplitBy(e.target.value as '' | 'category' | 'geo' | 'chunk')
+              }
+            >
+              <option value=''>One ad for all channels</option>
+              <option value='category'>One ad per category</option>
+              <option value='geo'>One ad per geo</option>
+              <option value='chunk'>Fixed number of channels per ad</option>
+            </select>
+          </label>
+          {splitBy === 'chunk' && (
+            <label className='floating-label w-40'>
+              <span>Channels per ad</span>
+              <input
+                className='input input-bordered w-full'
+                type='number'
+                min={1}
+                value={chunkSize}
+                onChange={(e) => setChunkSize(Number(e.target.value))}
+              />
+            </label>
+          )}
+        </div>
+      )}
+
+      {targetType !== 'channel' && (
+        <label className='floating-label'>
+          <span>
+            {targetType === 'bot' ? 'Bot usernames' : 'Search queries'}
+          </span>
+          <textarea
+            className='textarea textarea-bordered w-full'
+            placeholder={
+              targetType === 'bot'
+                ? 'One bot username per line'
+                : 'One search query per line'
+            }
+            value={targets}
+            onChange={(e) => setTargets(e.target.value)}
+          />
+        </label>
+      )}
+
       <label className='floating-label'>
         <span>Promote Url</span>
         <div className='flex w-full'>
@@ -156,6 +287,57 @@ export const CreateAdForm: React.FC<CreateAdFormProps> = ({
         </div>
       </label>
 
+      <label className='floating-label'>
+        <span>Title</span>
+        <input
+          type='text'
+          placeholder='Title (defaults to the promote URL and date)'
+          maxLength={40}
+          value={title}
+          onChange={(e) => setTitle(e.target.value)}
+          className='input input-bordered w-full'
+        />
+      </label>
+
+      <label className='floating-label'>
+        <span>Website name</span>
+        <input
+          type='text'
+          placeholder='Website name'
+          maxLength={30}
+          value={websiteName}
+          onChange={(e) => setWebsiteName(e.target.value)}
+          className='input input-bordered w-full'
+        />
+      </label>
+
+      <label className='floating-label'>
+        <span>Ad info</span>
+        <input
+          type='text'
+          placeholder='Ad info'
+          maxLength={100}
+          value={adInfo}
+          onChange={(e) => setAdInfo(e.target.value)}
+          className='input input-bordered w-full'
+        />
+      </label>
+
+      <fieldset className='fieldset'>
+        <legend className='fieldset-legend'>Media</legend>
+        <input
+          type='file'
+          accept='image/jpeg,image/png,image/gif,video/mp4'
+          className='file-input file-input-bordered w-full'
+          onChange={(e) => uploadMedia(e.target.files?.[0])}
+        />
+        {media && (
+          <p className='label'>
+            Uploaded {media.file_name} ({media.id})
+          </p>
+        )}
+      </fieldset>
+
       <label className='floating-label'>
         <span>Cost per 1000 views</span>
         <div className='flex w-full'>
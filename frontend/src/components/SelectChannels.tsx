@@ -41,6 +41,10 @@ export const SelectChannels: React.FC<SelectChannelsProps> = ({
       setChannelsList(data);
       setIsModalOpen(true);
     } catch (error) {
+      if (api.isSessionExpired(error)) {
+        showToast(api.SESSION_EXPIRED_MESSAGE, 'error');
+        return;
+      }
       showToast(
         `Error fetching similar channels: ${(error as Error).message}`,
         'error',
@@ -7,11 +7,17 @@ export interface Channel {
   category: string;
   geo: string;
   subscribers: number;
+  tags: string[];
 }
 
 export interface CreateAdRequest {
+  title?: string;
   text: string;
   promote_url: string;
+  website_name?: string;
+  website_photo?: string;
+  media?: string;
+  ad_info?: string;
   cpm: number;
   views_per_user: number;
   budget: number;
@@ -19,5 +25,39 @@ export interface CreateAdRequest {
   active: boolean;
   target_type: 'channel' | 'search' | 'bot';
   channels: string[];
+  list_id?: number;
+  bots?: string[];
+  search_queries?: string[];
   method: 'draft' | 'save';
+  split_by?: 'category' | 'geo' | 'chunk';
+  chunk_size?: number;
+}
+
+export interface SplitAdResult {
+  group: string;
+  title: string;
+  channels: number[];
+  message?: string;
+  field?: string;
+  error?: string;
+  skipped?: boolean;
+}
+
+export interface CreateAdResponse {
+  status: 'success' | 'partial' | 'failed';
+  message?: string;
+  ads?: SplitAdResult[];
+}
+
+export type TelegramSession =
+  | { status: 'valid' }
+  | { status: 'expired'; error: string }
+  | { status: 'misconfigured'; missing: string[] };
+
+export interface Media {
+  id: string;
+  file_name: string;
+  content_type: string;
+  size: number;
+  uploaded_at: string;
 }